@@ -5,9 +5,10 @@
 use owo_colors::OwoColorize;
 use std::{collections::BTreeMap, sync::Arc};
 
+use dominion::edns::InfoCode;
 use dominion::{DnsHeader, DnsPacket, Flags, Name, ResourceRecord};
 
-type Answers = Arc<BTreeMap<String, String>>;
+type Answers = Arc<BTreeMap<Name<'static>, String>>;
 
 #[derive(Debug)]
 pub(crate) struct AHandler<'a> {
@@ -21,13 +22,12 @@ impl<'me> AHandler<'me> {
     }
 
     pub(crate) fn response<'a>(&self, question: &'a DnsPacket<'a>) -> DnsPacket<'a> {
-        let id = question.header.id;
         let name = question.questions[0].name.clone();
         let Some(text) = self.read_message(&name) else {
-            return super::refused(id);
+            return super::refused(question, InfoCode::NotAuthoritative);
         };
         println!("✉️  {}\n\n\t{text}\n\n", "A".red());
-        let ip = match self.answers.get(&text.to_ascii_lowercase()) {
+        let ip = match self.answers.get(&name.into_owned()) {
             Some(ip) => ip,
             None => "127.0.0.1",
         };
@@ -35,13 +35,12 @@ impl<'me> AHandler<'me> {
     }
 
     pub(crate) fn response_v6<'a>(&self, question: &'a DnsPacket<'a>) -> DnsPacket<'a> {
-        let id = question.header.id;
         let name = question.questions[0].name.clone();
         let Some(text) = self.read_message(&name) else {
-            return super::refused(id);
+            return super::refused(question, InfoCode::NotAuthoritative);
         };
         println!("✉️  {}\n\n\t{text}\n\n", "AAAA".blue());
-        let ip = match self.answers.get(&text.to_ascii_lowercase()) {
+        let ip = match self.answers.get(&name.into_owned()) {
             Some(ip) => ip,
             None => "::1",
         };
@@ -59,7 +58,10 @@ impl<'me> AHandler<'me> {
         let _ = labels
             .nth(self.filter.label_count() - 1)
             .expect("Because it is a subdomain it should have at least one more label");
-        let text: String = labels.rev().collect();
+        let text: String = labels
+            .rev()
+            .map(|l| String::from_utf8_lossy(l.as_bytes()))
+            .collect();
         Some(text)
     }
 }
@@ -89,7 +91,6 @@ fn answer<'a>(question: &'a DnsPacket<'a>, ip: &str) -> DnsPacket<'a> {
         rrtype: dominion::Type::A,
         class: dominion::Class::IN,
         ttl: 0,
-        rdlen: 4,
     };
     let rr = ResourceRecord {
         preamble,
@@ -121,7 +122,6 @@ fn answer_v6<'a>(question: &'a DnsPacket<'a>, ip: &str) -> DnsPacket<'a> {
         rrtype: dominion::Type::Aaaa,
         class: dominion::Class::IN,
         ttl: 0,
-        rdlen: 16,
     };
     let rr = ResourceRecord {
         preamble,
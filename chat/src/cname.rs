@@ -5,9 +5,10 @@
 use owo_colors::OwoColorize;
 use std::{collections::BTreeMap, sync::Arc};
 
+use dominion::edns::InfoCode;
 use dominion::{DnsHeader, DnsPacket, Flags, Name, ResourceRecord};
 
-type Answers = Arc<BTreeMap<String, String>>;
+type Answers = Arc<BTreeMap<Name<'static>, String>>;
 
 #[derive(Debug)]
 pub(crate) struct CnameHandler<'a> {
@@ -21,13 +22,12 @@ impl<'me> CnameHandler<'me> {
     }
 
     pub(crate) fn response<'a>(&self, question: &'a DnsPacket<'a>) -> DnsPacket<'a> {
-        let id = question.header.id;
         let name = &question.questions[0].name;
         let Some(text) = self.read_message(name) else {
-            return super::refused(id);
+            return super::refused(question, InfoCode::NotAuthoritative);
         };
         println!("📋️  {}\n\n\t{text}\n\n", "CNAME".purple());
-        let dname = match self.answers.get(&text.to_ascii_lowercase()) {
+        let dname = match self.answers.get(&name.clone().into_owned()) {
             Some(dname) => dname.to_string(),
             None => self.filter.to_string(),
         };
@@ -45,7 +45,10 @@ impl<'me> CnameHandler<'me> {
         let _ = labels
             .nth(self.filter.label_count() - 1)
             .expect("Because it is a subdomain it should have at least one more label");
-        let text: String = labels.rev().collect();
+        let text: String = labels
+            .rev()
+            .map(|l| String::from_utf8_lossy(l.as_bytes()))
+            .collect();
         Some(text)
     }
 }
@@ -76,7 +79,6 @@ fn answer<'a>(question: &'a DnsPacket<'a>, dname: String) -> DnsPacket<'a> {
         rrtype: dominion::Type::Cname,
         class: dominion::Class::IN,
         ttl: 0,
-        rdlen: dname.size() as _,
     };
     let rr = ResourceRecord {
         preamble,
@@ -4,6 +4,7 @@
 
 #![warn(rust_2018_idioms, missing_debug_implementations)]
 
+use dominion::edns::InfoCode;
 use dominion::{DnsPacket, Name, QType, ServerService};
 use std::{collections::BTreeMap, net::SocketAddr, sync::Arc};
 
@@ -21,8 +22,17 @@ type SMap = BTreeMap<String, String>;
 
 impl<'a> Chat<'a> {
     pub fn new(name: Name<'a>, files: Option<SMap>, answers: SMap) -> Result<Self, &'static str> {
+        // Las respuestas se buscan con el nombre completo de la pregunta
+        let origin = name.clone().into_owned();
+        let mut names = BTreeMap::new();
+        for (k, v) in answers {
+            let mut key = Name::try_from(k).map_err(|_| "could not parse an answer name")?;
+            key.append(&origin)
+                .map_err(|_| "could not parse an answer name")?;
+            names.insert(key, v);
+        }
         let name = Arc::new(name);
-        let answers = Arc::new(answers);
+        let answers = Arc::new(names);
         let a_handler = a::AHandler::new(answers.clone(), name.clone());
         let cname_handler = cname::CnameHandler::new(answers, name.clone());
         let txt_handler = if let Some(files) = files {
@@ -49,15 +59,29 @@ impl ServerService for Chat<'_> {
                     .txt_handler
                     .as_ref()
                     .map(|files| files.response(question)),
-                _ => Some(refused(question.header.id)),
+                _ => Some(refused(question, InfoCode::NotSupported)),
             }
         } else {
-            Some(refused(question.header.id))
+            Some(format_error(question))
         }
     }
 }
 
-fn refused(id: u16) -> DnsPacket<'static> {
+/// Build a REFUSED response, explaining why with an extended DNS error if the client supports EDNS.
+fn refused(question: &DnsPacket<'_>, reason: InfoCode) -> DnsPacket<'static> {
+    let mut packet = empty_response(question, dominion::ResponseCode::Refused);
+    if question.edns().is_some() {
+        packet.add_extended_error(reason, None);
+    }
+    packet
+}
+
+/// Build a FORMERR response for a query that can not be answered, like one without questions.
+fn format_error(question: &DnsPacket<'_>) -> DnsPacket<'static> {
+    empty_response(question, dominion::ResponseCode::FormErr)
+}
+
+fn empty_response(question: &DnsPacket<'_>, rcode: dominion::ResponseCode) -> DnsPacket<'static> {
     use dominion::*;
 
     let flags = Flags {
@@ -70,11 +94,11 @@ fn refused(id: u16) -> DnsPacket<'static> {
         z: Zero::Zero,
         ad: AuthenticData::NotAuthentic,
         cd: CheckingDisabled::Disabled,
-        rcode: ResponseCode::Refused,
+        rcode,
     };
 
     let header = DnsHeader {
-        id,
+        id: question.header.id,
         flags,
         questions: 0,
         answers: 0,
@@ -66,11 +66,8 @@ fn main() {
 
     let chat = match dominion_chat::Chat::new(name, config.files, config.answers) {
         Ok(chat) => chat,
-        Err(_) => {
-            eprintln!(
-                "{}: could not read the files from the configuration file.",
-                "ERROR".red()
-            );
+        Err(e) => {
+            eprintln!("{}: {e} from the configuration file.", "ERROR".red());
             std::process::exit(1)
         }
     };
@@ -2,9 +2,10 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use std::{collections::BTreeMap, path::Path, str, sync::Arc};
+use std::{collections::BTreeMap, path::Path, sync::Arc};
 
-use dominion::{DnsHeader, DnsPacket, Flags, Name, ResourceRecord};
+use dominion::edns::InfoCode;
+use dominion::{DnsHeader, DnsPacket, Flags, Label, Name, ResourceRecord};
 
 use memmap2::Mmap;
 use owo_colors::OwoColorize;
@@ -13,7 +14,7 @@ const MAX_TXT_SIZE: usize = 255;
 
 #[derive(Debug)]
 pub(crate) struct TxtHandler<'a> {
-    files: BTreeMap<String, Mmap>,
+    files: BTreeMap<Label<'static>, Mmap>,
     filter: Arc<Name<'a>>,
 }
 
@@ -30,6 +31,7 @@ impl<'me> TxtHandler<'me> {
             // SAFETY: Because we copy the bytes from the appropiate part of the file
             // before we use them, a change in the underlying file will not produce UB
             let v = unsafe { Mmap::map(&fd).map_err(|_| "could not read the file")? };
+            let k = Label::try_from(k).map_err(|_| "could not parse a file name")?;
             files.insert(k, v);
         }
         Ok(Self { files, filter })
@@ -41,7 +43,7 @@ impl<'me> TxtHandler<'me> {
 
         // Si no es un subdominio no es una petición nuestra
         if !self.filter.is_subdomain(name) {
-            return super::refused(id);
+            return super::refused(question, InfoCode::NotAuthoritative);
         }
 
         // Obtenemos la clave del subdominio
@@ -53,12 +55,9 @@ impl<'me> TxtHandler<'me> {
 
         // Si no podemos leer el cacho es que algo ha ido mal y rechazamos
         // la solicitud.
-        let Some(chunk) = self.read_chunk(label) else {
-            return super::refused(id);
+        let Some(chunk) = self.read_chunk(label.as_bytes()) else {
+            return super::refused(question, InfoCode::Other);
         };
-        let chunk = str::from_utf8(chunk)
-            .expect("files can only be text")
-            .to_string();
 
         let header = DnsHeader {
             id,
@@ -71,33 +70,37 @@ impl<'me> TxtHandler<'me> {
         DnsPacket {
             header,
             questions: question.questions.clone(),
-            answers: vec![answer(name, chunk)],
+            answers: vec![answer(name, chunk.to_vec())],
             authority: vec![],
             additional: vec![],
         }
     }
 
-    fn read_chunk<'a>(&'a self, key: &str) -> Option<&'a [u8]> {
+    fn read_chunk<'a>(&'a self, key: &[u8]) -> Option<&'a [u8]> {
         use std::cmp::min;
         // Key format <fileid>-<position> for example:
         //     file1-3
         // If there is no position we asume initial position
-        let (file, i) = match key.rsplit_once('-') {
+        let (file, i) = match key.iter().rposition(|&b| b == b'-') {
             None => (key, 0),
-            Some(key_i) => (key_i.0, key_i.1.parse().ok()?),
+            Some(sep) => (
+                &key[..sep],
+                std::str::from_utf8(&key[sep + 1..]).ok()?.parse().ok()?,
+            ),
         };
 
         // Si lo mandamos cifrado y codificado el tamaño será más
         // grande por lo que este código debería cambiar. Se limita
         // a mandar respuestas en claro o cifradas de forma externa.
-        let map = self.files.get(&file.to_ascii_lowercase())?;
+        let file = Label::try_from(file.to_vec()).ok()?;
+        let map = self.files.get(&file)?;
         let i = i * MAX_TXT_SIZE;
         let j = min(map.len(), i + MAX_TXT_SIZE);
         map.get(i..j)
     }
 }
 
-fn log(label: &'_ str) {
+fn log(label: &Label<'_>) {
     println!("🗒️ {} Asked for {label}\n\n", "TXT".green());
 }
 
@@ -118,18 +121,13 @@ fn flags() -> Flags {
     }
 }
 
-fn answer<'a>(name: &Name<'a>, txt: String) -> ResourceRecord<'a> {
+fn answer<'a>(name: &Name<'a>, txt: Vec<u8>) -> ResourceRecord<'a> {
     use dominion::RecordPreamble;
-    let txt_len: u16 = txt
-        .len()
-        .try_into()
-        .expect("TXT message max length is 255 bytes");
     let preamble = RecordPreamble {
         name: name.clone(),
         rrtype: dominion::Type::Txt,
         class: dominion::Class::IN,
         ttl: 0,
-        rdlen: 1u16 + txt_len,
     };
     ResourceRecord {
         preamble,
@@ -40,10 +40,19 @@ use std::{
     net::{SocketAddr, UdpSocket},
 };
 
+#[cfg(feature = "tsig")]
+use std::time::SystemTime;
+
 pub use dominion_parser::body::name::*;
 pub use dominion_parser::body::*;
 pub use dominion_parser::header::*;
 pub use dominion_parser::*;
+// Both the root and the body of the parser have a `tsig` module, the root one signs messages.
+#[cfg(feature = "tsig")]
+pub use dominion_parser::tsig;
+
+/// The maximum payload of a UDP datagram, responses are serialized into a buffer of this size.
+const MAX_UDP_SIZE: usize = 65507;
 
 /// A DNS service, it recieves a [DnsPacket] as a question and it has to return anotherone as a response.
 ///
@@ -60,6 +69,31 @@ pub use dominion_parser::*;
 pub trait ServerService {
     /// Take a [DnsPacket] as an question and return the response to be sent to the client.
     fn run<'a>(&self, client: SocketAddr, question: &'a DnsPacket<'a>) -> Option<DnsPacket<'a>>;
+
+    /// The TSIG keys accepted by the service (requires the `tsig` feature).
+    ///
+    /// Requests signed with TSIG are verified before calling [ServerService::run], and the
+    /// responses are signed with the same key. Requests that fail verification are answered
+    /// with the error without calling [ServerService::run], so a signed question that reaches
+    /// it has been authenticated. Unsigned requests are passed as they are.
+    #[cfg(feature = "tsig")]
+    fn keys(&self) -> Option<&tsig::KeyStore<'_>> {
+        None
+    }
+
+    /// Called when a packet from `client` can not be parsed, with the location of the error in
+    /// the packet. The returned packet, if any, is sent to the client.
+    ///
+    /// By default the packet is ignored. A service can use it to log misbehaving clients, or to
+    /// answer with [ResponseCode::FormErr] using the header in [PacketError::partial].
+    fn parse_error<'a>(
+        &self,
+        client: SocketAddr,
+        error: &'a PacketError<'a>,
+    ) -> Option<DnsPacket<'a>> {
+        let _ = (client, error);
+        None
+    }
 }
 
 #[doc(hidden)]
@@ -113,7 +147,9 @@ impl Server<Builder> {
 impl Server<Runner> {
     /// Run the [ServerService] in the thread-pool.
     ///
-    /// If an error is encountered when parsing the [DnsPacket] the error is silently droped.
+    /// If an error is encountered when parsing the [DnsPacket] it is passed to
+    /// [ServerService::parse_error], and the packet is dropped unless the service returns a
+    /// response for it.
     pub fn serve<T>(self, srv: T)
     where
         T: ServerService + Sync,
@@ -130,23 +166,154 @@ impl Server<Runner> {
 
     fn serve_sth(&self, srv: &impl ServerService) -> Result<(), std::io::Error> {
         let mut buff = [0; 512];
+        // The response buffer is allocated once per thread and reused for every response.
+        let mut out = vec![0; MAX_UDP_SIZE];
         loop {
             let (n, src) = self
                 .socket
                 .as_ref()
                 .expect("Runners can only be created with a active socket")
                 .recv_from(&mut buff)?;
-            let packet = match DnsPacket::try_from(&buff[..n]) {
+            let packet = match DnsPacket::parse_with(&buff[..n], &ParseOptions::default()) {
                 Ok(packet) => packet,
-                Err(_) => continue,
+                Err(error) => {
+                    if let Some(res) = srv.parse_error(src, &error) {
+                        self.send(&res, src, &mut out)?;
+                    }
+                    continue;
+                }
+            };
+            #[cfg(feature = "tsig")]
+            let signer = match srv
+                .keys()
+                .map(|keys| (keys, keys.verify(&buff[..n], SystemTime::now())))
+            {
+                Some((_, Ok(signer))) => signer,
+                Some((keys, Err(error))) => {
+                    let res = keys.error_response(&packet, &error, SystemTime::now());
+                    self.send(&res, src, &mut out)?;
+                    continue;
+                }
+                None => None,
             };
-            if let Some(res) = srv.run(src, &packet) {
-                let serialized = Vec::<u8>::from(&res);
-                self.socket
-                    .as_ref()
-                    .expect("Runners can only be created with a active socket")
-                    .send_to(&serialized[..], src)?;
+            #[cfg_attr(not(feature = "tsig"), allow(unused_mut))]
+            if let Some(mut res) = srv.run(src, &packet) {
+                #[cfg(feature = "tsig")]
+                if let Some((key, mac)) = &signer {
+                    key.sign(&mut res, Some(mac), SystemTime::now());
+                }
+                self.send(&res, src, &mut out)?;
             };
         }
     }
+
+    /// Send the response to `src`. A response that can not be serialized, for example because
+    /// it does not fit in a UDP datagram, is replaced by a SERVFAIL with only the questions so
+    /// a single bad response does not stop the thread.
+    fn send(
+        &self,
+        res: &DnsPacket<'_>,
+        src: SocketAddr,
+        buff: &mut [u8],
+    ) -> Result<(), std::io::Error> {
+        let n = match res.serialize_into(buff) {
+            Ok(n) => n,
+            Err(_) => match server_failure(res).serialize_into(buff) {
+                Ok(n) => n,
+                Err(_) => return Ok(()),
+            },
+        };
+        self.socket
+            .as_ref()
+            .expect("Runners can only be created with a active socket")
+            .send_to(&buff[..n], src)?;
+        Ok(())
+    }
+}
+
+/// Build a SERVFAIL response with the same ID, flags and questions as `res`.
+fn server_failure<'a>(res: &DnsPacket<'a>) -> DnsPacket<'a> {
+    let header = DnsHeader {
+        id: res.header.id,
+        flags: Flags {
+            rcode: ResponseCode::ServFail,
+            ..res.header.flags
+        },
+        questions: res.header.questions,
+        answers: 0,
+        authority: 0,
+        additional: 0,
+    };
+    DnsPacket {
+        header,
+        questions: res.questions.clone(),
+        answers: Vec::new(),
+        authority: Vec::new(),
+        additional: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// Answers `big.` with TXT records that do not fit in a UDP datagram, and any other
+    /// question with an empty response.
+    struct Oversized;
+
+    impl ServerService for Oversized {
+        fn run<'a>(
+            &self,
+            _client: SocketAddr,
+            question: &'a DnsPacket<'a>,
+        ) -> Option<DnsPacket<'a>> {
+            let mut res = question.clone();
+            res.header.flags.qr = QueryResponse::Response;
+            if question.questions[0].name == Name::try_from("big").unwrap() {
+                let preamble = RecordPreamble {
+                    name: question.questions[0].name.clone(),
+                    rrtype: Type::Txt,
+                    class: Class::IN,
+                    ttl: 0,
+                };
+                for _ in 0..2 {
+                    let data = RecordData::Txt(vec![b'a'; MAX_UDP_SIZE / 2].into());
+                    res.answers.push(ResourceRecord {
+                        preamble: preamble.clone(),
+                        data,
+                    });
+                }
+            }
+            Some(res)
+        }
+    }
+
+    fn query(client: &UdpSocket, name: &str) -> ResponseCode {
+        let mut packet =
+            DnsPacket::try_from(&include_bytes!("../../parser/assets/dns_request.bin")[..])
+                .unwrap();
+        packet.questions[0].name = Name::try_from(name).unwrap();
+        client.send(&Vec::from(&packet)).unwrap();
+        let mut buff = [0; 512];
+        let n = client.recv(&mut buff).unwrap();
+        DnsPacket::try_from(&buff[..n]).unwrap().header.flags.rcode
+    }
+
+    #[test]
+    fn oversized_response() {
+        let server = Server::default()
+            .bind("127.0.0.1:0".parse().unwrap())
+            .unwrap();
+        let addr = server.socket.as_ref().unwrap().local_addr().unwrap();
+        std::thread::spawn(move || server.serve(Oversized));
+
+        let client = UdpSocket::bind("127.0.0.1:0").unwrap();
+        client
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        client.connect(addr).unwrap();
+        assert_eq!(query(&client, "big"), ResponseCode::ServFail);
+        assert_eq!(query(&client, "small"), ResponseCode::NoError);
+    }
 }
@@ -2,8 +2,9 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use criterion::{Criterion, black_box, criterion_group, criterion_main};
 use dominion_parser::DnsPacket;
+use dominion_parser::view::DnsPacketView;
 
 use pprof::criterion::{Output, PProfProfiler};
 
@@ -30,12 +31,37 @@ pub fn parse_response(c: &mut Criterion) {
     });
 }
 
+/// Read the name of the first question, which is usually all a filter needs.
+fn first_question(view: &DnsPacketView<'_>) -> usize {
+    view.questions()
+        .next()
+        .map_or(0, |q| q.name().labels().count())
+}
+
+pub fn view_long_request(c: &mut Criterion) {
+    c.bench_function("view_longreq", |b| {
+        b.iter(|| first_question(&DnsPacketView::try_from(black_box(&LONG_REQ[..])).unwrap()))
+    });
+}
+
+pub fn view_request(c: &mut Criterion) {
+    c.bench_function("view_req", |b| {
+        b.iter(|| first_question(&DnsPacketView::try_from(black_box(&REQ[..])).unwrap()))
+    });
+}
+
+pub fn view_response(c: &mut Criterion) {
+    c.bench_function("view_res", |b| {
+        b.iter(|| first_question(&DnsPacketView::try_from(black_box(&RES[..])).unwrap()))
+    });
+}
+
 criterion_group!(
     name = parse;
     config = Criterion::default()
             .with_profiler(
                 PProfProfiler::new(100, Output::Flamegraph(None))
             );
-    targets = parse_request, parse_long_request, parse_response
+    targets = parse_request, parse_long_request, parse_response, view_request, view_long_request, view_response
 );
 criterion_main!(parse);
@@ -4,9 +4,9 @@
 
 use pretty_hex::pretty_hex;
 
+use dominion_parser::DnsPacket;
 use dominion_parser::body::{RecordData, RecordPreamble, ResourceRecord};
 use dominion_parser::header::{AuthenticData, QueryResponse, RecursionAvailable};
-use dominion_parser::DnsPacket;
 
 const REQ: &[u8; 33] = include_bytes!("../assets/dns_request.bin");
 const RES: &[u8; 49] = include_bytes!("../assets/dns_response.bin");
@@ -28,11 +28,9 @@ fn main() {
             .expect("QType is not a valid Type"),
         class: res.questions[0].class,
         ttl: 300,
-        rdlen: 4,
     };
     let data = RecordData::A("204.74.99.100".parse().unwrap());
     let answer = ResourceRecord { preamble, data };
-    res.header.answers = 1;
     res.answers.push(answer);
 
     let res = Vec::<u8>::from(&res);
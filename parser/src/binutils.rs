@@ -3,7 +3,8 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use crate::ParseError;
-use std::net::{Ipv4Addr, Ipv6Addr};
+use crate::writer::Writer;
+use core::net::{Ipv4Addr, Ipv6Addr};
 
 #[inline]
 fn safe_read<const N: usize>(buff: &[u8], pos: usize) -> Result<[u8; N], ParseError> {
@@ -28,12 +29,24 @@ pub(crate) fn safe_u16_read(buff: &[u8], pos: usize) -> Result<u16, ParseError>
     Ok(u16::from_be_bytes(bytes))
 }
 
+#[inline]
+pub(crate) fn safe_u32_read(buff: &[u8], pos: usize) -> Result<u32, ParseError> {
+    let bytes = safe_read::<4>(buff, pos)?;
+    Ok(u32::from_be_bytes(bytes))
+}
+
 #[inline]
 pub(crate) fn safe_i32_read(buff: &[u8], pos: usize) -> Result<i32, ParseError> {
     let bytes = safe_read::<4>(buff, pos)?;
     Ok(i32::from_be_bytes(bytes))
 }
 
+#[inline]
+pub(crate) fn safe_slice_read(buff: &[u8], pos: usize, len: usize) -> Result<&[u8], ParseError> {
+    buff.get(pos..pos + len)
+        .ok_or(ParseError::OobRead(pos + len))
+}
+
 #[inline]
 pub(crate) fn safe_ipv4_read(buff: &[u8], pos: usize) -> Result<Ipv4Addr, ParseError> {
     let bytes = safe_read::<4>(buff, pos)?;
@@ -47,11 +60,16 @@ pub(crate) fn safe_ipv6_read(buff: &[u8], pos: usize) -> Result<Ipv6Addr, ParseE
 }
 
 #[inline]
-pub(crate) fn push_u16(target: &mut Vec<u8>, n: u16) {
-    target.extend(n.to_be_bytes());
+pub(crate) fn push_u16<W: Writer + ?Sized>(target: &mut W, n: u16) {
+    target.put(&n.to_be_bytes());
+}
+
+#[inline]
+pub(crate) fn push_u32<W: Writer + ?Sized>(target: &mut W, n: u32) {
+    target.put(&n.to_be_bytes());
 }
 
 #[inline]
-pub(crate) fn push_i32(target: &mut Vec<u8>, n: i32) {
-    target.extend(n.to_be_bytes());
+pub(crate) fn push_i32<W: Writer + ?Sized>(target: &mut W, n: i32) {
+    target.put(&n.to_be_bytes());
 }
@@ -2,18 +2,30 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
+/// DNSSEC record data (RFC 4034 and RFC 5155)
+pub mod dnssec;
 /// Domain name structure and funtions
 pub mod name;
+/// Transaction signature record data (RFC 8945)
+pub mod tsig;
 
-use crate::ParseError;
 use crate::binutils::*;
-use crate::body::name::Name;
-use std::borrow::Cow;
-use std::net::{Ipv4Addr, Ipv6Addr};
-use std::str;
+use crate::body::dnssec::{Dnskey, Ds, Nsec, Nsec3, Nsec3Param, Rrsig};
+use crate::body::name::{Compressor, Name};
+use crate::body::tsig::Tsig;
+use crate::edns::EdnsOption;
+use crate::writer::{Counter, Writer, checked_u16, patch_len};
+use crate::{ParseError, ParseOptions};
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::net::{Ipv4Addr, Ipv6Addr};
+use core::str;
 
 const INIT_RR_SIZE: usize = 64;
 
+const MAX_CHARACTER_STRING: usize = 255;
+
 macro_rules! types {
     (
         $(
@@ -72,6 +84,14 @@ macro_rules! types {
                 #[$inner]
                 $variant,
             )*
+            /// Incremental zone transfer (RFC 1995)
+            Ixfr,
+            /// Full zone transfer (RFC 5936)
+            Axfr,
+            /// Mailbox-related records (MB, MG or MR)
+            Mailb,
+            /// Mail agent records (obsolete, see MX)
+            Maila,
             /// All types
             All,
             /// ?: A value has been received that does not correspond to any known qtype.
@@ -93,6 +113,10 @@ macro_rules! types {
             fn from(value: u16) -> Self {
                 match value {
                     $($value => Self::$variant,)*
+                    251 => Self::Ixfr,
+                    252 => Self::Axfr,
+                    253 => Self::Mailb,
+                    254 => Self::Maila,
                     255 => Self::All,
                     _ => Self::Unknown(value),
                 }
@@ -104,6 +128,10 @@ macro_rules! types {
             fn from(value: QType) -> Self {
                 match value {
                     $(QType::$variant => $value,)*
+                    QType::Ixfr => 251,
+                    QType::Axfr => 252,
+                    QType::Mailb => 253,
+                    QType::Maila => 254,
                     QType::All => 255,
                     QType::Unknown(n) => n,
                 }
@@ -155,7 +183,18 @@ impl<'a> Question<'a> {
     /// the DNS packet to be considered valid.
     #[inline]
     pub fn parse(buff: &'a [u8], start: usize) -> Result<(Self, usize), crate::ParseError> {
-        let (name, size) = Name::parse(buff, start)?;
+        Self::parse_with(buff, start, &ParseOptions::default())
+    }
+
+    /// Parse from the specified `buff`, starting at position `start`, with the given
+    /// [ParseOptions].
+    #[inline]
+    pub fn parse_with(
+        buff: &'a [u8],
+        start: usize,
+        options: &ParseOptions,
+    ) -> Result<(Self, usize), ParseError> {
+        let (name, size) = Name::parse_with(buff, start, options)?;
         let n = start + size;
         Ok((
             Question {
@@ -169,8 +208,19 @@ impl<'a> Question<'a> {
 
     /// Serialize the [Question] and append it tho the end of the provided `packet`
     #[inline]
-    pub fn serialize(&self, packet: &mut Vec<u8>) {
-        self.name.serialize(packet);
+    pub fn serialize<W: Writer + ?Sized>(&self, packet: &mut W) {
+        self.serialize_compressed(packet, &mut Compressor::disabled());
+    }
+
+    /// Serialize the [Question] and append it tho the end of the provided `packet`, compressing
+    /// the domain name with the provided [Compressor].
+    #[inline]
+    pub fn serialize_compressed<W: Writer + ?Sized>(
+        &self,
+        packet: &mut W,
+        compressor: &mut Compressor,
+    ) {
+        self.name.serialize_compressed(packet, compressor);
         push_u16(packet, self.qtype.into());
         push_u16(packet, self.class.into());
     }
@@ -221,20 +271,74 @@ impl<'a> ResourceRecord<'a> {
     /// Parse from the specified `buff`, starting at position `pos`.
     #[inline]
     pub fn parse(buff: &'a [u8], pos: usize) -> Result<(Self, usize), ParseError> {
-        let (preamble, size) = RecordPreamble::parse(buff, pos)?;
-        let (data, len) = RecordData::parse(buff, pos + size, &preamble)?;
-        Ok((Self { preamble, data }, size + len))
+        Self::parse_with(buff, pos, &ParseOptions::default())
+    }
+
+    /// Parse from the specified `buff`, starting at position `pos`, with the given [ParseOptions].
+    #[inline]
+    pub fn parse_with(
+        buff: &'a [u8],
+        pos: usize,
+        options: &ParseOptions,
+    ) -> Result<(Self, usize), ParseError> {
+        let (preamble, rdlen, size) = RecordPreamble::parse(buff, pos, options)?;
+        let end = pos + size + rdlen as usize;
+        if buff.len() < end {
+            Err(ParseError::OobRead(end))?
+        }
+        // RFC 2136: records of class ANY or NONE can have empty data whatever their type.
+        let data = if rdlen == 0
+            && matches!(preamble.class, Class::Any | Class::None)
+            && preamble.rrtype != Type::Opt
+        {
+            RecordData::Empty
+        } else {
+            RecordData::parse(buff, pos + size, rdlen, preamble.rrtype, options)?
+        };
+        Ok((Self { preamble, data }, size + rdlen as usize))
     }
 
     /// Serialize the [ResourceRecord] and append it tho the end of the provided `packet`
     #[inline]
-    pub fn serialize(&self, packet: &mut Vec<u8>) {
-        self.preamble.serialize(packet);
-        self.data.serialize(packet);
+    pub fn serialize<W: Writer + ?Sized>(&self, packet: &mut W) {
+        self.serialize_compressed(packet, &mut Compressor::disabled());
+    }
+
+    /// Serialize the [ResourceRecord] and append it tho the end of the provided `packet`,
+    /// compressing the domain names with the provided [Compressor].
+    ///
+    /// Only the owner name and the domain names inside the RDATA of the record types defined
+    /// in RFC 1035 are compressed. The RDLENGTH field is computed from the serialized RDATA.
+    #[inline]
+    pub fn serialize_compressed<W: Writer + ?Sized>(
+        &self,
+        packet: &mut W,
+        compressor: &mut Compressor,
+    ) {
+        self.preamble.serialize(packet, compressor);
+        if packet.written_mut().is_some() {
+            // Placeholder for the RDLENGTH, it is written once we know the size of the RDATA.
+            push_u16(packet, 0);
+            let start = packet.position();
+            self.data.serialize(packet, compressor);
+            patch_len(packet, start);
+        } else {
+            // The written data can not be modified, so the RDATA is measured first. Names are
+            // not compressed in this case as the written data can not be read either.
+            let mut counter = Counter::default();
+            self.data
+                .serialize(&mut counter, &mut Compressor::disabled());
+            let len = checked_u16(packet, counter.position());
+            push_u16(packet, len);
+            self.data.serialize(packet, compressor);
+        }
     }
 }
 
 /// The [ResourceRecord] preamble. Common data to all resource record types.
+///
+/// The length of the RR data is not stored, it is computed from the [RecordData] when
+/// the [ResourceRecord] is serialized.
 #[derive(Debug, Clone)]
 pub struct RecordPreamble<'a> {
     /// The domain name the RR refers to.
@@ -245,14 +349,17 @@ pub struct RecordPreamble<'a> {
     pub class: Class,
     /// The time interval that the resource record may be cached before the source of the information should again be consulted.
     pub ttl: i32,
-    /// The length of the RR data.
-    pub rdlen: u16,
 }
 
 impl<'a> RecordPreamble<'a> {
+    /// Parse the preamble, returning it together with the length of the RR data.
     #[inline]
-    fn parse(buff: &'a [u8], pos: usize) -> Result<(Self, usize), ParseError> {
-        let (name, size) = Name::parse(buff, pos)?;
+    fn parse(
+        buff: &'a [u8],
+        pos: usize,
+        options: &ParseOptions,
+    ) -> Result<(Self, u16, usize), ParseError> {
+        let (name, size) = Name::parse_with(buff, pos, options)?;
         let n = size + pos;
         Ok((
             RecordPreamble {
@@ -260,19 +367,19 @@ impl<'a> RecordPreamble<'a> {
                 rrtype: safe_u16_read(buff, n)?.into(),
                 class: safe_u16_read(buff, n + 2)?.into(),
                 ttl: safe_i32_read(buff, n + 4)?,
-                rdlen: safe_u16_read(buff, n + 8)?,
             },
+            safe_u16_read(buff, n + 8)?,
             size + 10,
         ))
     }
 
+    /// Serialize the preamble without the RDLENGTH field.
     #[inline]
-    fn serialize(&self, packet: &mut Vec<u8>) {
-        self.name.serialize(packet);
+    fn serialize<W: Writer + ?Sized>(&self, packet: &mut W, compressor: &mut Compressor) {
+        self.name.serialize_compressed(packet, compressor);
         push_u16(packet, self.rrtype.into());
         push_u16(packet, self.class.into());
         push_i32(packet, self.ttl);
-        push_u16(packet, self.rdlen);
     }
 }
 
@@ -286,6 +393,25 @@ pub enum RecordData<'a> {
     Ns(Name<'a>),
     /// The canonical name for an alias.
     Cname(Name<'a>),
+    /// Marks the start of a zone of authority.
+    Soa {
+        /// The name server that was the original or primary source of data for this zone.
+        mname: Name<'a>,
+        /// The mailbox of the person responsible for this zone.
+        rname: Name<'a>,
+        /// The version number of the original copy of the zone.
+        serial: u32,
+        /// Time interval before the zone should be refreshed.
+        refresh: u32,
+        /// Time interval that should elapse before a failed refresh should be retried.
+        retry: u32,
+        /// Time value that specifies the upper limit on the time interval that can elapse before the zone is no longer authoritative.
+        expire: u32,
+        /// The TTL to be used for negative responses.
+        minimum: u32,
+    },
+    /// A domain name pointer.
+    Ptr(Name<'a>),
     /// Mail exchange.
     Mx {
         /// The preference given to this RR among others at the same owner.
@@ -294,81 +420,387 @@ pub enum RecordData<'a> {
         exchange: Name<'a>,
     },
     /// Text strings
-    Txt(Cow<'a, str>),
+    Txt(Txt<'a>),
     /// A host address IPv6
     Aaaa(Ipv6Addr),
+    /// The location of the server(s) for a specific protocol and domain.
+    Srv {
+        /// The priority of this target host, lower values are preferred.
+        priority: u16,
+        /// A server selection mechanism for entries with the same priority.
+        weight: u16,
+        /// The port on this target host of this service.
+        port: u16,
+        /// The domain name of the target host.
+        target: Name<'a>,
+    },
+    /// The options of an EDNS(0) OPT pseudo-record, see [Edns](crate::edns::Edns).
+    Opt(Vec<EdnsOption<'a>>),
+    /// Delegation signer.
+    Ds(Ds<'a>),
+    /// Signature over a RRset.
+    Rrsig(Rrsig<'a>),
+    /// Next secure record.
+    Nsec(Nsec<'a>),
+    /// Public key of a zone.
+    Dnskey(Dnskey<'a>),
+    /// Hashed next secure record.
+    Nsec3(Nsec3<'a>),
+    /// Parameters of the NSEC3 chain of a zone.
+    Nsec3Param(Nsec3Param<'a>),
+    /// Transaction signature.
+    Tsig(Tsig<'a>),
+    /// No data, used by the records of class ANY or NONE in UPDATE messages (RFC 2136).
+    Empty,
     /// ?: A value has been received that does not correspond to any known type.
     Unknown(Cow<'a, [u8]>),
 }
 
 impl<'a> RecordData<'a> {
+    /// Parse the RR data of type `rrtype` and length `rdlen` starting at position `pos`.
+    ///
+    /// It will error if the data does not have exactly `rdlen` bytes, so the next record is not
+    /// read from the middle of this one.
     #[inline]
     fn parse(
         buff: &'a [u8],
         pos: usize,
-        rrpreamble: &RecordPreamble<'_>,
-    ) -> Result<(Self, usize), ParseError> {
-        match rrpreamble.rrtype {
-            Type::A => Ok((Self::A(safe_ipv4_read(buff, pos)?), 4)),
+        rdlen: u16,
+        rrtype: Type,
+        options: &ParseOptions,
+    ) -> Result<Self, ParseError> {
+        let rdlen_usize = rdlen as usize;
+        let (data, len) = match rrtype {
+            Type::A => (Self::A(safe_ipv4_read(buff, pos)?), 4),
             Type::Ns => {
-                let (name, n) = Name::parse(buff, pos)?;
-                Ok((Self::Ns(name), n))
+                let (name, n) = Name::parse_with(buff, pos, options)?;
+                (Self::Ns(name), n)
             }
             Type::Cname => {
-                let (name, n) = Name::parse(buff, pos)?;
-                Ok((Self::Cname(name), n))
+                let (name, n) = Name::parse_with(buff, pos, options)?;
+                (Self::Cname(name), n)
+            }
+            Type::Soa => {
+                let (mname, n) = Name::parse_with(buff, pos, options)?;
+                let (rname, m) = Name::parse_with(buff, pos + n, options)?;
+                let pos = pos + n + m;
+                let soa = Self::Soa {
+                    mname,
+                    rname,
+                    serial: safe_u32_read(buff, pos)?,
+                    refresh: safe_u32_read(buff, pos + 4)?,
+                    retry: safe_u32_read(buff, pos + 8)?,
+                    expire: safe_u32_read(buff, pos + 12)?,
+                    minimum: safe_u32_read(buff, pos + 16)?,
+                };
+                (soa, n + m + 20)
+            }
+            Type::Ptr => {
+                let (name, n) = Name::parse_with(buff, pos, options)?;
+                (Self::Ptr(name), n)
             }
             Type::Mx => {
-                let (exchange, n) = Name::parse(buff, pos + 2)?;
-                Ok((
-                    Self::Mx {
-                        preference: safe_u16_read(buff, pos)?,
-                        exchange,
-                    },
-                    n + 2,
-                ))
+                let (exchange, n) = Name::parse_with(buff, pos + 2, options)?;
+                let mx = Self::Mx {
+                    preference: safe_u16_read(buff, pos)?,
+                    exchange,
+                };
+                (mx, n + 2)
             }
-            Type::Txt => {
-                let len = safe_u8_read(buff, pos)?;
-                let str_bytes = str::from_utf8(&buff[pos..pos + len as usize])?;
-                Ok((Self::Txt(Cow::from(str_bytes)), len as _))
+            Type::Txt => (Self::Txt(Txt::parse(buff, pos, rdlen)?), rdlen_usize),
+            Type::Aaaa => (Self::Aaaa(safe_ipv6_read(buff, pos)?), 16),
+            Type::Srv => {
+                let (target, n) = Name::parse_with(buff, pos + 6, options)?;
+                let srv = Self::Srv {
+                    priority: safe_u16_read(buff, pos)?,
+                    weight: safe_u16_read(buff, pos + 2)?,
+                    port: safe_u16_read(buff, pos + 4)?,
+                    target,
+                };
+                (srv, n + 6)
             }
-            Type::Aaaa => Ok((Self::Aaaa(safe_ipv6_read(buff, pos)?), 16)),
+            Type::Opt => (
+                Self::Opt(EdnsOption::parse_all(buff, pos, rdlen)?),
+                rdlen_usize,
+            ),
+            Type::Ds => (Self::Ds(Ds::parse(buff, pos, rdlen)?), rdlen_usize),
+            Type::Rrsig => (
+                Self::Rrsig(Rrsig::parse(buff, pos, rdlen, options)?),
+                rdlen_usize,
+            ),
+            Type::Nsec => (
+                Self::Nsec(Nsec::parse(buff, pos, rdlen, options)?),
+                rdlen_usize,
+            ),
+            Type::Dnskey => (Self::Dnskey(Dnskey::parse(buff, pos, rdlen)?), rdlen_usize),
+            Type::Nsec3 => (Self::Nsec3(Nsec3::parse(buff, pos, rdlen)?), rdlen_usize),
+            Type::Nsec3Param => (
+                Self::Nsec3Param(Nsec3Param::parse(buff, pos, rdlen)?),
+                rdlen_usize,
+            ),
+            Type::Tsig => (
+                Self::Tsig(Tsig::parse(buff, pos, rdlen, options)?),
+                rdlen_usize,
+            ),
             Type::Unknown(_) => {
-                let len = rrpreamble.rdlen as _;
-                let end = pos + len;
+                let end = pos + rdlen_usize;
                 if buff.len() < end {
                     Err(ParseError::OobRead(end))?
                 }
                 let cow_bytes = Cow::from(&buff[pos..end]);
-                Ok((Self::Unknown(cow_bytes), len))
+                (Self::Unknown(cow_bytes), rdlen_usize)
             }
+        };
+        if len != rdlen_usize {
+            Err(ParseError::RdataLength(rdlen, len))?
         }
+        Ok(data)
     }
 
     #[inline]
-    fn serialize(&self, packet: &mut Vec<u8>) {
-        use std::ops::Deref;
+    fn serialize<W: Writer + ?Sized>(&self, packet: &mut W, compressor: &mut Compressor) {
+        use core::ops::Deref;
+        match self {
+            Self::A(ip) => packet.put(&ip.octets()),
+            Self::Ns(name) => name.serialize_compressed(packet, compressor),
+            Self::Cname(name) => name.serialize_compressed(packet, compressor),
+            Self::Soa {
+                mname,
+                rname,
+                serial,
+                refresh,
+                retry,
+                expire,
+                minimum,
+            } => {
+                mname.serialize_compressed(packet, compressor);
+                rname.serialize_compressed(packet, compressor);
+                push_u32(packet, *serial);
+                push_u32(packet, *refresh);
+                push_u32(packet, *retry);
+                push_u32(packet, *expire);
+                push_u32(packet, *minimum);
+            }
+            Self::Ptr(name) => name.serialize_compressed(packet, compressor),
+            Self::Mx {
+                preference,
+                exchange,
+            } => {
+                push_u16(packet, *preference);
+                exchange.serialize_compressed(packet, compressor);
+            }
+            Self::Txt(txt) => txt.serialize(packet),
+            Self::Aaaa(ip) => packet.put(&ip.octets()),
+            Self::Srv {
+                priority,
+                weight,
+                port,
+                target,
+            } => {
+                push_u16(packet, *priority);
+                push_u16(packet, *weight);
+                push_u16(packet, *port);
+                // RFC 2782: name compression is not to be used for this field.
+                target.serialize(packet);
+            }
+            Self::Opt(options) => {
+                for option in options {
+                    option.serialize(packet);
+                }
+            }
+            Self::Ds(ds) => ds.serialize(packet),
+            Self::Rrsig(rrsig) => rrsig.serialize(packet),
+            Self::Nsec(nsec) => nsec.serialize(packet),
+            Self::Dnskey(dnskey) => dnskey.serialize(packet),
+            Self::Nsec3(nsec3) => nsec3.serialize(packet),
+            Self::Nsec3Param(param) => param.serialize(packet),
+            Self::Tsig(tsig) => tsig.serialize(packet),
+            Self::Empty => (),
+            Self::Unknown(buff) => packet.put(buff.deref()),
+        }
+    }
+
+    /// Serialize the data in its canonical form (RFC 4034 section 6.2 and RFC 6840 section
+    /// 5.1), where domain names are not compressed and are in lowercase for the types that
+    /// require it.
+    ///
+    /// ```
+    /// # use dominion_parser::body::RecordData;
+    /// let data = RecordData::Cname("WWW.Example.com".try_into().unwrap());
+    /// let mut rdata = Vec::new();
+    /// data.serialize_canonical(&mut rdata);
+    /// assert_eq!(&rdata[..], b"\x03www\x07example\x03com\x00");
+    /// ```
+    pub fn serialize_canonical<W: Writer + ?Sized>(&self, packet: &mut W) {
         match self {
-            Self::A(ip) => packet.extend(ip.octets()),
-            Self::Ns(name) => name.serialize(packet),
-            Self::Cname(name) => name.serialize(packet),
+            Self::Ns(name) | Self::Cname(name) | Self::Ptr(name) => {
+                name.serialize_canonical(packet)
+            }
+            Self::Soa {
+                mname,
+                rname,
+                serial,
+                refresh,
+                retry,
+                expire,
+                minimum,
+            } => {
+                mname.serialize_canonical(packet);
+                rname.serialize_canonical(packet);
+                push_u32(packet, *serial);
+                push_u32(packet, *refresh);
+                push_u32(packet, *retry);
+                push_u32(packet, *expire);
+                push_u32(packet, *minimum);
+            }
             Self::Mx {
                 preference,
                 exchange,
             } => {
                 push_u16(packet, *preference);
-                exchange.serialize(packet);
+                exchange.serialize_canonical(packet);
+            }
+            Self::Srv {
+                priority,
+                weight,
+                port,
+                target,
+            } => {
+                push_u16(packet, *priority);
+                push_u16(packet, *weight);
+                push_u16(packet, *port);
+                target.serialize_canonical(packet);
+            }
+            Self::Rrsig(rrsig) => {
+                rrsig.serialize_unsigned(packet);
+                packet.put(&rrsig.signature);
             }
-            Self::Txt(txt) => {
-                // <character-string> is a single length octet followed by that number of characters.
-                // <character-string> is treated as binary information, and can be up to 256 characters in
-                // length (including the length octet).
-                packet.push(txt.len() as _);
-                packet.extend(txt.as_bytes());
+            _ => self.serialize(packet, &mut Compressor::disabled()),
+        }
+    }
+}
+
+/// The data of a TXT record, one or more `<character-string>`s.
+///
+/// A `<character-string>` is a single length octet followed by that number of bytes, so it can
+/// contain binary information of up to 255 bytes.
+///
+/// ```
+/// # use dominion_parser::body::Txt;
+/// let mut txt = Txt::from("v=spf1 ");
+/// txt.push(b"-all"[..].into());
+/// assert_eq!(txt.strings().count(), 2);
+/// assert_eq!(txt.to_text().unwrap(), "v=spf1 -all".to_string());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Txt<'a> {
+    strings: Vec<Cow<'a, [u8]>>,
+}
+
+impl<'a> From<&'a str> for Txt<'a> {
+    #[inline]
+    fn from(text: &'a str) -> Self {
+        Cow::from(text.as_bytes()).into()
+    }
+}
+
+impl From<String> for Txt<'_> {
+    #[inline]
+    fn from(text: String) -> Self {
+        Cow::<'_, [u8]>::from(text.into_bytes()).into()
+    }
+}
+
+impl<'a> From<&'a [u8]> for Txt<'a> {
+    #[inline]
+    fn from(bytes: &'a [u8]) -> Self {
+        Cow::from(bytes).into()
+    }
+}
+
+impl From<Vec<u8>> for Txt<'_> {
+    #[inline]
+    fn from(bytes: Vec<u8>) -> Self {
+        Cow::<'_, [u8]>::from(bytes).into()
+    }
+}
+
+impl<'a> From<Cow<'a, [u8]>> for Txt<'a> {
+    #[inline]
+    fn from(bytes: Cow<'a, [u8]>) -> Self {
+        let mut txt = Txt::new();
+        txt.push(bytes);
+        txt
+    }
+}
+
+impl<'a> Txt<'a> {
+    /// Create a new TXT record data without any string. It is serialized as a single empty
+    /// `<character-string>`, as TXT records need at least one.
+    #[inline]
+    pub fn new() -> Self {
+        Txt {
+            strings: Vec::new(),
+        }
+    }
+
+    /// Push a new string to the end of the record data. Strings longer than 255 bytes are split
+    /// into several consecutive `<character-string>`s.
+    #[inline]
+    pub fn push(&mut self, bytes: Cow<'a, [u8]>) {
+        match bytes {
+            _ if bytes.len() <= MAX_CHARACTER_STRING => self.strings.push(bytes),
+            Cow::Borrowed(bytes) => self
+                .strings
+                .extend(bytes.chunks(MAX_CHARACTER_STRING).map(Cow::from)),
+            Cow::Owned(bytes) => self.strings.extend(
+                bytes
+                    .chunks(MAX_CHARACTER_STRING)
+                    .map(|chunk| Cow::from(chunk.to_vec())),
+            ),
+        }
+    }
+
+    /// Return an iterator over the `<character-string>`s in the record data.
+    #[inline]
+    pub fn strings(&self) -> impl DoubleEndedIterator<Item = &'_ [u8]> {
+        self.strings.iter().map(|cow| cow.as_ref())
+    }
+
+    /// Join all the strings in the record data into a single text.
+    ///
+    /// # Errors
+    ///
+    /// It will error if the joined strings are not valid UTF-8.
+    #[inline]
+    pub fn to_text(&self) -> Result<String, str::Utf8Error> {
+        String::from_utf8(self.strings.concat()).map_err(|e| e.utf8_error())
+    }
+
+    /// Parse the `<character-string>`s contained in the `rdlen` bytes starting at position `pos`.
+    fn parse(buff: &'a [u8], pos: usize, rdlen: u16) -> Result<Self, ParseError> {
+        let mut txt = Txt::new();
+        let end = pos + rdlen as usize;
+        let mut pos = pos;
+        while pos < end {
+            let len = safe_u8_read(buff, pos)? as usize;
+            if pos + 1 + len > end {
+                Err(ParseError::OobRead(pos + 1 + len))?
             }
-            Self::Aaaa(ip) => packet.extend(ip.octets()),
-            Self::Unknown(buff) => packet.extend(buff.deref()),
+            txt.strings.push(Cow::from(&buff[pos + 1..pos + 1 + len]));
+            pos += len + 1;
+        }
+        Ok(txt)
+    }
+
+    #[inline]
+    fn serialize<W: Writer + ?Sized>(&self, packet: &mut W) {
+        if self.strings.is_empty() {
+            packet.put(&[0]);
+        }
+        for string in self.strings() {
+            packet.put(&[string.len() as _]);
+            packet.put(string);
         }
     }
 }
@@ -380,12 +812,34 @@ types! {
     Ns = 2
     /// The canonical name for an alias
     Cname = 5
+    /// Marks the start of a zone of authority
+    Soa = 6
+    /// A domain name pointer
+    Ptr = 12
     /// A mail exchange
     Mx = 15
     /// Text strings
     Txt = 16
     /// A host address (IPv6)
     Aaaa = 28
+    /// Server selection
+    Srv = 33
+    /// EDNS(0) OPT pseudo-record
+    Opt = 41
+    /// Delegation signer
+    Ds = 43
+    /// DNSSEC signature
+    Rrsig = 46
+    /// Next secure record
+    Nsec = 47
+    /// DNSSEC public key
+    Dnskey = 48
+    /// Hashed next secure record
+    Nsec3 = 50
+    /// NSEC3 parameters
+    Nsec3Param = 51
+    /// Transaction signature
+    Tsig = 250
 }
 
 /// An enumeration of the different available DNS Classes.
@@ -401,6 +855,8 @@ pub enum Class {
     CH,
     /// HS: Hesiod [Dyer 87]
     HS,
+    /// NONE: no class, used to delete records in UPDATE messages (RFC 2136)
+    None,
     /// *: any class
     Any,
     /// ?: A value has been received that does not correspond to any known class
@@ -415,6 +871,7 @@ impl From<u16> for Class {
             2 => Self::CS,
             3 => Self::CH,
             4 => Self::HS,
+            254 => Self::None,
             255 => Self::Any,
             _ => Self::Unknown(value),
         }
@@ -429,6 +886,7 @@ impl From<Class> for u16 {
             Class::CS => 2,
             Class::CH => 3,
             Class::HS => 4,
+            Class::None => 254,
             Class::Any => 255,
             Class::Unknown(n) => n,
         }
@@ -438,6 +896,7 @@ impl From<Class> for u16 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::vec;
 
     #[test]
     fn class_transformations() {
@@ -445,15 +904,138 @@ mod tests {
         assert_eq!(Class::CS, From::from(2u16));
         assert_eq!(Class::CH, From::from(3u16));
         assert_eq!(Class::HS, From::from(4u16));
+        assert_eq!(Class::None, From::from(254u16));
         assert_eq!(Class::Any, From::from(255u16));
         assert_eq!(Class::Unknown(225u16), From::from(225u16));
 
-        assert_eq!(1u16, From::from(Class::IN));
-        assert_eq!(2u16, From::from(Class::CS));
-        assert_eq!(3u16, From::from(Class::CH));
-        assert_eq!(4u16, From::from(Class::HS));
-        assert_eq!(255u16, From::from(Class::Any));
-        assert_eq!(225u16, From::from(Class::Unknown(225u16)));
+        assert_eq!(1u16, u16::from(Class::IN));
+        assert_eq!(2u16, u16::from(Class::CS));
+        assert_eq!(3u16, u16::from(Class::CH));
+        assert_eq!(4u16, u16::from(Class::HS));
+        assert_eq!(254u16, u16::from(Class::None));
+        assert_eq!(255u16, u16::from(Class::Any));
+        assert_eq!(225u16, u16::from(Class::Unknown(225u16)));
+    }
+
+    #[test]
+    fn txt_multiple_strings() {
+        let buff = [
+            5, 104, 101, 108, 108, 111, // hello
+            0,   // <empty>
+            3, 0, 255, 10, // <binary>
+            1, 1, // <outside of rdata>
+        ];
+        let txt = Txt::parse(&buff[..], 0, 11).unwrap();
+        let mut strings = txt.strings();
+        assert_eq!(strings.next(), Some(&b"hello"[..]));
+        assert_eq!(strings.next(), Some(&b""[..]));
+        assert_eq!(strings.next(), Some(&[0, 255, 10][..]));
+        assert_eq!(strings.next(), None);
+        assert!(txt.to_text().is_err());
+
+        let mut out = Vec::new();
+        txt.serialize(&mut out);
+        assert_eq!(&out[..], &buff[..11]);
+    }
+
+    #[test]
+    fn txt_overflow_rdata() {
+        let buff = [5, 104, 101, 108, 108, 111, 5, 104, 101, 108, 108, 111];
+        assert!(Txt::parse(&buff[..], 0, 9).is_err());
+    }
+
+    #[test]
+    fn txt_empty() {
+        let mut out = Vec::new();
+        Txt::new().serialize(&mut out);
+        assert_eq!(&out[..], &[0]);
+
+        let parsed = Txt::parse(&out[..], 0, 1).unwrap();
+        assert_eq!(parsed.strings().collect::<Vec<_>>(), vec![&b""[..]]);
+    }
+
+    #[test]
+    fn txt_split_long_strings() {
+        let long = "a".repeat(600);
+        let txt = Txt::from(long.as_str());
+        let lengths: Vec<usize> = txt.strings().map(|s| s.len()).collect();
+        assert_eq!(lengths, vec![255, 255, 90]);
+        assert_eq!(txt.to_text().unwrap(), long);
+
+        let txt = Txt::from(long.clone());
+        let lengths: Vec<usize> = txt.strings().map(|s| s.len()).collect();
+        assert_eq!(lengths, vec![255, 255, 90]);
+
+        let mut out = Vec::new();
+        txt.serialize(&mut out);
+        assert_eq!(out.len(), 603);
+        let parsed = Txt::parse(&out[..], 0, 603).unwrap();
+        assert_eq!(parsed.to_text().unwrap(), long);
+    }
+
+    #[test]
+    fn rdata_length_mismatch() {
+        // <root> A IN 300, RDLENGTH, 192.0.2.1 and two more bytes
+        let record = |rdlen: u8| [0, 0, 1, 0, 1, 0, 0, 1, 44, 0, rdlen, 192, 0, 2, 1, 0, 0];
+        let buff = record(4);
+        let (rr, n) = ResourceRecord::parse(&buff[..], 0).unwrap();
+        assert!(matches!(rr.data, RecordData::A(ip) if ip == Ipv4Addr::new(192, 0, 2, 1)));
+        assert_eq!(n, 15);
+        assert!(matches!(
+            ResourceRecord::parse(&record(2)[..], 0),
+            Err(ParseError::RdataLength(2, 4))
+        ));
+        assert!(matches!(
+            ResourceRecord::parse(&record(6)[..], 0),
+            Err(ParseError::RdataLength(6, 4))
+        ));
+
+        // <root> MX IN 300, RDLENGTH, 10 <root> and one more byte
+        let record = |rdlen: u8| [0, 0, 15, 0, 1, 0, 0, 1, 44, 0, rdlen, 0, 10, 0, 0];
+        assert!(ResourceRecord::parse(&record(3)[..], 0).is_ok());
+        assert!(matches!(
+            ResourceRecord::parse(&record(4)[..], 0),
+            Err(ParseError::RdataLength(4, 3))
+        ));
+    }
+
+    #[test]
+    fn soa_unsigned_fields() {
+        let rr = ResourceRecord {
+            preamble: RecordPreamble {
+                name: Name::try_from("example.com").unwrap(),
+                rrtype: Type::Soa,
+                class: Class::IN,
+                ttl: 300,
+            },
+            data: RecordData::Soa {
+                mname: Name::try_from("ns.example.com").unwrap(),
+                rname: Name::try_from("hostmaster.example.com").unwrap(),
+                serial: u32::MAX,
+                refresh: 0x8000_0000,
+                retry: 3_000_000_000,
+                expire: u32::MAX,
+                minimum: 0x8000_0001,
+            },
+        };
+        let mut out = Vec::new();
+        rr.serialize(&mut out);
+        let (parsed, _) = ResourceRecord::parse(&out[..], 0).unwrap();
+        let RecordData::Soa {
+            serial,
+            refresh,
+            retry,
+            expire,
+            minimum,
+            ..
+        } = parsed.data
+        else {
+            panic!("Expected SOA record")
+        };
+        assert_eq!(
+            [serial, refresh, retry, expire, minimum],
+            [u32::MAX, 0x8000_0000, 3_000_000_000, u32::MAX, 0x8000_0001]
+        );
     }
 
     #[test]
@@ -461,15 +1043,43 @@ mod tests {
         assert_eq!(QType::A, From::from(1u16));
         assert_eq!(QType::Ns, From::from(2u16));
         assert_eq!(QType::Cname, From::from(5u16));
+        assert_eq!(QType::Soa, From::from(6u16));
+        assert_eq!(QType::Ptr, From::from(12u16));
         assert_eq!(QType::Mx, From::from(15u16));
+        assert_eq!(QType::Srv, From::from(33u16));
+        assert_eq!(QType::Ds, From::from(43u16));
+        assert_eq!(QType::Rrsig, From::from(46u16));
+        assert_eq!(QType::Nsec, From::from(47u16));
+        assert_eq!(QType::Dnskey, From::from(48u16));
+        assert_eq!(QType::Nsec3, From::from(50u16));
+        assert_eq!(QType::Nsec3Param, From::from(51u16));
+        assert_eq!(QType::Tsig, From::from(250u16));
+        assert_eq!(QType::Ixfr, From::from(251u16));
+        assert_eq!(QType::Axfr, From::from(252u16));
+        assert_eq!(QType::Mailb, From::from(253u16));
+        assert_eq!(QType::Maila, From::from(254u16));
         assert_eq!(QType::All, From::from(255u16));
         assert_eq!(QType::Unknown(225u16), From::from(225u16));
 
-        assert_eq!(1u16, From::from(QType::A));
-        assert_eq!(2u16, From::from(QType::Ns));
-        assert_eq!(5u16, From::from(QType::Cname));
-        assert_eq!(15u16, From::from(QType::Mx));
-        assert_eq!(255u16, From::from(QType::All));
-        assert_eq!(225u16, From::from(QType::Unknown(225u16)));
+        assert_eq!(1u16, u16::from(QType::A));
+        assert_eq!(2u16, u16::from(QType::Ns));
+        assert_eq!(5u16, u16::from(QType::Cname));
+        assert_eq!(6u16, u16::from(QType::Soa));
+        assert_eq!(12u16, u16::from(QType::Ptr));
+        assert_eq!(15u16, u16::from(QType::Mx));
+        assert_eq!(33u16, u16::from(QType::Srv));
+        assert_eq!(43u16, u16::from(QType::Ds));
+        assert_eq!(46u16, u16::from(QType::Rrsig));
+        assert_eq!(47u16, u16::from(QType::Nsec));
+        assert_eq!(48u16, u16::from(QType::Dnskey));
+        assert_eq!(50u16, u16::from(QType::Nsec3));
+        assert_eq!(51u16, u16::from(QType::Nsec3Param));
+        assert_eq!(250u16, u16::from(QType::Tsig));
+        assert_eq!(251u16, u16::from(QType::Ixfr));
+        assert_eq!(252u16, u16::from(QType::Axfr));
+        assert_eq!(253u16, u16::from(QType::Mailb));
+        assert_eq!(254u16, u16::from(QType::Maila));
+        assert_eq!(255u16, u16::from(QType::All));
+        assert_eq!(225u16, u16::from(QType::Unknown(225u16)));
     }
 }
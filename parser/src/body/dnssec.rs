@@ -0,0 +1,775 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use crate::binutils::*;
+use crate::body::Type;
+use crate::body::name::Name;
+use crate::writer::{Writer, checked_u8};
+use crate::{ParseError, ParseOptions};
+use alloc::borrow::Cow;
+use alloc::collections::BTreeSet;
+use alloc::vec::Vec;
+use thiserror::Error;
+
+const MAX_BITMAP_LENGTH: u8 = 32;
+
+/// An error was encountered when trying to parse the data of a DNSSEC record.
+#[derive(Error, Debug)]
+pub enum DnssecError {
+    /// A window of a type bitmap has a length outside of the range 1 to 32.
+    #[error(
+        "Window {0} of the type bitmap has a length of {1} bytes (it should be between 1 and 32)."
+    )]
+    BitmapLength(u8, u8),
+    /// The windows of a type bitmap are not in increasing order.
+    #[error("Window {0} of the type bitmap is repeated or out of order.")]
+    BitmapOrder(u8),
+    /// A field with a length prefix is longer than the record data.
+    #[error("Field of length {0} does not fit in the record data.")]
+    FieldLength(usize),
+}
+
+macro_rules! u8_registry {
+    (
+        $(#[$outer:meta])*
+        $typ:ident with: $(
+            #[$inner:meta]
+            $variant:tt = $value:literal
+        )+
+    ) => {
+        $(#[$outer])*
+        #[non_exhaustive]
+        #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+        pub enum $typ {
+            $(
+                #[$inner]
+                $variant,
+            )*
+            /// ?: A value has been received that does not correspond to any known value.
+            Unknown(u8),
+        }
+
+        impl From<u8> for $typ {
+            #[inline]
+            fn from(value: u8) -> Self {
+                match value {
+                    $($value => Self::$variant,)*
+                    _ => Self::Unknown(value),
+                }
+            }
+        }
+
+        impl From<$typ> for u8 {
+            #[inline]
+            fn from(value: $typ) -> Self {
+                match value {
+                    $($typ::$variant => $value,)*
+                    $typ::Unknown(n) => n,
+                }
+            }
+        }
+    };
+}
+
+u8_registry! {
+    /// DNSSEC algorithm numbers, as registered by IANA.
+    Algorithm with:
+        /// RSA/MD5 (deprecated)
+        RsaMd5 = 1
+        /// Diffie-Hellman
+        Dh = 2
+        /// DSA/SHA-1
+        Dsa = 3
+        /// RSA/SHA-1
+        RsaSha1 = 5
+        /// DSA-NSEC3-SHA1
+        DsaNsec3Sha1 = 6
+        /// RSASHA1-NSEC3-SHA1
+        RsaSha1Nsec3Sha1 = 7
+        /// RSA/SHA-256
+        RsaSha256 = 8
+        /// RSA/SHA-512
+        RsaSha512 = 10
+        /// GOST R 34.10-2001
+        EccGost = 12
+        /// ECDSA Curve P-256 with SHA-256
+        EcdsaP256Sha256 = 13
+        /// ECDSA Curve P-384 with SHA-384
+        EcdsaP384Sha384 = 14
+        /// Ed25519
+        Ed25519 = 15
+        /// Ed448
+        Ed448 = 16
+        /// Indirect
+        Indirect = 252
+        /// Private algorithm identified by a domain name
+        PrivateDns = 253
+        /// Private algorithm identified by an OID
+        PrivateOid = 254
+}
+
+u8_registry! {
+    /// Digest algorithms used by DS records, as registered by IANA.
+    DigestType with:
+        /// SHA-1
+        Sha1 = 1
+        /// SHA-256
+        Sha256 = 2
+        /// GOST R 34.11-94
+        Gost = 3
+        /// SHA-384
+        Sha384 = 4
+}
+
+/// The public key of a zone, used to verify the RRSIG records of the zone (RFC 4034).
+///
+/// ```text
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    |                     FLAGS                     |
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    |       PROTOCOL        |       ALGORITHM       |
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    /                  PUBLIC KEY                   /
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dnskey<'a> {
+    /// The flags of the key, see [Dnskey::ZONE_KEY], [Dnskey::REVOKE] and [Dnskey::SECURE_ENTRY_POINT].
+    pub flags: u16,
+    /// Should always be 3.
+    pub protocol: u8,
+    /// The algorithm of the public key.
+    pub algorithm: Algorithm,
+    /// The public key, its format depends on the algorithm.
+    pub public_key: Cow<'a, [u8]>,
+}
+
+impl<'a> Dnskey<'a> {
+    /// The key can be used to verify the RRSIG records of the zone.
+    pub const ZONE_KEY: u16 = 0x0100;
+    /// The key has been revoked (RFC 5011).
+    pub const REVOKE: u16 = 0x0080;
+    /// The key is intended to be pointed to by a DS record of the parent zone.
+    pub const SECURE_ENTRY_POINT: u16 = 0x0001;
+
+    /// Returns true if the zone key flag is set.
+    #[inline]
+    pub fn is_zone_key(&self) -> bool {
+        self.flags & Self::ZONE_KEY != 0
+    }
+
+    /// Returns true if the secure entry point flag is set, which usually marks a key-signing key.
+    #[inline]
+    pub fn is_secure_entry_point(&self) -> bool {
+        self.flags & Self::SECURE_ENTRY_POINT != 0
+    }
+
+    /// Compute the key tag of the key (RFC 4034, Appendix B), used by RRSIG and DS records
+    /// to identify it.
+    pub fn key_tag(&self) -> u16 {
+        if self.algorithm == Algorithm::RsaMd5 {
+            let len = self.public_key.len();
+            if len < 3 {
+                return 0;
+            }
+            return u16::from_be_bytes([self.public_key[len - 3], self.public_key[len - 2]]);
+        }
+        let mut rdata = Vec::with_capacity(4 + self.public_key.len());
+        self.serialize(&mut rdata);
+        let mut acc: u32 = 0;
+        for (i, byte) in rdata.iter().enumerate() {
+            acc += if i & 1 == 1 {
+                *byte as u32
+            } else {
+                (*byte as u32) << 8
+            };
+        }
+        acc += (acc >> 16) & 0xFFFF;
+        (acc & 0xFFFF) as u16
+    }
+
+    pub(crate) fn parse(buff: &'a [u8], pos: usize, rdlen: u16) -> Result<Self, ParseError> {
+        let end = pos + rdlen as usize;
+        Ok(Dnskey {
+            flags: safe_u16_read(buff, pos)?,
+            protocol: safe_u8_read(buff, pos + 2)?,
+            algorithm: safe_u8_read(buff, pos + 3)?.into(),
+            public_key: Cow::from(remaining(buff, pos + 4, end)?),
+        })
+    }
+
+    pub(crate) fn serialize<W: Writer + ?Sized>(&self, packet: &mut W) {
+        push_u16(packet, self.flags);
+        packet.put(&[self.protocol]);
+        packet.put(&[self.algorithm.into()]);
+        packet.put(&self.public_key);
+    }
+}
+
+/// A signature over a RRset (RFC 4034).
+///
+/// ```text
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    |                 TYPE COVERED                  |
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    |       ALGORITHM       |        LABELS         |
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    |                 ORIGINAL TTL                  |
+///    |                                               |
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    |             SIGNATURE EXPIRATION              |
+///    |                                               |
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    |              SIGNATURE INCEPTION              |
+///    |                                               |
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    |                    KEY TAG                    |
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    /                 SIGNER'S NAME                 /
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    /                   SIGNATURE                   /
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+/// ```
+#[derive(Debug, Clone)]
+pub struct Rrsig<'a> {
+    /// The type of the RRset covered by the signature.
+    pub type_covered: Type,
+    /// The algorithm used to create the signature.
+    pub algorithm: Algorithm,
+    /// The number of labels of the owner name, without the root and a leading wildcard.
+    pub labels: u8,
+    /// The TTL of the covered RRset as it appears in the authoritative zone.
+    pub original_ttl: u32,
+    /// The signature must not be used after this time, in seconds since the UNIX epoch (mod 2^32).
+    pub expiration: u32,
+    /// The signature must not be used before this time, in seconds since the UNIX epoch (mod 2^32).
+    pub inception: u32,
+    /// The key tag of the DNSKEY that validates this signature.
+    pub key_tag: u16,
+    /// The owner of the DNSKEY that validates this signature.
+    pub signer: Name<'a>,
+    /// The cryptographic signature.
+    pub signature: Cow<'a, [u8]>,
+}
+
+impl<'a> Rrsig<'a> {
+    pub(crate) fn parse(
+        buff: &'a [u8],
+        pos: usize,
+        rdlen: u16,
+        options: &ParseOptions,
+    ) -> Result<Self, ParseError> {
+        let end = pos + rdlen as usize;
+        let (signer, n) = Name::parse_with(buff, pos + 18, options)?;
+        Ok(Rrsig {
+            type_covered: safe_u16_read(buff, pos)?.into(),
+            algorithm: safe_u8_read(buff, pos + 2)?.into(),
+            labels: safe_u8_read(buff, pos + 3)?,
+            original_ttl: safe_u32_read(buff, pos + 4)?,
+            expiration: safe_u32_read(buff, pos + 8)?,
+            inception: safe_u32_read(buff, pos + 12)?,
+            key_tag: safe_u16_read(buff, pos + 16)?,
+            signer,
+            signature: Cow::from(remaining(buff, pos + 18 + n, end)?),
+        })
+    }
+
+    pub(crate) fn serialize<W: Writer + ?Sized>(&self, packet: &mut W) {
+        self.serialize_fields(packet);
+        // RFC 4034: the signer's name must not be compressed.
+        self.signer.serialize(packet);
+        packet.put(&self.signature);
+    }
+
+    /// Serialize the data without the signature and with the signer's name in canonical
+    /// form, as it is included in the signed data (RFC 4034 section 3.1.8.1).
+    pub(crate) fn serialize_unsigned<W: Writer + ?Sized>(&self, packet: &mut W) {
+        self.serialize_fields(packet);
+        self.signer.serialize_canonical(packet);
+    }
+
+    fn serialize_fields<W: Writer + ?Sized>(&self, packet: &mut W) {
+        push_u16(packet, self.type_covered.into());
+        packet.put(&[self.algorithm.into()]);
+        packet.put(&[self.labels]);
+        push_u32(packet, self.original_ttl);
+        push_u32(packet, self.expiration);
+        push_u32(packet, self.inception);
+        push_u16(packet, self.key_tag);
+    }
+}
+
+/// A delegation signer, which refers to a DNSKEY of the child zone (RFC 4034).
+///
+/// ```text
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    |                    KEY TAG                    |
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    |       ALGORITHM       |      DIGEST TYPE      |
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    /                    DIGEST                     /
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ds<'a> {
+    /// The key tag of the referred DNSKEY.
+    pub key_tag: u16,
+    /// The algorithm of the referred DNSKEY.
+    pub algorithm: Algorithm,
+    /// The algorithm used to compute the digest.
+    pub digest_type: DigestType,
+    /// The digest of the owner name and data of the referred DNSKEY.
+    pub digest: Cow<'a, [u8]>,
+}
+
+impl<'a> Ds<'a> {
+    pub(crate) fn parse(buff: &'a [u8], pos: usize, rdlen: u16) -> Result<Self, ParseError> {
+        let end = pos + rdlen as usize;
+        Ok(Ds {
+            key_tag: safe_u16_read(buff, pos)?,
+            algorithm: safe_u8_read(buff, pos + 2)?.into(),
+            digest_type: safe_u8_read(buff, pos + 3)?.into(),
+            digest: Cow::from(remaining(buff, pos + 4, end)?),
+        })
+    }
+
+    pub(crate) fn serialize<W: Writer + ?Sized>(&self, packet: &mut W) {
+        push_u16(packet, self.key_tag);
+        packet.put(&[self.algorithm.into()]);
+        packet.put(&[self.digest_type.into()]);
+        packet.put(&self.digest);
+    }
+}
+
+/// Authenticated denial of existence, the next owner name in the zone and the types present
+/// at the owner of this record (RFC 4034).
+///
+/// ```text
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    /               NEXT DOMAIN NAME                /
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    /                 TYPE BIT MAPS                 /
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+/// ```
+#[derive(Debug, Clone)]
+pub struct Nsec<'a> {
+    /// The next owner name in the canonical ordering of the zone.
+    pub next: Name<'a>,
+    /// The types present at the owner name.
+    pub types: TypeBitmap,
+}
+
+impl<'a> Nsec<'a> {
+    pub(crate) fn parse(
+        buff: &'a [u8],
+        pos: usize,
+        rdlen: u16,
+        options: &ParseOptions,
+    ) -> Result<Self, ParseError> {
+        let end = pos + rdlen as usize;
+        let (next, n) = Name::parse_with(buff, pos, options)?;
+        Ok(Nsec {
+            next,
+            types: TypeBitmap::parse(buff, pos + n, end)?,
+        })
+    }
+
+    pub(crate) fn serialize<W: Writer + ?Sized>(&self, packet: &mut W) {
+        // RFC 4034: the next domain name must not be compressed.
+        self.next.serialize(packet);
+        self.types.serialize(packet);
+    }
+}
+
+/// Hashed authenticated denial of existence (RFC 5155).
+///
+/// ```text
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    |    HASH ALGORITHM     |         FLAGS         |
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    |                  ITERATIONS                   |
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    |      SALT LENGTH      |         SALT          /
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    |      HASH LENGTH      |  NEXT HASHED OWNER    /
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    /                 TYPE BIT MAPS                 /
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Nsec3<'a> {
+    /// The hash algorithm, only SHA-1 (1) is defined.
+    pub hash_algorithm: u8,
+    /// The flags of the record, see [Nsec3::OPT_OUT].
+    pub flags: u8,
+    /// Additional times the hash function has been applied.
+    pub iterations: u16,
+    /// The salt appended to the name before hashing, of at most 255 bytes.
+    pub salt: Cow<'a, [u8]>,
+    /// The next hashed owner name in the zone, in binary form, of at most 255 bytes.
+    pub next_hashed: Cow<'a, [u8]>,
+    /// The types present at the original owner name.
+    pub types: TypeBitmap,
+}
+
+impl<'a> Nsec3<'a> {
+    /// The record may cover unsigned delegations.
+    pub const OPT_OUT: u8 = 0x01;
+
+    /// Returns true if the opt-out flag is set.
+    #[inline]
+    pub fn is_opt_out(&self) -> bool {
+        self.flags & Self::OPT_OUT != 0
+    }
+
+    pub(crate) fn parse(buff: &'a [u8], pos: usize, rdlen: u16) -> Result<Self, ParseError> {
+        let end = pos + rdlen as usize;
+        let (salt, pos_hash) = length_prefixed(buff, pos + 4, end)?;
+        let (next_hashed, pos_types) = length_prefixed(buff, pos_hash, end)?;
+        Ok(Nsec3 {
+            hash_algorithm: safe_u8_read(buff, pos)?,
+            flags: safe_u8_read(buff, pos + 1)?,
+            iterations: safe_u16_read(buff, pos + 2)?,
+            salt: Cow::from(salt),
+            next_hashed: Cow::from(next_hashed),
+            types: TypeBitmap::parse(buff, pos_types, end)?,
+        })
+    }
+
+    pub(crate) fn serialize<W: Writer + ?Sized>(&self, packet: &mut W) {
+        packet.put(&[self.hash_algorithm]);
+        packet.put(&[self.flags]);
+        push_u16(packet, self.iterations);
+        put_length_prefixed(packet, &self.salt);
+        put_length_prefixed(packet, &self.next_hashed);
+        self.types.serialize(packet);
+    }
+}
+
+/// The parameters needed by authoritative servers to compute hashed owner names (RFC 5155).
+///
+/// ```text
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    |    HASH ALGORITHM     |         FLAGS         |
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    |                  ITERATIONS                   |
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    |      SALT LENGTH      |         SALT          /
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Nsec3Param<'a> {
+    /// The hash algorithm, only SHA-1 (1) is defined.
+    pub hash_algorithm: u8,
+    /// Flags, should be 0.
+    pub flags: u8,
+    /// Additional times the hash function has been applied.
+    pub iterations: u16,
+    /// The salt appended to the name before hashing, of at most 255 bytes.
+    pub salt: Cow<'a, [u8]>,
+}
+
+impl<'a> Nsec3Param<'a> {
+    pub(crate) fn parse(buff: &'a [u8], pos: usize, rdlen: u16) -> Result<Self, ParseError> {
+        let end = pos + rdlen as usize;
+        let (salt, _) = length_prefixed(buff, pos + 4, end)?;
+        Ok(Nsec3Param {
+            hash_algorithm: safe_u8_read(buff, pos)?,
+            flags: safe_u8_read(buff, pos + 1)?,
+            iterations: safe_u16_read(buff, pos + 2)?,
+            salt: Cow::from(salt),
+        })
+    }
+
+    pub(crate) fn serialize<W: Writer + ?Sized>(&self, packet: &mut W) {
+        packet.put(&[self.hash_algorithm]);
+        packet.put(&[self.flags]);
+        push_u16(packet, self.iterations);
+        put_length_prefixed(packet, &self.salt);
+    }
+}
+
+/// The set of types present at a name, as encoded in NSEC and NSEC3 records.
+///
+/// ```
+/// # use dominion_parser::body::Type;
+/// # use dominion_parser::body::dnssec::TypeBitmap;
+/// let types: TypeBitmap = [Type::A, Type::Rrsig, Type::Nsec].into_iter().collect();
+/// assert!(types.contains(Type::A));
+/// assert!(!types.contains(Type::Aaaa));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TypeBitmap {
+    types: BTreeSet<u16>,
+}
+
+impl FromIterator<Type> for TypeBitmap {
+    #[inline]
+    fn from_iter<I: IntoIterator<Item = Type>>(iter: I) -> Self {
+        TypeBitmap {
+            types: iter.into_iter().map(u16::from).collect(),
+        }
+    }
+}
+
+impl TypeBitmap {
+    /// Create an empty bitmap.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a type to the set, returns false if it was already present.
+    #[inline]
+    pub fn insert(&mut self, rrtype: Type) -> bool {
+        self.types.insert(rrtype.into())
+    }
+
+    /// Remove a type from the set, returns false if it was not present.
+    #[inline]
+    pub fn remove(&mut self, rrtype: Type) -> bool {
+        self.types.remove(&rrtype.into())
+    }
+
+    /// Returns true if the type is present in the set.
+    #[inline]
+    pub fn contains(&self, rrtype: Type) -> bool {
+        self.types.contains(&rrtype.into())
+    }
+
+    /// Number of types in the set.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.types.len()
+    }
+
+    /// Returns true if there are no types in the set.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.types.is_empty()
+    }
+
+    /// Iterate over the types in increasing numerical order.
+    #[inline]
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = Type> + '_ {
+        self.types.iter().map(|n| Type::from(*n))
+    }
+
+    /// Parse the windows of the bitmap from position `pos` until `end`.
+    fn parse(buff: &[u8], mut pos: usize, end: usize) -> Result<Self, ParseError> {
+        let mut types = BTreeSet::new();
+        let mut last_window = None;
+        while pos < end {
+            let window = safe_u8_read(buff, pos)?;
+            let len = safe_u8_read(buff, pos + 1)?;
+            if last_window.is_some_and(|last| last >= window) {
+                Err(DnssecError::BitmapOrder(window))?
+            }
+            if len == 0 || len > MAX_BITMAP_LENGTH {
+                Err(DnssecError::BitmapLength(window, len))?
+            }
+            let bits = within(buff, pos + 2, len as usize, end)?;
+            for (i, byte) in bits.iter().enumerate() {
+                for bit in 0..8 {
+                    if byte & (0x80 >> bit) != 0 {
+                        types.insert(((window as u16) << 8) | (i * 8 + bit) as u16);
+                    }
+                }
+            }
+            last_window = Some(window);
+            pos += 2 + len as usize;
+        }
+        Ok(TypeBitmap { types })
+    }
+
+    fn serialize<W: Writer + ?Sized>(&self, packet: &mut W) {
+        let mut types = self.types.iter().peekable();
+        while let Some(first) = types.peek() {
+            let window = (*first >> 8) as u8;
+            let mut bits = [0u8; MAX_BITMAP_LENGTH as usize];
+            let mut len = 0;
+            while let Some(n) = types.next_if(|n| (*n >> 8) as u8 == window) {
+                let low = (n & 0xFF) as usize;
+                bits[low / 8] |= 0x80 >> (low % 8);
+                len = low / 8 + 1;
+            }
+            packet.put(&[window]);
+            packet.put(&[len as _]);
+            packet.put(&bits[..len]);
+        }
+    }
+}
+
+/// Read `len` bytes starting at `pos` that have to end before `end`.
+#[inline]
+fn within(buff: &[u8], pos: usize, len: usize, end: usize) -> Result<&[u8], ParseError> {
+    if pos + len > end {
+        Err(DnssecError::FieldLength(len))?
+    }
+    safe_slice_read(buff, pos, len)
+}
+
+/// Read the bytes from `pos` until `end`.
+#[inline]
+fn remaining(buff: &[u8], pos: usize, end: usize) -> Result<&[u8], ParseError> {
+    if pos > end {
+        Err(ParseError::OobRead(pos))?
+    }
+    safe_slice_read(buff, pos, end - pos)
+}
+
+/// Read a field prefixed by its length in one byte, returning the position after it.
+#[inline]
+fn length_prefixed(buff: &[u8], pos: usize, end: usize) -> Result<(&[u8], usize), ParseError> {
+    let len = safe_u8_read(buff, pos)? as usize;
+    let field = within(buff, pos + 1, len, end)?;
+    Ok((field, pos + 1 + len))
+}
+
+/// Write a field prefixed by its length in one byte, making the writer fail if it is longer
+/// than 255 bytes.
+#[inline]
+fn put_length_prefixed<W: Writer + ?Sized>(packet: &mut W, field: &[u8]) {
+    let len = checked_u8(packet, field.len());
+    packet.put(&[len]);
+    packet.put(&field[..len as usize]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::writer::{SliceWriter, WriteError};
+    use alloc::vec;
+
+    #[test]
+    fn bitmap_roundtrip() {
+        // RFC 4034, section 4.3: A MX RRSIG NSEC TYPE1234
+        let buff = [
+            0x00, 0x06, 0x40, 0x01, 0x00, 0x00, 0x00, 0x03, //
+            0x04, 0x1b, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, //
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, //
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, //
+            0x00, 0x00, 0x00, 0x00, 0x20,
+        ];
+        let bitmap = TypeBitmap::parse(&buff, 0, buff.len()).unwrap();
+        let types: Vec<_> = bitmap.iter().collect();
+        assert_eq!(
+            types,
+            vec![
+                Type::A,
+                Type::Mx,
+                Type::Rrsig,
+                Type::Nsec,
+                Type::Unknown(1234)
+            ]
+        );
+
+        let mut out = Vec::new();
+        bitmap.serialize(&mut out);
+        assert_eq!(&out[..], &buff[..]);
+    }
+
+    #[test]
+    fn bitmap_invalid() {
+        let zero_len = [0x00, 0x00];
+        assert!(TypeBitmap::parse(&zero_len, 0, 2).is_err());
+        let too_long = [0x00, 0x21];
+        assert!(TypeBitmap::parse(&too_long, 0, 2).is_err());
+        let out_of_order = [0x01, 0x01, 0x80, 0x00, 0x01, 0x40];
+        assert!(TypeBitmap::parse(&out_of_order, 0, 6).is_err());
+        let overflow = [0x00, 0x04, 0x40];
+        assert!(TypeBitmap::parse(&overflow, 0, 3).is_err());
+    }
+
+    #[test]
+    fn dnskey_key_tag() {
+        // RFC 4034, section 5.4: the key with tag 60485 of dskey.example.com.
+        let public_key = [
+            0x01, 0x03, 0x9e, 0x8a, 0x24, 0x74, 0x18, 0xe3, 0x18, 0x90, 0x3b, 0x21, 0x5a, 0x84,
+            0x8a, 0xcf, 0xd5, 0xf3, 0x7f, 0x02, 0x6b, 0xd4, 0x06, 0x2d, 0xb2, 0x6c, 0x77, 0x4c,
+            0x69, 0x09, 0x68, 0xd5, 0xd5, 0x6d, 0xf8, 0xbf, 0xda, 0x91, 0xe6, 0xf3, 0x6d, 0x9a,
+            0x27, 0x98, 0x88, 0xf4, 0x13, 0x33, 0x35, 0x7c, 0x5e, 0x60, 0x29, 0x99, 0x0d, 0x10,
+            0xfd, 0xf5, 0x66, 0x30, 0x62, 0xa5, 0x12, 0x76, 0x33, 0x26, 0x98, 0x0a, 0x61, 0x5d,
+            0xdb, 0xf1, 0x7a, 0x05, 0xdd, 0xfc, 0xce, 0x7e, 0x5f, 0xb3, 0xab, 0xcc, 0xa0, 0x5a,
+            0x31, 0xb0, 0x95, 0x74, 0x52, 0xd4, 0x52, 0x1e, 0x83, 0x87, 0x07, 0x89, 0x06, 0x31,
+            0x15, 0xbf, 0x97, 0xf6, 0xc3, 0x08, 0xcc, 0xf5, 0x7c, 0xdc, 0x9c, 0xe7, 0xfe, 0x10,
+            0xf6, 0xed, 0x1b, 0xd0, 0xcc, 0x06, 0x60, 0x03, 0x8c, 0x50, 0xdc, 0xdb, 0x0f, 0xeb,
+            0x96, 0x3c, 0x2f, 0x17,
+        ];
+        let key = Dnskey {
+            flags: 256,
+            protocol: 3,
+            algorithm: Algorithm::RsaSha1,
+            public_key: Cow::from(&public_key[..]),
+        };
+        assert!(key.is_zone_key());
+        assert!(!key.is_secure_entry_point());
+        assert_eq!(key.key_tag(), 60485);
+    }
+
+    #[test]
+    fn nsec3param_roundtrip() {
+        let buff = [0x01, 0x00, 0x00, 0x0c, 0x04, 0xaa, 0xbb, 0xcc, 0xdd];
+        let param = Nsec3Param::parse(&buff, 0, buff.len() as _).unwrap();
+        assert_eq!(param.hash_algorithm, 1);
+        assert_eq!(param.iterations, 12);
+        assert_eq!(&param.salt[..], &[0xaa, 0xbb, 0xcc, 0xdd]);
+
+        let mut out = Vec::new();
+        param.serialize(&mut out);
+        assert_eq!(&out[..], &buff[..]);
+    }
+
+    #[test]
+    fn nsec3_salt_overflow() {
+        let buff = [0x01, 0x00, 0x00, 0x00, 0x08, 0xaa, 0xbb, 0x00];
+        assert!(Nsec3::parse(&buff, 0, buff.len() as _).is_err());
+    }
+
+    #[test]
+    fn nsec3_long_fields() {
+        let mut nsec3 = Nsec3 {
+            hash_algorithm: 1,
+            flags: 0,
+            iterations: 0,
+            salt: Cow::from(vec![0xaa; 255]),
+            next_hashed: Cow::from(vec![0xbb; 256]),
+            types: TypeBitmap::default(),
+        };
+        let mut buff = [0; 1024];
+        let mut writer = SliceWriter::new(&mut buff);
+        nsec3.serialize(&mut writer);
+        assert!(matches!(writer.finish(), Err(WriteError::Overflow(256))));
+
+        nsec3.next_hashed = Cow::from(vec![0xbb; 255]);
+        let mut writer = SliceWriter::new(&mut buff);
+        nsec3.serialize(&mut writer);
+        let out = writer.finish().unwrap();
+        let parsed = Nsec3::parse(out, 0, out.len() as _).unwrap();
+        assert_eq!(parsed, nsec3);
+
+        let param = Nsec3Param {
+            hash_algorithm: 1,
+            flags: 0,
+            iterations: 0,
+            salt: Cow::from(vec![0xaa; 300]),
+        };
+        let mut writer = SliceWriter::new(&mut buff);
+        param.serialize(&mut writer);
+        assert!(matches!(writer.finish(), Err(WriteError::Overflow(300))));
+    }
+
+    #[test]
+    fn algorithm_transformations() {
+        assert_eq!(Algorithm::RsaSha256, From::from(8u8));
+        assert_eq!(Algorithm::EcdsaP256Sha256, From::from(13u8));
+        assert_eq!(Algorithm::Ed25519, From::from(15u8));
+        assert_eq!(Algorithm::Unknown(100), From::from(100u8));
+        assert_eq!(13u8, u8::from(Algorithm::EcdsaP256Sha256));
+        assert_eq!(100u8, u8::from(Algorithm::Unknown(100)));
+
+        assert_eq!(DigestType::Sha256, From::from(2u8));
+        assert_eq!(4u8, u8::from(DigestType::Sha384));
+    }
+}
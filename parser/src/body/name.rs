@@ -2,56 +2,242 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use crate::ParseError;
 use crate::binutils::*;
+use crate::writer::Writer;
+use crate::{ParseError, ParseOptions};
 
 use thiserror::Error;
 
-use std::borrow::Cow;
-use std::fmt;
-use std::iter::zip;
-use std::ops::Deref;
-use std::str;
+use alloc::borrow::Cow;
+use alloc::format;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::iter::zip;
+use core::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use core::ops::Index;
+use core::str;
 
 const INIT_NUM_LABELS: usize = 8;
 
+const REVERSE_V4: [&str; 2] = ["arpa", "in-addr"];
+const REVERSE_V6: [&str; 2] = ["arpa", "ip6"];
+
 pub(crate) const MAX_JUMPS: u8 = 5;
 
 pub(crate) const MAX_LABEL_SIZE: usize = 63;
 pub(crate) const MAX_NAME_SIZE: usize = 255;
 
+/// Pointers only have 14 bits to store the offset.
+const MAX_POINTER_OFFSET: usize = 0b0011_1111_1111_1111;
+
 /// An error was encountered when trying to work with a domain name
 #[derive(Error, Debug)]
 pub enum NameError {
     /// Some label in the DNS packet it too long, overflowing the packet or not following the DNS specification.
     #[error(
-        "Specified label length ({0}) is empty or is bigger than DNS specification (maximum {}).",
-        MAX_LABEL_SIZE
+        "Specified label length ({0}) is empty or is bigger than DNS specification (maximum {max}).",
+        max = MAX_LABEL_SIZE
     )]
     LabelLength(usize),
-    /// Some label in one of the domain names is not valid because it contains characters that are not alphanumeric or `-`.
+    /// Some label in one of the domain names is not valid because it contains a `.` or characters that are not printable ASCII.
     #[error("The provided label is not a valid domain name label")]
     LabelContent,
     /// One of the labels in the packet has a length that is bigger than the DNS specification.
     #[error(
-        "Name length ({0}) is too long, is bigger than DNS specification (maximum {}).",
-        MAX_NAME_SIZE
+        "Name length ({0}) is too long, is bigger than DNS specification (maximum {max}).",
+        max = MAX_NAME_SIZE
     )]
     NameLength(usize),
+    /// Some escape sequence in the presentation format of a domain name is not a `\` followed
+    /// by a character or by a decimal number of three digits up to 255.
+    #[error("Invalid escape sequence in the domain name.")]
+    Escape,
+    /// Some text is not a valid internationalized domain name (requires the `idna` feature).
+    #[cfg(feature = "idna")]
+    #[error("The text is not a valid internationalized domain name.")]
+    Idna,
+}
+
+/// A label of a domain name, which can contain any octet.
+///
+/// Labels are compared ignoring the case of ASCII letters, and displayed in the presentation
+/// format of RFC 1035 section 5.1: special characters like `.` are escaped with a `\` and the
+/// octets that are not printable ASCII are written as `\DDD`, with their decimal value.
+///
+/// ```
+/// # use dominion_parser::body::name::Label;
+/// let label = Label::try_from(&b"a.b\x00"[..]).unwrap();
+/// assert_eq!(label.to_string(), "a\\.b\\000");
+/// assert_eq!(Label::try_from("WWW").unwrap(), "www");
+/// ```
+#[derive(Clone)]
+pub struct Label<'a>(Cow<'a, [u8]>);
+
+impl fmt::Display for Label<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for &b in self.as_bytes() {
+            match b {
+                b'.' | b'\\' | b'"' | b'(' | b')' | b';' | b'@' | b'$' => {
+                    write!(f, "\\{}", b as char)?
+                }
+                0x21..=0x7E => write!(f, "{}", b as char)?,
+                _ => write!(f, "\\{b:03}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Label<'_> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl<'b> PartialEq<Label<'b>> for Label<'_> {
+    #[inline]
+    fn eq(&self, other: &Label<'b>) -> bool {
+        self.as_bytes().eq_ignore_ascii_case(other.as_bytes())
+    }
+}
+
+impl PartialEq<str> for Label<'_> {
+    #[inline]
+    fn eq(&self, other: &str) -> bool {
+        self.as_bytes().eq_ignore_ascii_case(other.as_bytes())
+    }
+}
+
+impl PartialEq<&str> for Label<'_> {
+    #[inline]
+    fn eq(&self, other: &&str) -> bool {
+        self.as_bytes().eq_ignore_ascii_case(other.as_bytes())
+    }
+}
+
+impl Eq for Label<'_> {}
+
+impl Hash for Label<'_> {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u8(self.0.len() as _);
+        for b in self.as_bytes() {
+            state.write_u8(b.to_ascii_lowercase());
+        }
+    }
+}
+
+impl<'b> PartialOrd<Label<'b>> for Label<'_> {
+    #[inline]
+    fn partial_cmp(&self, other: &Label<'b>) -> Option<Ordering> {
+        Some(canonical_label_cmp(self, other))
+    }
+}
+
+/// Labels are sorted as octet strings with the ASCII letters in lowercase, as in the canonical
+/// order of RFC 4034 section 6.1.
+impl Ord for Label<'_> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        canonical_label_cmp(self, other)
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for Label<'a> {
+    type Error = NameError;
+
+    #[inline]
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        Label::new(Cow::Borrowed(value))
+    }
+}
+
+impl<'a> TryFrom<Vec<u8>> for Label<'a> {
+    type Error = NameError;
+
+    #[inline]
+    fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
+        Label::new(Cow::Owned(value))
+    }
+}
+
+/// The text is used as it is, without decoding escape sequences.
+impl<'a> TryFrom<&'a str> for Label<'a> {
+    type Error = NameError;
+
+    #[inline]
+    fn try_from(value: &'a str) -> Result<Self, Self::Error> {
+        Label::new(Cow::Borrowed(value.as_bytes()))
+    }
+}
+
+/// The text is used as it is, without decoding escape sequences.
+impl<'a> TryFrom<String> for Label<'a> {
+    type Error = NameError;
+
+    #[inline]
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Label::new(Cow::Owned(value.into_bytes()))
+    }
+}
+
+impl<'a> Label<'a> {
+    /// Create a label, checking that it is not empty and that it fits in 63 bytes.
+    #[inline]
+    fn new(bytes: Cow<'a, [u8]>) -> Result<Self, NameError> {
+        match bytes.len() {
+            1..=MAX_LABEL_SIZE => Ok(Label(bytes)),
+            len => Err(NameError::LabelLength(len)),
+        }
+    }
+
+    /// The octets of the label.
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// The label as text, if it is valid UTF-8.
+    ///
+    /// ```
+    /// # use dominion_parser::body::name::Label;
+    /// assert_eq!(Label::try_from("com").unwrap().as_str(), Some("com"));
+    /// assert_eq!(Label::try_from(&b"\xFF"[..]).unwrap().as_str(), None);
+    /// ```
+    #[inline]
+    pub fn as_str(&self) -> Option<&str> {
+        str::from_utf8(&self.0).ok()
+    }
+
+    /// Copy the label if it is borrowed, so that it does not depend on the original buffer.
+    #[inline]
+    pub fn into_owned(self) -> Label<'static> {
+        Label(Cow::Owned(self.0.into_owned()))
+    }
 }
 
 /// A domain name represented as an inverted list of labels.
 #[derive(Clone)]
 pub struct Name<'a> {
     /// Domain name labels
-    labels: Vec<Cow<'a, str>>,
-    /// Length of the domain name
+    labels: Vec<Label<'a>>,
+    /// Length of the labels in the wire format, without the final root label
     len: u8,
 }
 
+/// Domain names are displayed in the presentation format of RFC 1035 section 5.1, with a final
+/// `.`. The root domain name is displayed as `.`.
 impl fmt::Display for Name<'_> {
     #[inline]
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.labels.is_empty() {
+            return write!(f, ".");
+        }
         for l in self.iter_human() {
             write!(f, "{}.", l)?;
         }
@@ -62,10 +248,68 @@ impl fmt::Display for Name<'_> {
 impl fmt::Debug for Name<'_> {
     #[inline]
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for l in self.iter_human() {
-            write!(f, "{}.", l)?;
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// Names are equal if they have the same labels, ignoring the case of ASCII letters.
+///
+/// ```
+/// # use dominion_parser::body::name::Name;
+/// let name = Name::try_from("WWW.Example.com").unwrap();
+/// assert_eq!(name, Name::try_from("www.example.COM.").unwrap());
+/// assert_ne!(name, Name::try_from("example.com").unwrap());
+/// ```
+impl<'b> PartialEq<Name<'b>> for Name<'_> {
+    #[inline]
+    fn eq(&self, other: &Name<'b>) -> bool {
+        self.label_count() == other.label_count()
+            && zip(self.iter_hierarchy(), other.iter_hierarchy()).all(|(x, y)| x == y)
+    }
+}
+
+impl Eq for Name<'_> {}
+
+impl Hash for Name<'_> {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for label in self.iter_hierarchy() {
+            label.hash(state);
         }
-        Ok(())
+    }
+}
+
+impl<'b> PartialOrd<Name<'b>> for Name<'_> {
+    #[inline]
+    fn partial_cmp(&self, other: &Name<'b>) -> Option<Ordering> {
+        Some(canonical_cmp(self, other))
+    }
+}
+
+/// Names are sorted in the canonical order of RFC 4034 section 6.1: label by label starting
+/// from the TLD, ignoring the case of ASCII letters, with parents before their subdomains.
+///
+/// ```
+/// # use dominion_parser::body::name::Name;
+/// let parent = Name::try_from("example").unwrap();
+/// let sub = Name::try_from("Z.a.example").unwrap();
+/// let other = Name::try_from("zABC.a.EXAMPLE").unwrap();
+/// assert!(parent < sub && sub < other);
+/// ```
+impl Ord for Name<'_> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        canonical_cmp(self, other)
+    }
+}
+
+/// Labels are indexed in human order.
+impl<'a> Index<usize> for Name<'a> {
+    type Output = Label<'a>;
+
+    #[inline]
+    fn index(&self, i: usize) -> &Self::Output {
+        self.get(i).expect("label index out of bounds")
     }
 }
 
@@ -79,25 +323,38 @@ impl Default for Name<'_> {
 impl From<Name<'_>> for Vec<u8> {
     #[inline]
     fn from(name: Name<'_>) -> Self {
-        let mut out = Vec::with_capacity(name.len as _);
+        let mut out = Vec::with_capacity(name.size());
         name.serialize(&mut out);
         out
     }
 }
 
+/// Domain names are parsed from the presentation format of RFC 1035 section 5.1, where `\.` is
+/// a `.` inside of a label and `\DDD` is the octet with the decimal value `DDD`. Any other octet
+/// that is not printable ASCII, like a space, has to be escaped. The final `.` is optional, and
+/// a single `.` is the root domain name.
+///
+/// ```
+/// # use dominion_parser::body::name::Name;
+/// let name = Name::try_from("a\\.b.example.com.").unwrap();
+/// assert_eq!(name.label_count(), 3);
+/// assert_eq!(name.to_string(), "a\\.b.example.com.");
+///
+/// let name = Name::try_from("\\000\\255.example").unwrap();
+/// assert_eq!(name.iter_human().next().unwrap().as_bytes(), &[0, 255]);
+///
+/// assert_eq!(Name::try_from(".").unwrap().label_count(), 0);
+/// assert!(Name::try_from("").is_err());
+/// assert!(Name::try_from("a..b").is_err());
+/// ```
 impl<'a> TryFrom<&'a str> for Name<'a> {
     type Error = NameError;
 
     fn try_from(value: &'a str) -> Result<Self, Self::Error> {
-        let bytes = value.as_bytes();
-        let labels = match bytes[bytes.len() - 1] {
-            b'.' => str::from_utf8(&bytes[..bytes.len() - 1]).expect("&str was valid previously"),
-            _ => &value,
-        };
-
+        let labels = parse_presentation(value.as_bytes())?;
         let mut name = Name::default();
-        for label in labels.rsplit('.') {
-            name.push_label(label.into())?;
+        for label in labels.into_iter().rev() {
+            name.push(label)?;
         }
         Ok(name)
     }
@@ -106,18 +363,9 @@ impl<'a> TryFrom<&'a str> for Name<'a> {
 impl<'a> TryFrom<String> for Name<'a> {
     type Error = NameError;
 
+    #[inline]
     fn try_from(value: String) -> Result<Self, Self::Error> {
-        let bytes = value.as_bytes();
-        let labels = match bytes[bytes.len() - 1] {
-            b'.' => str::from_utf8(&bytes[..bytes.len() - 1]).expect("&str was valid previously"),
-            _ => &value,
-        };
-
-        let mut name = Name::default();
-        for label in labels.rsplit('.') {
-            name.push_label(label.to_owned().into())?;
-        }
-        Ok(name)
+        Name::try_from(value.as_str()).map(Name::into_owned)
     }
 }
 
@@ -131,208 +379,1032 @@ impl<'a> Name<'a> {
     /// to be considered valid. Jump pointers should only point backwards inside the `buf`.
     #[inline]
     pub fn parse(buff: &'a [u8], pos: usize) -> Result<(Self, usize), ParseError> {
+        Self::parse_with(buff, pos, &ParseOptions::default())
+    }
+
+    /// Parse from the specified `buff`, starting at position `pos`, with the given [ParseOptions].
+    ///
+    /// # Errors
+    ///
+    /// It will error if the buffer does not contain a valid domain name for the `options`.
+    #[inline]
+    pub fn parse_with(
+        buff: &'a [u8],
+        pos: usize,
+        options: &ParseOptions,
+    ) -> Result<(Self, usize), ParseError> {
         let mut name = Name::new();
-        let blen = buff.len();
-        let (mut pos, mut size, mut jumps) = (pos, 0, 0);
-        loop {
-            if jumps > MAX_JUMPS {
-                Err(ParseError::ExcesiveJumps(jumps))?;
+        let size = walk(buff, pos, options, |bytes| {
+            name.push_unchecked(Label(Cow::Borrowed(bytes)));
+            Ok(())
+        })?;
+        name.labels.reverse();
+        Ok((name, size))
+    }
+
+    /// Push a label that is known to fit in the domain name.
+    #[inline]
+    fn push_unchecked(&mut self, label: Label<'a>) {
+        // SAFETY: It wont overflow because the caller has checked that the domain name fits in
+        // 255 bytes.
+        self.len += label.0.len() as u8 + 1;
+        self.labels.push(label);
+    }
+
+    /// Serialize the [Name] and append it tho the end of the provided `packet`
+    #[inline]
+    pub fn serialize<W: Writer + ?Sized>(&self, packet: &mut W) {
+        for label in self.iter_human() {
+            packet.put(&[label.0.len() as _]);
+            packet.put(label.as_bytes());
+        }
+        packet.put(&[0u8]);
+    }
+
+    /// Serialize the [Name] in its canonical form (RFC 4034 section 6.2), uncompressed and with
+    /// every ASCII letter in lowercase, and append it tho the end of the provided `packet`.
+    ///
+    /// ```
+    /// # use dominion_parser::body::name::Name;
+    /// let mut packet = Vec::new();
+    /// let name = Name::try_from("WWW.Example.com").unwrap();
+    /// name.serialize_canonical(&mut packet);
+    /// assert_eq!(&packet[..], b"\x03www\x07example\x03com\x00");
+    /// ```
+    #[inline]
+    pub fn serialize_canonical<W: Writer + ?Sized>(&self, packet: &mut W) {
+        for label in self.iter_human() {
+            let label = label.as_bytes();
+            packet.put(&[label.len() as _]);
+            let mut lower = [0; MAX_LABEL_SIZE];
+            let lower = &mut lower[..label.len()];
+            lower.copy_from_slice(label);
+            lower.make_ascii_lowercase();
+            packet.put(lower);
+        }
+        packet.put(&[0u8]);
+    }
+
+    /// Serialize the [Name] and append it tho the end of the provided `packet`, replacing
+    /// the longest suffix already written to the packet with a pointer to it.
+    ///
+    /// The written labels are remembered by the [Compressor] so that following names can
+    /// point to them.
+    ///
+    /// ```
+    /// # use dominion_parser::body::name::{Compressor, Name};
+    /// let mut packet = Vec::new();
+    /// let mut compressor = Compressor::new(0);
+    /// let name = Name::try_from("example.com").unwrap();
+    /// let sub = Name::try_from("www.example.com").unwrap();
+    ///
+    /// name.serialize_compressed(&mut packet, &mut compressor);
+    /// sub.serialize_compressed(&mut packet, &mut compressor);
+    /// assert_eq!(&packet[13..], &[3, b'w', b'w', b'w', 0b1100_0000, 0]);
+    /// ```
+    #[inline]
+    pub fn serialize_compressed<W: Writer + ?Sized>(
+        &self,
+        packet: &mut W,
+        compressor: &mut Compressor,
+    ) {
+        let count = self.labels.len();
+        for (i, label) in self.iter_human().enumerate() {
+            let suffix = &self.labels[..count - i];
+            let ptr = packet
+                .written_mut()
+                .and_then(|written| compressor.find(written, suffix));
+            if let Some(ptr) = ptr {
+                push_u16(packet, ptr | 0b1100_0000_0000_0000);
+                return;
             }
-            match read_label_metadata(buff, pos)? {
-                LabelMeta::Pointer(ptr) if ptr >= pos => Err(ParseError::InvalidJump)?,
-                LabelMeta::Size(s) if s > MAX_LABEL_SIZE => Err(NameError::LabelLength(s))?,
-                LabelMeta::Size(s) if blen <= pos + s => Err(NameError::LabelLength(s))?,
-                LabelMeta::Size(s) if name.len as usize + s > MAX_NAME_SIZE => {
-                    Err(NameError::NameLength(name.len as usize + s))?
-                }
-                LabelMeta::Size(s) if jumps == 0 => {
-                    name.push_bytes(&buff[pos + 1..pos + s + 1])?;
-                    pos += s + 1;
-                    size += s + 1;
-                }
-                LabelMeta::Size(s) => {
-                    name.push_bytes(&buff[pos + 1..pos + s + 1])?;
-                    pos += s + 1;
-                }
-                LabelMeta::Pointer(ptr) if jumps == 0 => {
-                    (pos, size, jumps) = (ptr, size + 2, jumps + 1);
-                }
-                LabelMeta::Pointer(ptr) => (pos, jumps) = (ptr, jumps + 1),
-                LabelMeta::End if jumps == 0 => {
-                    name.labels.reverse();
-                    return Ok((name, size + 1));
+            compressor.insert(packet.position());
+            packet.put(&[label.0.len() as _]);
+            packet.put(label.as_bytes());
+        }
+        packet.put(&[0u8]);
+    }
+
+    /// Create a new, empty, domain name, which is the root domain name.
+    ///
+    /// ```
+    /// # use dominion_parser::body::name::Name;
+    /// let name = Name::new();
+    /// assert_eq!(name.to_string(), ".".to_string())
+    /// ```
+    #[inline]
+    pub fn new() -> Self {
+        Name {
+            labels: Vec::with_capacity(INIT_NUM_LABELS),
+            len: 0,
+        }
+    }
+
+    /// Obtain the top level domain (TLD) of the provided domain name.
+    ///
+    /// ```
+    /// # use dominion_parser::body::name::Name;
+    /// let mut name = Name::try_from("example.com").unwrap();
+    /// assert_eq!(name.tld().unwrap(), "com")
+    /// ```
+    #[inline]
+    pub fn tld(&self) -> Option<&'_ Label<'a>> {
+        self.labels.first()
+    }
+
+    /// Push a new label to the end of the domain name, as a subdomain of the current one.
+    ///
+    /// # Error
+    ///
+    /// Will error if the label is not a valid DNS label, or if the resulting Domain name is too big.
+    ///
+    /// ```
+    /// # use dominion_parser::body::name::Name;
+    /// let mut name = Name::new();
+    /// name.push_label("com".into()).unwrap();
+    /// name.push_label("example".into()).unwrap();
+    /// assert_eq!(name.to_string(), "example.com.".to_string())
+    /// ```
+    #[inline]
+    pub fn push_label(&mut self, label: Cow<'a, str>) -> Result<(), NameError> {
+        let label = match label {
+            Cow::Borrowed(label) => Label::try_from(label)?,
+            Cow::Owned(label) => Label::try_from(label)?,
+        };
+        if !valid_label(label.as_bytes()) {
+            Err(NameError::LabelContent)?
+        }
+        self.push(label)
+    }
+
+    /// Push a [Label] to the end of the domain name, as a subdomain of the current one. Unlike
+    /// [Name::push_label] the label can contain any octet.
+    ///
+    /// # Error
+    ///
+    /// Will error if the resulting Domain name is too big.
+    ///
+    /// ```
+    /// # use dominion_parser::body::name::{Label, Name};
+    /// let mut name = Name::try_from("example.com").unwrap();
+    /// name.push(Label::try_from("with space").unwrap()).unwrap();
+    /// assert_eq!(name.to_string(), "with\\032space.example.com.".to_string())
+    /// ```
+    #[inline]
+    pub fn push(&mut self, label: Label<'a>) -> Result<(), NameError> {
+        let size = self.size() + label.0.len() + 1;
+        if size > MAX_NAME_SIZE {
+            Err(NameError::NameLength(size))?
+        }
+        self.push_unchecked(label);
+        Ok(())
+    }
+
+    /// Get the number of labels in the domain name.
+    ///
+    /// ```
+    /// # use dominion_parser::body::name::Name;
+    /// let mut name = Name::try_from("example.com").unwrap();
+    /// assert_eq!(2, name.label_count())
+    /// ```
+    #[inline]
+    pub fn label_count(&self) -> usize {
+        self.labels.len()
+    }
+
+    /// Get the size of the domain name in bytes.
+    ///
+    /// ```
+    /// # use dominion_parser::body::name::Name;
+    /// let mut name = Name::try_from("example.com").unwrap();
+    /// assert_eq!(13, name.size())
+    /// ```
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.len as usize + 1
+    }
+
+    /// Check if `sub` is a subdomain of the current domain name.
+    ///
+    /// ```
+    /// # use dominion_parser::body::name::Name;
+    /// let mut name = Name::try_from("example.com").unwrap();
+    /// let mut sub = Name::try_from("subdomain.example.com").unwrap();
+    ///
+    /// assert!(name.is_subdomain(&sub))
+    /// ```
+    #[inline]
+    pub fn is_subdomain(&self, sub: &Name<'_>) -> bool {
+        if self.labels.len() >= sub.labels.len() {
+            false
+        } else {
+            zip(self.iter_hierarchy(), sub.iter_hierarchy()).all(|(x, y)| x == y)
+        }
+    }
+
+    /// Get the label at position `i`, in human order.
+    ///
+    /// ```
+    /// # use dominion_parser::body::name::Name;
+    /// let name = Name::try_from("www.example.com").unwrap();
+    /// assert_eq!(name.get(0).unwrap(), "www");
+    /// assert_eq!(name[2], "com");
+    /// assert!(name.get(3).is_none());
+    /// ```
+    #[inline]
+    pub fn get(&self, i: usize) -> Option<&Label<'a>> {
+        let count = self.labels.len();
+        i.checked_add(1)
+            .and_then(|n| count.checked_sub(n))
+            .map(|i| &self.labels[i])
+    }
+
+    /// Obtain the parent of the domain name, removing its first label. The root domain name
+    /// does not have a parent.
+    ///
+    /// ```
+    /// # use dominion_parser::body::name::Name;
+    /// let name = Name::try_from("www.example.com").unwrap();
+    /// assert_eq!(name.parent().unwrap().to_string(), "example.com.");
+    /// assert!(Name::new().parent().is_none());
+    /// ```
+    #[inline]
+    pub fn parent(&self) -> Option<Name<'a>> {
+        let (_, labels) = self.labels.split_last()?;
+        Some(Name::from_labels(labels.to_vec()))
+    }
+
+    /// Append `suffix` to the end of the domain name, which becomes a subdomain of it. This is
+    /// useful to make relative names absolute, for example with the origin of a zone.
+    ///
+    /// # Error
+    ///
+    /// Will error if the resulting Domain name is too big, leaving the domain name unchanged.
+    ///
+    /// ```
+    /// # use dominion_parser::body::name::Name;
+    /// let mut name = Name::try_from("www").unwrap();
+    /// name.append(&Name::try_from("example.com").unwrap()).unwrap();
+    /// assert_eq!(name.to_string(), "www.example.com.");
+    /// ```
+    #[inline]
+    pub fn append(&mut self, suffix: &Name<'a>) -> Result<(), NameError> {
+        let size = self.size() + suffix.len as usize;
+        if size > MAX_NAME_SIZE {
+            Err(NameError::NameLength(size))?
+        }
+        self.labels.splice(0..0, suffix.labels.iter().cloned());
+        // SAFETY: It wont overflow because we have checked that the domain name length is not
+        // bigger than 255.
+        self.len += suffix.len;
+        Ok(())
+    }
+
+    /// Remove `suffix` from the end of the domain name, returning the name relative to it. It
+    /// will return `None` if the domain name is not `suffix` or a subdomain of it.
+    ///
+    /// ```
+    /// # use dominion_parser::body::name::Name;
+    /// let name = Name::try_from("www.Example.com").unwrap();
+    /// let origin = Name::try_from("example.com").unwrap();
+    ///
+    /// let relative = name.strip_suffix(&origin).unwrap();
+    /// assert_eq!(relative.to_string(), "www");
+    /// assert_eq!(relative.label_count(), 1);
+    /// assert_eq!(relative.to_absolute(&origin).unwrap(), name);
+    /// assert!(origin.strip_suffix(&name).is_none());
+    /// ```
+    #[inline]
+    pub fn strip_suffix(&self, suffix: &Name<'_>) -> Option<RelativeName<'a>> {
+        let count = suffix.label_count();
+        if count > self.label_count()
+            || zip(self.iter_hierarchy(), suffix.iter_hierarchy()).any(|(x, y)| x != y)
+        {
+            return None;
+        }
+        Some(RelativeName {
+            labels: self.labels[count..].to_vec(),
+        })
+    }
+
+    /// Obtain the closest common ancestor of two domain names, which is the root domain name if
+    /// they do not have any common suffix.
+    ///
+    /// ```
+    /// # use dominion_parser::body::name::Name;
+    /// let a = Name::try_from("www.example.com").unwrap();
+    /// let b = Name::try_from("mail.EXAMPLE.com").unwrap();
+    /// assert_eq!(a.common_ancestor(&b).to_string(), "example.com.");
+    ///
+    /// let c = Name::try_from("example.org").unwrap();
+    /// assert_eq!(a.common_ancestor(&c).to_string(), ".");
+    /// ```
+    #[inline]
+    pub fn common_ancestor(&self, other: &Name<'_>) -> Name<'a> {
+        let count = zip(self.iter_hierarchy(), other.iter_hierarchy())
+            .take_while(|(x, y)| x == y)
+            .count();
+        Name::from_labels(self.labels[..count].to_vec())
+    }
+
+    /// Check if the domain name is a wildcard, where the first label is `*` (RFC 4592).
+    ///
+    /// ```
+    /// # use dominion_parser::body::name::Name;
+    /// assert!(Name::try_from("*.example.com").unwrap().is_wildcard());
+    /// assert!(!Name::try_from("www.*.example.com").unwrap().is_wildcard());
+    /// ```
+    #[inline]
+    pub fn is_wildcard(&self) -> bool {
+        self.labels.last().is_some_and(|l| l.as_bytes() == b"*")
+    }
+
+    /// Check if the domain name is a wildcard that matches `name`, which has to be a subdomain
+    /// of the parent of the wildcard.
+    ///
+    /// ```
+    /// # use dominion_parser::body::name::Name;
+    /// let wildcard = Name::try_from("*.example.com").unwrap();
+    /// assert!(wildcard.matches_wildcard(&Name::try_from("www.example.com").unwrap()));
+    /// assert!(wildcard.matches_wildcard(&Name::try_from("a.b.example.com").unwrap()));
+    /// assert!(!wildcard.matches_wildcard(&Name::try_from("example.com").unwrap()));
+    /// ```
+    #[inline]
+    pub fn matches_wildcard(&self, name: &Name<'_>) -> bool {
+        let count = self.label_count();
+        self.is_wildcard()
+            && name.label_count() >= count
+            && zip(self.iter_hierarchy(), name.iter_hierarchy())
+                .take(count - 1)
+                .all(|(x, y)| x == y)
+    }
+
+    /// Create a domain name from its labels in hierarchical order, known to fit in 255 bytes.
+    #[inline]
+    fn from_labels(labels: Vec<Label<'a>>) -> Self {
+        // SAFETY: It wont overflow because the labels come from a valid domain name.
+        let len = labels.iter().map(|l| l.0.len() as u8 + 1).sum();
+        Name { labels, len }
+    }
+
+    /// Check if the domain name is a valid hostname (RFC 952 and RFC 1123), where every label
+    /// only contains letters, digits and `-`, and does not begin or end with a `-`.
+    ///
+    /// ```
+    /// # use dominion_parser::body::name::Name;
+    /// let name = Name::try_from("www.example.com").unwrap();
+    /// assert!(name.is_hostname());
+    ///
+    /// let name = Name::try_from("_sip._tcp.example.com").unwrap();
+    /// assert!(!name.is_hostname());
+    /// ```
+    #[inline]
+    pub fn is_hostname(&self) -> bool {
+        self.iter_hierarchy()
+            .all(|label| valid_hostname_label(label.as_bytes()))
+    }
+
+    /// Create the domain name used for reverse lookups of the provided IP address, under the
+    /// `in-addr.arpa` domain for IPv4 and under the `ip6.arpa` domain for IPv6.
+    ///
+    /// ```
+    /// # use dominion_parser::body::name::Name;
+    /// let name = Name::from_reverse_ip("192.0.2.1".parse().unwrap());
+    /// assert_eq!(name.to_string(), "1.2.0.192.in-addr.arpa.".to_string());
+    ///
+    /// let name = Name::from_reverse_ip("2001:db8::1".parse().unwrap());
+    /// assert_eq!(
+    ///     name.to_string(),
+    ///     "1.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.8.b.d.0.1.0.0.2.ip6.arpa.".to_string()
+    /// );
+    /// ```
+    pub fn from_reverse_ip(ip: IpAddr) -> Self {
+        let mut name = Name::new();
+        let (suffix, labels): (_, Vec<String>) = match ip {
+            IpAddr::V4(ip) => (REVERSE_V4, ip.octets().iter().map(u8::to_string).collect()),
+            IpAddr::V6(ip) => (
+                REVERSE_V6,
+                ip.octets()
+                    .iter()
+                    .flat_map(|b| [b >> 4, b & 0x0F])
+                    .map(|n| format!("{n:x}"))
+                    .collect(),
+            ),
+        };
+        for label in suffix {
+            name.push_label(label.into())
+                .expect("reverse lookup domain labels are valid");
+        }
+        for label in labels {
+            name.push_label(label.into())
+                .expect("reverse lookup domain labels are valid");
+        }
+        name
+    }
+
+    /// Obtain the IP address of a reverse lookup domain name. It will return `None` if the
+    /// domain name is not a complete `in-addr.arpa` or `ip6.arpa` name.
+    ///
+    /// ```
+    /// # use dominion_parser::body::name::Name;
+    /// let name = Name::try_from("1.2.0.192.in-addr.arpa").unwrap();
+    /// assert_eq!(name.to_reverse_ip(), Some("192.0.2.1".parse().unwrap()));
+    ///
+    /// let name = Name::try_from("2.0.192.in-addr.arpa").unwrap();
+    /// assert_eq!(name.to_reverse_ip(), None);
+    /// ```
+    pub fn to_reverse_ip(&self) -> Option<IpAddr> {
+        let has_suffix =
+            |suffix: [&str; 2]| zip(self.iter_hierarchy(), suffix).all(|(l, s)| l == s);
+        match self.labels.len() {
+            6 if has_suffix(REVERSE_V4) => {
+                let mut octets = [0u8; 4];
+                for (octet, label) in zip(&mut octets, self.iter_hierarchy().skip(2)) {
+                    let label = label.as_str()?;
+                    // Only plain decimal octets, `parse` also accepts a leading `+`.
+                    if !label.bytes().all(|b| b.is_ascii_digit())
+                        || label.len() > 1 && label.starts_with('0')
+                    {
+                        return None;
+                    }
+                    *octet = label.parse().ok()?;
                 }
-                LabelMeta::End => {
-                    name.labels.reverse();
-                    return Ok((name, size));
+                Some(Ipv4Addr::from(octets).into())
+            }
+            34 if has_suffix(REVERSE_V6) => {
+                let mut octets = [0u8; 16];
+                let mut nibbles = self.iter_hierarchy().skip(2).map(|label| {
+                    let mut chars = label.as_bytes().iter().map(|&b| b as char);
+                    match (chars.next().and_then(|c| c.to_digit(16)), chars.next()) {
+                        (Some(n), None) => Some(n as u8),
+                        _ => None,
+                    }
+                });
+                for octet in &mut octets {
+                    let high = nibbles.next()??;
+                    let low = nibbles.next()??;
+                    *octet = (high << 4) | low;
                 }
+                Some(Ipv6Addr::from(octets).into())
             }
+            _ => None,
         }
     }
 
-    /// Safely push a slice of bytes as as a subdomain label.
-    fn push_bytes(&mut self, bytes: &'a [u8]) -> Result<(), NameError> {
-        if valid_label(bytes) {
-            // SAFETY: Because we have verified that the label is only ASCII alphanumeric + `-`
-            // we now the label is valid UTF8.
-            let label = unsafe { str::from_utf8_unchecked(bytes) };
-            self.labels.push(label.into());
-            // SAFETY: It wont overflow because valid labels have a length that fits in one byte.
-            self.len += bytes.len() as u8 + 1;
-            Ok(())
-        } else {
-            Err(NameError::LabelContent)
+    /// Create a domain name from Unicode text (requires the `idna` feature), applying the
+    /// UTS #46 mapping and encoding the labels that are not ASCII with punycode.
+    ///
+    /// Unlike [Name::try_from] escape sequences are not decoded, the text is only split at
+    /// the label separators.
+    ///
+    /// # Errors
+    ///
+    /// It will error if the text is not a valid internationalized domain name, or if it is not
+    /// a valid domain name after the conversion.
+    ///
+    /// ```
+    /// # use dominion_parser::body::name::Name;
+    /// let name = Name::from_unicode("BÜCHER.example").unwrap();
+    /// assert_eq!(name.to_string(), "xn--bcher-kva.example.");
+    /// ```
+    #[cfg(feature = "idna")]
+    pub fn from_unicode(text: &str) -> Result<Self, NameError> {
+        let ascii = idna::domain_to_ascii(text).map_err(|_| NameError::Idna)?;
+        let mut name = Name::new();
+        let labels = ascii.strip_suffix('.').unwrap_or(&ascii);
+        if labels.is_empty() && !ascii.is_empty() {
+            return Ok(name);
+        }
+        for label in labels.rsplit('.') {
+            name.push(Label::try_from(label.to_string())?)?;
+        }
+        Ok(name)
+    }
+
+    /// Display the domain name with the punycode labels (A-labels) decoded into Unicode
+    /// (requires the `idna` feature). The labels that are not valid A-labels are displayed
+    /// as with [Display](fmt::Display).
+    ///
+    /// ```
+    /// # use dominion_parser::body::name::Name;
+    /// let name = Name::try_from("xn--bcher-kva.Example").unwrap();
+    /// assert_eq!(name.to_unicode(), "bücher.Example.");
+    /// ```
+    #[cfg(feature = "idna")]
+    pub fn to_unicode(&self) -> String {
+        if self.labels.is_empty() {
+            return ".".to_string();
+        }
+        let mut out = String::with_capacity(self.size());
+        for label in self.iter_human() {
+            let unicode = label
+                .as_str()
+                .filter(|l| l.get(..4).is_some_and(|p| p.eq_ignore_ascii_case("xn--")))
+                .map(idna::domain_to_unicode);
+            match unicode {
+                Some((unicode, Ok(()))) if !unicode.contains('.') => out.push_str(&unicode),
+                _ => out.push_str(&label.to_string()),
+            }
+            out.push('.');
+        }
+        out
+    }
+
+    /// Return an iterator over the labels in human order.
+    ///
+    /// ```
+    /// # use dominion_parser::body::name::Name;
+    /// let mut name = Name::try_from("subdomain.example.com").unwrap();
+    /// let mut human = name.iter_human();
+    ///
+    /// assert_eq!(human.next().unwrap(), "subdomain");
+    /// assert_eq!(human.next().unwrap(), "example");
+    /// assert_eq!(human.next().unwrap(), "com");
+    /// ```
+    #[inline]
+    pub fn iter_human(&self) -> impl DoubleEndedIterator<Item = &'_ Label<'a>> {
+        self.iter_hierarchy().rev()
+    }
+
+    /// Return an iterator over the labels in hierarchical order.
+    ///
+    /// ```
+    /// # use dominion_parser::body::name::Name;
+    /// let mut name = Name::try_from("subdomain.example.com").unwrap();
+    /// let mut hierarchy = name.iter_hierarchy();
+    ///
+    /// assert_eq!(hierarchy.next().unwrap(), "com");
+    /// assert_eq!(hierarchy.next().unwrap(), "example");
+    /// assert_eq!(hierarchy.next().unwrap(), "subdomain");
+    /// ```
+    #[inline]
+    pub fn iter_hierarchy(&self) -> impl DoubleEndedIterator<Item = &'_ Label<'a>> {
+        self.labels.iter()
+    }
+
+    /// Copy the labels that are borrowed, so that the domain name does not depend on the
+    /// original buffer.
+    #[inline]
+    pub fn into_owned(self) -> Name<'static> {
+        Name {
+            labels: self.labels.into_iter().map(Label::into_owned).collect(),
+            len: self.len,
+        }
+    }
+}
+
+/// A domain name relative to an origin, obtained from [Name::strip_suffix].
+///
+/// It is not a complete domain name, so it can not be serialized. Use
+/// [RelativeName::to_absolute] to append an origin to it.
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct RelativeName<'a> {
+    /// Domain name labels
+    labels: Vec<Label<'a>>,
+}
+
+/// Relative names are displayed in the presentation format of RFC 1035 section 5.1, without a
+/// final `.`. A relative name without labels is the origin itself and is displayed as `@`.
+impl fmt::Display for RelativeName<'_> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.labels.is_empty() {
+            return write!(f, "@");
+        }
+        let mut labels = self.iter_human();
+        if let Some(l) = labels.next() {
+            write!(f, "{}", l)?;
         }
+        for l in labels {
+            write!(f, ".{}", l)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for RelativeName<'_> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl<'a> RelativeName<'a> {
+    /// Get the number of labels of the relative name.
+    #[inline]
+    pub fn label_count(&self) -> usize {
+        self.labels.len()
+    }
+
+    /// Create a domain name appending `origin` to the relative name.
+    ///
+    /// Will error if the resulting domain name is too big.
+    #[inline]
+    pub fn to_absolute(&self, origin: &Name<'a>) -> Result<Name<'a>, NameError> {
+        let mut name = Name::from_labels(self.labels.clone());
+        name.append(origin)?;
+        Ok(name)
+    }
+
+    /// Return an iterator over the labels in human order.
+    #[inline]
+    pub fn iter_human(&self) -> impl DoubleEndedIterator<Item = &'_ Label<'a>> {
+        self.iter_hierarchy().rev()
+    }
+
+    /// Return an iterator over the labels in hierarchical order.
+    #[inline]
+    pub fn iter_hierarchy(&self) -> impl DoubleEndedIterator<Item = &'_ Label<'a>> {
+        self.labels.iter()
+    }
+
+    /// Copy the labels that are borrowed, so that the relative name does not depend on the
+    /// original buffer.
+    #[inline]
+    pub fn into_owned(self) -> RelativeName<'static> {
+        RelativeName {
+            labels: self.labels.into_iter().map(Label::into_owned).collect(),
+        }
+    }
+}
+
+/// A domain name borrowed from a DNS packet, obtained from a
+/// [DnsPacketView](crate::view::DnsPacketView).
+///
+/// The labels are not copied, they are read from the packet when needed following the
+/// compression pointers. The name has been validated when the packet view was created.
+#[derive(Clone, Copy)]
+pub struct NameView<'a> {
+    buff: &'a [u8],
+    pos: usize,
+}
+
+impl fmt::Display for NameView<'_> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut labels = self.labels().peekable();
+        if labels.peek().is_none() {
+            return write!(f, ".");
+        }
+        for l in labels {
+            write!(f, "{}.", l)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for NameView<'_> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl<'a> From<NameView<'a>> for Name<'a> {
+    #[inline]
+    fn from(view: NameView<'a>) -> Self {
+        view.to_name()
+    }
+}
+
+impl<'a> NameView<'a> {
+    /// Validate the domain name starting at `pos`, returning it together with its size at
+    /// that position.
+    #[inline]
+    pub(crate) fn parse(buff: &'a [u8], pos: usize) -> Result<(Self, usize), ParseError> {
+        let size = walk(buff, pos, &ParseOptions::default(), |_| Ok(()))?;
+        Ok((NameView { buff, pos }, size))
     }
 
-    /// Serialize the [Name] and append it tho the end of the provided `packet`
+    /// Obtain a domain name that has already been validated, together with its size at `pos`.
     #[inline]
-    pub fn serialize(&self, packet: &mut Vec<u8>) {
-        for label in self.iter_human() {
-            packet.push(label.len() as _);
-            packet.extend(label.as_bytes());
+    pub(crate) fn parse_unchecked(buff: &'a [u8], pos: usize) -> (Self, usize) {
+        let mut size = 0;
+        loop {
+            match buff.get(pos + size).copied().unwrap_or_default() {
+                0 => return (NameView { buff, pos }, size + 1),
+                0b1100_0000..=0xFF => return (NameView { buff, pos }, size + 2),
+                s => size += s as usize + 1,
+            }
         }
-        packet.push(0u8);
     }
 
-    /// Create a new, empty, domain name.
+    /// Return an iterator over the labels in human order, reading them from the packet.
     ///
     /// ```
-    /// # use dominion_parser::body::name::Name;
-    /// let name = Name::new();
-    /// assert_eq!(name.to_string(), "".to_string())
+    /// # use dominion_parser::view::DnsPacketView;
+    /// # const REQ: &'static [u8; 33] = include_bytes!("../../assets/dns_request.bin");
+    /// let packet = DnsPacketView::try_from(&REQ[..]).unwrap();
+    /// let name = packet.questions().next().unwrap().name();
+    /// let mut human = name.labels();
+    ///
+    /// assert_eq!(human.next().unwrap(), "hello");
+    /// assert_eq!(human.next().unwrap(), "world");
+    /// assert_eq!(human.next().unwrap(), "com");
+    /// assert!(human.next().is_none());
     /// ```
     #[inline]
-    pub fn new() -> Self {
-        Name {
-            labels: Vec::with_capacity(INIT_NUM_LABELS),
-            len: 0,
+    pub fn labels(&self) -> Labels<'a> {
+        Labels {
+            buff: self.buff,
+            pos: self.pos,
         }
     }
 
-    /// Obtain the top level domain (TLD) of the provided domain name.
-    ///
-    /// ```
-    /// # use dominion_parser::body::name::Name;
-    /// let mut name = Name::try_from("example.com").unwrap();
-    /// assert_eq!(name.tld(), Some("com"))
-    /// ```
+    /// Get the number of labels of the domain name.
     #[inline]
-    pub fn tld(&self) -> Option<&'_ str> {
-        self.labels.first().map(|cow| cow.deref())
+    pub fn label_count(&self) -> usize {
+        self.labels().count()
     }
 
-    /// Push a new label to the end of the domain name, as a subdomain of the current one.
-    ///
-    /// # Error
-    ///
-    /// Will error if the label is not a valid DNS label, or if the resulting Domain name is too big.
+    /// Check if this is the same domain name as `name`, ignoring the case of ASCII letters.
     ///
     /// ```
     /// # use dominion_parser::body::name::Name;
-    /// let mut name = Name::new();
-    /// name.push_label("com".into()).unwrap();
-    /// name.push_label("example".into()).unwrap();
-    /// assert_eq!(name.to_string(), "example.com.".to_string())
+    /// # use dominion_parser::view::DnsPacketView;
+    /// # const REQ: &'static [u8; 33] = include_bytes!("../../assets/dns_request.bin");
+    /// let packet = DnsPacketView::try_from(&REQ[..]).unwrap();
+    /// let name = packet.questions().next().unwrap().name();
+    ///
+    /// assert!(name.matches(&Name::try_from("HELLO.world.com").unwrap()));
     /// ```
     #[inline]
-    pub fn push_label(&mut self, label: Cow<'a, str>) -> Result<(), NameError> {
-        let len = label.len();
-        if label.is_empty() || len > MAX_LABEL_SIZE {
-            Err(NameError::LabelLength(len))
-        } else if len + self.len as usize > MAX_NAME_SIZE {
-            Err(NameError::NameLength(len + self.len as usize))
-        } else if !valid_label(label.as_bytes()) {
-            Err(NameError::LabelContent)
-        } else {
-            // SAFETY: It wont overflow because we have checked that the domain name length is not bigger than 255.
-            self.len += len as u8;
-            self.labels.push(label);
-            Ok(())
+    pub fn matches(&self, name: &Name<'_>) -> bool {
+        let mut labels = self.labels();
+        name.iter_human()
+            .all(|l| labels.next().is_some_and(|x| x == *l))
+            && labels.next().is_none()
+    }
+
+    /// Copy the labels into an owned [Name], which still borrows the text from the packet.
+    #[inline]
+    pub fn to_name(&self) -> Name<'a> {
+        let mut name = Name::new();
+        for label in self.labels() {
+            name.push_unchecked(label);
         }
+        name.labels.reverse();
+        name
     }
+}
+
+/// An iterator over the labels of a [NameView], in human order.
+#[derive(Debug, Clone)]
+pub struct Labels<'a> {
+    buff: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Iterator for Labels<'a> {
+    type Item = Label<'a>;
 
-    /// Get the number of labels in the domain name.
-    ///
-    /// ```
-    /// # use dominion_parser::body::name::Name;
-    /// let mut name = Name::try_from("example.com").unwrap();
-    /// assert_eq!(2, name.label_count())
-    /// ```
     #[inline]
-    pub fn label_count(&self) -> usize {
-        self.labels.len()
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match read_label_metadata(self.buff, self.pos).ok()? {
+                LabelMeta::End => return None,
+                LabelMeta::Pointer(ptr) => self.pos = ptr,
+                LabelMeta::Size(s) => {
+                    let bytes = self.buff.get(self.pos + 1..self.pos + s + 1)?;
+                    self.pos += s + 1;
+                    return Some(Label(Cow::Borrowed(bytes)));
+                }
+            }
+        }
     }
+}
 
-    /// Get the size of the domain name in bytes.
-    ///
-    /// ```
-    /// # use dominion_parser::body::name::Name;
-    /// let mut name = Name::try_from("example.com").unwrap();
-    /// assert_eq!(13, name.size())
-    /// ```
+/// Remembers the position of the domain names written to a DNS packet so they can be
+/// reused by later names in the same packet, as described in RFC 1035 section 4.1.4.
+///
+/// A [Compressor] should only be used with a single packet, and it should not be used
+/// for names in positions where compression is not allowed (for example inside the
+/// RDATA of record types defined after RFC 1035).
+#[derive(Debug, Clone)]
+pub struct Compressor {
+    /// Position in the buffer where the DNS packet starts.
+    start: usize,
+    /// Offsets from the start of the packet of every label that can be pointed to.
+    offsets: Vec<u16>,
+    /// Whether names should be compressed at all.
+    enabled: bool,
+}
+
+impl Default for Compressor {
     #[inline]
-    pub fn size(&self) -> usize {
-        self.len as usize + self.label_count() + 1
+    fn default() -> Self {
+        Self::new(0)
     }
+}
 
-    /// Check if `sub` is a subdomain of the current domain name.
-    ///
-    /// ```
-    /// # use dominion_parser::body::name::Name;
-    /// let mut name = Name::try_from("example.com").unwrap();
-    /// let mut sub = Name::try_from("subdomain.example.com").unwrap();
-    ///
-    /// assert!(name.is_subdomain(&sub))
-    /// ```
+impl Compressor {
+    /// Create a new [Compressor] for a packet that begins at position `start` of the buffer.
     #[inline]
-    pub fn is_subdomain(&self, sub: &Name<'_>) -> bool {
-        if self.labels.len() >= sub.labels.len() {
-            false
-        } else {
-            zip(self.iter_hierarchy(), sub.iter_hierarchy())
-                .fold(true, |acc, (x, y)| acc && x.eq_ignore_ascii_case(y))
+    pub fn new(start: usize) -> Self {
+        Compressor {
+            start,
+            offsets: Vec::with_capacity(INIT_NUM_LABELS),
+            enabled: true,
         }
     }
 
-    /// Return an iterator over the labels in human order.
-    ///
-    /// ```
-    /// # use dominion_parser::body::name::Name;
-    /// let mut name = Name::try_from("subdomain.example.com").unwrap();
-    /// let mut human = name.iter_human();
-    ///
-    /// assert_eq!(human.next(), Some("subdomain"));
-    /// assert_eq!(human.next(), Some("example"));
-    /// assert_eq!(human.next(), Some("com"));
-    /// ```
+    /// Create a [Compressor] that never compresses any name.
     #[inline]
-    pub fn iter_human(&self) -> impl DoubleEndedIterator<Item = &'_ str> {
-        self.iter_hierarchy().rev()
+    pub fn disabled() -> Self {
+        Compressor {
+            start: 0,
+            offsets: Vec::new(),
+            enabled: false,
+        }
     }
 
-    /// Return an iterator over the labels in hierarchical order.
-    ///
-    /// ```
-    /// # use dominion_parser::body::name::Name;
-    /// let mut name = Name::try_from("subdomain.example.com").unwrap();
-    /// let mut hierarchy = name.iter_hierarchy();
-    ///
-    /// assert_eq!(hierarchy.next(), Some("com"));
-    /// assert_eq!(hierarchy.next(), Some("example"));
-    /// assert_eq!(hierarchy.next(), Some("subdomain"));
-    /// ```
+    /// Remember a label written at position `pos` of the buffer.
+    #[inline]
+    fn insert(&mut self, pos: usize) {
+        if self.enabled && pos >= self.start && pos - self.start <= MAX_POINTER_OFFSET {
+            self.offsets.push((pos - self.start) as u16);
+        }
+    }
+
+    /// Find the offset of a previously written name equal to `suffix` (in hierarchical order).
     #[inline]
-    pub fn iter_hierarchy(&self) -> impl DoubleEndedIterator<Item = &'_ str> {
-        self.labels.iter().map(|cow| cow.deref())
+    fn find(&self, packet: &[u8], suffix: &[Label<'_>]) -> Option<u16> {
+        if !self.enabled {
+            return None;
+        }
+        self.offsets
+            .iter()
+            .copied()
+            .find(|&offset| self.matches(packet, offset, suffix))
+    }
+
+    /// Check if the name written at `offset` is equal to the labels in `suffix`.
+    fn matches(&self, packet: &[u8], offset: u16, suffix: &[Label<'_>]) -> bool {
+        let mut pos = self.start + offset as usize;
+        let mut labels = suffix.iter().rev();
+        loop {
+            match packet.get(pos) {
+                Some(0) => return labels.next().is_none(),
+                Some(&b) if b & 0b1100_0000 == 0b1100_0000 => match packet.get(pos + 1) {
+                    Some(&low) => {
+                        let ptr = u16::from_be_bytes([b & 0b0011_1111, low]) as usize;
+                        pos = self.start + ptr;
+                    }
+                    None => return false,
+                },
+                Some(&len) => {
+                    let len = len as usize;
+                    let (Some(label), Some(written)) =
+                        (labels.next(), packet.get(pos + 1..pos + 1 + len))
+                    else {
+                        return false;
+                    };
+                    if !label.as_bytes().eq_ignore_ascii_case(written) {
+                        return false;
+                    }
+                    pos += len + 1;
+                }
+                None => return false,
+            }
+        }
     }
 }
 
-/// A label can only contain a `-` or alphanumeric characters, and must begin with a letter.
-fn valid_label(label: &[u8]) -> bool {
-    label
+/// Walk the labels of the domain name starting at `pos`, following the compression pointers,
+/// and return the size of the name at that position.
+#[inline]
+fn walk<'a>(
+    buff: &'a [u8],
+    pos: usize,
+    options: &ParseOptions,
+    mut label: impl FnMut(&'a [u8]) -> Result<(), NameError>,
+) -> Result<usize, ParseError> {
+    let blen = buff.len();
+    let (mut pos, mut size, mut jumps, mut len) = (pos, 0, 0, 0);
+    loop {
+        match read_label_metadata(buff, pos)? {
+            LabelMeta::Pointer(_) if jumps == options.max_jumps => {
+                Err(ParseError::ExcesiveJumps(jumps))?
+            }
+            LabelMeta::Pointer(ptr) if ptr >= pos && !options.forward_pointers => {
+                Err(ParseError::InvalidJump)?
+            }
+            LabelMeta::Size(s) if s > MAX_LABEL_SIZE => Err(NameError::LabelLength(s))?,
+            LabelMeta::Size(s) if blen <= pos + s => Err(NameError::LabelLength(s))?,
+            // The size in the wire format includes the length octets and the final root label.
+            LabelMeta::Size(s) if len + s + 2 > MAX_NAME_SIZE => {
+                Err(NameError::NameLength(len + s + 2))?
+            }
+            LabelMeta::Size(s) => {
+                label(&buff[pos + 1..pos + s + 1])?;
+                if jumps == 0 {
+                    size += s + 1;
+                }
+                pos += s + 1;
+                len += s + 1;
+            }
+            LabelMeta::Pointer(ptr) if jumps == 0 => {
+                (pos, size, jumps) = (ptr, size + 2, jumps + 1);
+            }
+            LabelMeta::Pointer(ptr) => (pos, jumps) = (ptr, jumps + 1),
+            LabelMeta::End if jumps == 0 => return Ok(size + 1),
+            LabelMeta::End => return Ok(size),
+        }
+    }
+}
+
+/// Compare two names using the canonical ordering of RFC 4034 section 6.1.
+fn canonical_cmp(a: &Name<'_>, b: &Name<'_>) -> Ordering {
+    for (x, y) in zip(a.iter_hierarchy(), b.iter_hierarchy()) {
+        let ordering = canonical_label_cmp(x, y);
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    a.label_count().cmp(&b.label_count())
+}
+
+/// Compare two labels as octet strings with the ASCII letters in lowercase.
+fn canonical_label_cmp(a: &Label<'_>, b: &Label<'_>) -> Ordering {
+    a.as_bytes()
         .iter()
-        .all(|&b| b.is_ascii_alphanumeric() || b == b'-')
+        .map(u8::to_ascii_lowercase)
+        .cmp(b.as_bytes().iter().map(u8::to_ascii_lowercase))
+}
+
+/// Split a domain name in presentation format into its labels in human order, decoding the
+/// escape sequences. Octets that are not printable ASCII have to be escaped.
+fn parse_presentation(text: &[u8]) -> Result<Vec<Label<'_>>, NameError> {
+    let mut labels = Vec::with_capacity(INIT_NUM_LABELS);
+    if text == b"." {
+        return Ok(labels);
+    }
+    let (mut start, mut i) = (0, 0);
+    // Labels with escape sequences can not be borrowed from the text.
+    let mut unescaped: Option<Vec<u8>> = None;
+    while i < text.len() {
+        match text[i] {
+            b'.' => {
+                labels.push(presentation_label(&text[start..i], unescaped.take())?);
+                start = i + 1;
+            }
+            b'\\' => {
+                let label = unescaped.get_or_insert_with(|| text[start..i].to_vec());
+                let (byte, size) = read_escape(&text[i + 1..])?;
+                label.push(byte);
+                i += size;
+            }
+            0x21..=0x7E => {
+                if let Some(label) = &mut unescaped {
+                    label.push(text[i]);
+                }
+            }
+            _ => Err(NameError::LabelContent)?,
+        }
+        i += 1;
+    }
+    if start < text.len() || text.is_empty() {
+        labels.push(presentation_label(&text[start..], unescaped)?);
+    }
+    Ok(labels)
+}
+
+#[inline]
+fn presentation_label(text: &[u8], unescaped: Option<Vec<u8>>) -> Result<Label<'_>, NameError> {
+    match unescaped {
+        Some(label) => Label::try_from(label),
+        None => Label::try_from(text),
+    }
+}
+
+/// Decode the escape sequence after a `\`, returning the octet and the size of the sequence.
+#[inline]
+fn read_escape(text: &[u8]) -> Result<(u8, usize), NameError> {
+    match text {
+        [a, b, c, ..] if a.is_ascii_digit() && b.is_ascii_digit() && c.is_ascii_digit() => {
+            let n = [a, b, c]
+                .into_iter()
+                .fold(0u16, |n, d| n * 10 + (d - b'0') as u16);
+            let byte = u8::try_from(n).map_err(|_| NameError::Escape)?;
+            Ok((byte, 3))
+        }
+        [a, ..] if !a.is_ascii_digit() => Ok((*a, 1)),
+        _ => Err(NameError::Escape),
+    }
+}
+
+/// A label given as text can contain any printable ASCII character except `.`, for example `_` in service names.
+fn valid_label(label: &[u8]) -> bool {
+    label.iter().all(|&b| b.is_ascii_graphic() && b != b'.')
+}
+
+/// A hostname label can only contain a `-` or alphanumeric characters, and can not begin or end with a `-`.
+fn valid_hostname_label(label: &[u8]) -> bool {
+    !label.starts_with(b"-")
+        && !label.ends_with(b"-")
+        && label
+            .iter()
+            .all(|&b| b.is_ascii_alphanumeric() || b == b'-')
 }
 
 enum LabelMeta {
@@ -359,6 +1431,7 @@ fn read_label_metadata(buff: &[u8], pos: usize) -> Result<LabelMeta, ParseError>
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::vec;
 
     #[test]
     fn valid_labels() {
@@ -368,6 +1441,38 @@ mod tests {
         assert!(!valid_label(invalid.as_bytes()));
     }
 
+    #[test]
+    fn valid_service_labels() {
+        assert!(valid_label(b"_sip"));
+        assert!(valid_label(b"*"));
+        assert!(!valid_label(b"with space"));
+        assert!(!valid_label(b"\x00binary"));
+    }
+
+    #[test]
+    fn valid_hostname_labels() {
+        assert!(valid_hostname_label(b"example"));
+        assert!(valid_hostname_label(b"3com"));
+        assert!(valid_hostname_label(b"my-host"));
+        assert!(!valid_hostname_label(b"-host"));
+        assert!(!valid_hostname_label(b"host-"));
+        assert!(!valid_hostname_label(b"_tcp"));
+    }
+
+    #[test]
+    fn parse_service_name() {
+        let buff = [
+            4, 95, 115, 105, 112, // _sip
+            4, 95, 116, 99, 112, // _tcp
+            7, 101, 120, 97, 109, 112, 108, 101, // example
+            3, 99, 111, 109, 0, // com
+        ];
+        let (name, n) = Name::parse(&buff[..], 0).unwrap();
+        assert_eq!(n, buff.len());
+        assert_eq!(name.to_string(), "_sip._tcp.example.com.".to_string());
+        assert!(!name.is_hostname());
+    }
+
     #[test]
     fn no_jumps() {
         let buff = [
@@ -381,6 +1486,34 @@ mod tests {
         assert_eq!(name.to_string(), "hello.world.com.".to_string())
     }
 
+    #[test]
+    fn jump_options() {
+        let buff = [
+            192, 2, // <jump to 2>
+            192, 4, // <jump to 4>
+            192, 6, // <jump to 6>
+            3, 99, 111, 109, 0, // com
+        ];
+        let res = Name::parse(&buff[..], 0);
+        assert!(matches!(res, Err(ParseError::InvalidJump)));
+
+        let lenient = ParseOptions::lenient();
+        let (name, n) = Name::parse_with(&buff[..], 0, &lenient).unwrap();
+        assert_eq!(n, 2);
+        assert_eq!(name.to_string(), "com.");
+
+        let options = ParseOptions {
+            max_jumps: 2,
+            ..lenient
+        };
+        let res = Name::parse_with(&buff[..], 0, &options);
+        assert!(matches!(res, Err(ParseError::ExcesiveJumps(2))));
+
+        // A pointer to itself is stopped by the maximum of jumps.
+        let res = Name::parse_with(&[192, 0], 0, &lenient);
+        assert!(matches!(res, Err(ParseError::ExcesiveJumps(u8::MAX))));
+    }
+
     #[test]
     fn with_jumps() {
         let buff = [
@@ -423,6 +1556,86 @@ mod tests {
         assert_eq!(&buff[..17], &out[..17])
     }
 
+    #[test]
+    fn serialize_compressed() {
+        let mut out = Vec::new();
+        let mut compressor = Compressor::new(0);
+        let first = Name::try_from("hello.world.com").unwrap();
+        let second = Name::try_from("bye.world.com").unwrap();
+        let third = Name::try_from("HELLO.world.com").unwrap();
+        first.serialize_compressed(&mut out, &mut compressor);
+        second.serialize_compressed(&mut out, &mut compressor);
+        third.serialize_compressed(&mut out, &mut compressor);
+        assert_eq!(
+            &out[17..],
+            &[3, 98, 121, 101, 192, 6, 192, 0] // bye <jump to 6> <jump to 0>
+        );
+
+        let (name, n) = Name::parse(&out[..], 17).unwrap();
+        assert_eq!(n, 6);
+        assert_eq!(name.to_string(), "bye.world.com.".to_string());
+        let (name, n) = Name::parse(&out[..], 23).unwrap();
+        assert_eq!(n, 2);
+        assert_eq!(name.to_string(), "hello.world.com.".to_string());
+    }
+
+    #[test]
+    fn serialize_compressed_offset() {
+        let mut out = vec![0, 0];
+        let mut compressor = Compressor::new(2);
+        let name = Name::try_from("world.com").unwrap();
+        name.serialize_compressed(&mut out, &mut compressor);
+        name.serialize_compressed(&mut out, &mut compressor);
+        assert_eq!(&out[13..], &[192, 0]);
+    }
+
+    #[test]
+    fn serialize_compression_disabled() {
+        let mut out = Vec::new();
+        let mut compressor = Compressor::disabled();
+        let name = Name::try_from("world.com").unwrap();
+        name.serialize_compressed(&mut out, &mut compressor);
+        name.serialize_compressed(&mut out, &mut compressor);
+        assert_eq!(&out[..11], &out[11..]);
+    }
+
+    #[test]
+    fn reverse_ipv4() {
+        let ip: IpAddr = "10.0.0.254".parse().unwrap();
+        let name = Name::from_reverse_ip(ip);
+        assert_eq!(name.to_string(), "254.0.0.10.in-addr.arpa.".to_string());
+        assert_eq!(name.to_reverse_ip(), Some(ip));
+
+        let upper = Name::try_from("254.0.0.10.IN-ADDR.ARPA.").unwrap();
+        assert_eq!(upper.to_reverse_ip(), Some(ip));
+    }
+
+    #[test]
+    fn reverse_ipv6() {
+        let ip: IpAddr = "2001:db8:85a3::8a2e:370:7334".parse().unwrap();
+        let name = Name::from_reverse_ip(ip);
+        assert_eq!(name.label_count(), 34);
+        assert_eq!(name.to_reverse_ip(), Some(ip));
+    }
+
+    #[test]
+    fn reverse_invalid() {
+        let names = [
+            "example.com",
+            "256.0.0.10.in-addr.arpa",
+            "01.0.0.10.in-addr.arpa",
+            "a.0.0.10.in-addr.arpa",
+            "+1.0.0.10.in-addr.arpa",
+            "1.+0.0.10.in-addr.arpa",
+            "1.0.0.10.ip6.arpa",
+            "0.0.10.in-addr.arpa",
+        ];
+        for name in names {
+            let name = Name::try_from(name).unwrap();
+            assert_eq!(name.to_reverse_ip(), None);
+        }
+    }
+
     #[test]
     fn get_tld() {
         let mut name = Name::new();
@@ -431,7 +1644,7 @@ mod tests {
         name.push_label("hello".into()).unwrap();
 
         let tld = name.tld();
-        assert_eq!(tld, Some("com"));
+        assert_eq!(tld.unwrap(), "com");
     }
 
     #[test]
@@ -459,9 +1672,9 @@ mod tests {
         name.push_label("hello".into()).unwrap();
 
         let mut human = name.iter_human();
-        assert_eq!(human.next(), Some("hello"));
-        assert_eq!(human.next(), Some("world"));
-        assert_eq!(human.next(), Some("com"));
+        assert_eq!(human.next().unwrap(), "hello");
+        assert_eq!(human.next().unwrap(), "world");
+        assert_eq!(human.next().unwrap(), "com");
     }
 
     #[test]
@@ -472,9 +1685,9 @@ mod tests {
         name.push_label("hello".into()).unwrap();
 
         let mut human = name.iter_hierarchy();
-        assert_eq!(human.next(), Some("com"));
-        assert_eq!(human.next(), Some("world"));
-        assert_eq!(human.next(), Some("hello"));
+        assert_eq!(human.next().unwrap(), "com");
+        assert_eq!(human.next().unwrap(), "world");
+        assert_eq!(human.next().unwrap(), "hello");
     }
 
     #[test]
@@ -500,4 +1713,226 @@ mod tests {
         assert!(root.is_subdomain(&subd));
         assert!(!subd.is_subdomain(&root));
     }
+
+    #[test]
+    fn case_insensitive_eq() {
+        /// Records the hashed bytes, equal names must hash the same bytes.
+        #[derive(Default)]
+        struct Recorder(Vec<u8>);
+
+        impl Hasher for Recorder {
+            fn finish(&self) -> u64 {
+                0
+            }
+
+            fn write(&mut self, bytes: &[u8]) {
+                self.0.extend_from_slice(bytes);
+            }
+        }
+
+        let hashed = |name: &Name<'_>| {
+            let mut hasher = Recorder::default();
+            name.hash(&mut hasher);
+            hasher.0
+        };
+
+        let a = Name::try_from("Example.COM").unwrap();
+        let b = Name::try_from("example.com.").unwrap();
+        assert_eq!(a, b);
+        assert_eq!(a.cmp(&b), Ordering::Equal);
+        // Case is preserved.
+        assert_eq!(a.to_string(), "Example.COM.");
+
+        assert_eq!(hashed(&a), hashed(&b));
+        assert_ne!(hashed(&a), hashed(&Name::try_from("exam.plecom").unwrap()));
+
+        assert_ne!(a, Name::try_from("www.example.com").unwrap());
+        assert_ne!(a, Name::try_from("example.co").unwrap());
+    }
+
+    #[test]
+    fn canonical_order() {
+        // RFC 4034, section 6.1
+        let ordered = [
+            "example",
+            "a.example",
+            "yljkjljk.a.example",
+            "Z.a.example",
+            "zABC.a.EXAMPLE",
+            "z.example",
+            "*.z.example",
+        ];
+        for pair in ordered.windows(2) {
+            let a = Name::try_from(pair[0]).unwrap();
+            let b = Name::try_from(pair[1]).unwrap();
+            assert_eq!(a.cmp(&b), Ordering::Less, "{a} < {b}");
+        }
+        assert!(Name::new() < Name::try_from("example").unwrap());
+    }
+
+    #[test]
+    fn presentation_escapes() {
+        let name = Name::try_from(r"a\.b.c\\d.\065\098c.").unwrap();
+        let labels: Vec<&[u8]> = name.iter_human().map(Label::as_bytes).collect();
+        assert_eq!(labels, vec![&b"a.b"[..], b"c\\d", b"Abc"]);
+        assert_eq!(name.to_string(), r"a\.b.c\\d.Abc.");
+
+        assert!(matches!(Name::try_from(r"a\256"), Err(NameError::Escape)));
+        assert!(matches!(Name::try_from(r"a\12"), Err(NameError::Escape)));
+        assert!(matches!(Name::try_from("a\\"), Err(NameError::Escape)));
+        assert!(matches!(
+            Name::try_from(".a"),
+            Err(NameError::LabelLength(0))
+        ));
+        assert!(matches!(
+            Name::try_from(".."),
+            Err(NameError::LabelLength(0))
+        ));
+    }
+
+    #[test]
+    fn presentation_unescaped_content() {
+        let content = |text| matches!(Name::try_from(text), Err(NameError::LabelContent));
+        assert!(content("hello world"));
+        assert!(content("hello\tworld.com"));
+        assert!(content("bücher.example"));
+        assert!(content("\x00.com"));
+
+        let name = Name::try_from(r"hello\032world").unwrap();
+        assert_eq!(name.iter_human().next().unwrap(), "hello world");
+    }
+
+    #[test]
+    fn root_name() {
+        let root = Name::try_from(".").unwrap();
+        assert_eq!(root.label_count(), 0);
+        assert_eq!(root.to_string(), ".");
+        assert_eq!(root.size(), 1);
+        assert_eq!(Vec::from(root), vec![0]);
+
+        let (name, _) = Name::parse(&[0], 0).unwrap();
+        assert_eq!(name.to_string(), ".");
+    }
+
+    #[test]
+    fn binary_labels_round_trip() {
+        let buff = [3, 0, b'.', 0xFF, 3, b'c', b'o', b'm', 0];
+        let (name, _) = Name::parse_with(&buff, 0, &ParseOptions::lenient()).unwrap();
+        assert_eq!(name.to_string(), r"\000\.\255.com.");
+
+        let text = name.to_string();
+        let parsed = Name::try_from(text.as_str()).unwrap();
+        assert_eq!(Vec::from(parsed), buff);
+    }
+
+    #[test]
+    fn owned_names() {
+        let name = Name::try_from(String::from(r"hello\032world.com")).unwrap();
+        assert_eq!(name.iter_human().next().unwrap(), "hello world");
+        assert_eq!(name.size(), 17);
+    }
+
+    #[cfg(feature = "idna")]
+    #[test]
+    fn unicode_names() {
+        let name = Name::from_unicode("Bücher.例え.jp.").unwrap();
+        assert_eq!(name.to_string(), "xn--bcher-kva.xn--r8jz45g.jp.");
+        assert_eq!(name.to_unicode(), "bücher.例え.jp.");
+        assert_eq!(
+            name,
+            Name::try_from("XN--BCHER-KVA.xn--r8jz45g.jp").unwrap()
+        );
+
+        assert_eq!(Name::from_unicode(".").unwrap().label_count(), 0);
+        assert_eq!(Name::new().to_unicode(), ".");
+        assert!(Name::from_unicode("").is_err());
+        assert!(Name::from_unicode("xn--a.com").is_err());
+        assert!(Name::from_unicode(&"ü".repeat(64)).is_err());
+
+        // Invalid A-labels are displayed as they are.
+        let name = Name::try_from("xn--a.com").unwrap();
+        assert_eq!(name.to_unicode(), "xn--a.com.");
+        // Service labels are not changed.
+        let name = Name::from_unicode("_sip._tcp.bücher.de").unwrap();
+        assert_eq!(name.to_string(), "_sip._tcp.xn--bcher-kva.de.");
+    }
+
+    #[test]
+    fn name_manipulation() {
+        let root = Name::new();
+        let name = Name::try_from("www.example.com").unwrap();
+        assert_eq!(root.strip_suffix(&root).unwrap().label_count(), 0);
+        assert_eq!(root.strip_suffix(&root).unwrap().to_string(), "@");
+        let relative = name.strip_suffix(&root).unwrap();
+        assert_eq!(relative.to_string(), "www.example.com");
+        assert_eq!(relative.to_absolute(&root).unwrap(), name);
+        assert_eq!(name.strip_suffix(&name).unwrap().to_string(), "@");
+        assert!(
+            name.strip_suffix(&Name::try_from("www.example").unwrap())
+                .is_none()
+        );
+        assert_eq!(name.common_ancestor(&name), name);
+
+        let parent = name.parent().unwrap();
+        assert_eq!(parent.size(), name.size() - 4);
+        let relative = name.strip_suffix(&parent).unwrap();
+        assert_eq!(relative.to_string(), "www");
+        let absolute = relative.to_absolute(&parent).unwrap();
+        assert_eq!(absolute, name);
+        assert_eq!(Vec::from(absolute), Vec::from(name));
+
+        assert!(!root.is_wildcard());
+        assert!(!root.matches_wildcard(&root));
+        let wildcard = Name::try_from("*").unwrap();
+        assert!(wildcard.matches_wildcard(&Name::try_from("com").unwrap()));
+        assert!(!wildcard.matches_wildcard(&root));
+    }
+
+    #[test]
+    fn parse_any_octet() {
+        let buff = b"\x05a b.c\x01\x00\x03com\x00";
+        let (name, n) = Name::parse(&buff[..], 0).unwrap();
+        assert_eq!(n, buff.len());
+        assert_eq!(name.label_count(), 3);
+        let text = name.to_string();
+        assert_eq!(text, "a\\032b\\.c.\\000.com.");
+        assert_eq!(Name::try_from(text.as_str()).unwrap(), name);
+    }
+
+    #[test]
+    fn parse_too_long() {
+        let mut buff = Vec::new();
+        for _ in 0..3 {
+            buff.push(MAX_LABEL_SIZE as u8);
+            buff.extend([b'a'; MAX_LABEL_SIZE]);
+        }
+        // 3 * 64 + 62 + 1 = 255 bytes
+        buff.push(61);
+        buff.extend([b'a'; 61]);
+        buff.push(0);
+        let (name, n) = Name::parse(&buff[..], 0).unwrap();
+        assert_eq!(n, MAX_NAME_SIZE);
+        assert_eq!(name.size(), MAX_NAME_SIZE);
+
+        let mut buff = Vec::new();
+        for _ in 0..4 {
+            buff.push(MAX_LABEL_SIZE as u8);
+            buff.extend([b'a'; MAX_LABEL_SIZE]);
+        }
+        buff.push(0);
+        assert!(matches!(
+            Name::parse(&buff[..], 0),
+            Err(ParseError::InvalidName(NameError::NameLength(257)))
+        ));
+    }
+
+    #[test]
+    fn append_too_long() {
+        let label = "a".repeat(MAX_LABEL_SIZE);
+        let long = Name::try_from(format!("{label}.{label}.{label}")).unwrap();
+        let mut name = long.clone();
+        assert!(matches!(name.append(&long), Err(NameError::NameLength(_))));
+        assert_eq!(name, long);
+        assert_eq!(name.size(), long.size());
+    }
 }
@@ -0,0 +1,152 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use crate::binutils::*;
+use crate::body::name::Name;
+use crate::header::ExtendedResponseCode;
+use crate::writer::{Writer, checked_u16};
+use crate::{ParseError, ParseOptions};
+use alloc::borrow::Cow;
+
+/// A transaction signature, which authenticates the message it is attached to (RFC 8945).
+///
+/// The record is always the last one of the additional section, the top level `tsig` module
+/// (requires the `tsig` feature) signs and verifies messages.
+///
+/// ```text
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    /                 ALGORITHM NAME                /
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    |                                               |
+///    |                  TIME SIGNED                  |
+///    |                                               |
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    |                     FUDGE                     |
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    |                   MAC SIZE                    |
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    /                      MAC                      /
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    |                  ORIGINAL ID                  |
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    |                     ERROR                     |
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    |                   OTHER LEN                   |
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    /                  OTHER DATA                   /
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tsig<'a> {
+    /// The name of the MAC algorithm, like `hmac-sha256`.
+    pub algorithm: Name<'a>,
+    /// Seconds since the epoch when the message was signed, only the lower 48 bits are used.
+    pub time_signed: u64,
+    /// Seconds of error permitted in the time signed.
+    pub fudge: u16,
+    /// The message authentication code.
+    pub mac: Cow<'a, [u8]>,
+    /// The ID of the message when it was signed.
+    pub original_id: u16,
+    /// The TSIG error of a response, like [ExtendedResponseCode::BadKey].
+    pub error: ExtendedResponseCode,
+    /// Additional data, the current time of the server in BADTIME responses.
+    pub other: Cow<'a, [u8]>,
+}
+
+impl<'a> Tsig<'a> {
+    pub(crate) fn parse(
+        buff: &'a [u8],
+        pos: usize,
+        rdlen: u16,
+        options: &ParseOptions,
+    ) -> Result<Self, ParseError> {
+        let end = pos + rdlen as usize;
+        let (algorithm, n) = Name::parse_with(buff, pos, options)?;
+        let pos = pos + n;
+        let time_signed =
+            ((safe_u16_read(buff, pos)? as u64) << 32) | safe_u32_read(buff, pos + 2)? as u64;
+        let fudge = safe_u16_read(buff, pos + 6)?;
+        let mac_len = safe_u16_read(buff, pos + 8)? as usize;
+        if pos + 10 + mac_len > end {
+            Err(ParseError::OobRead(pos + 10 + mac_len))?
+        }
+        let mac = safe_slice_read(buff, pos + 10, mac_len)?;
+        let pos = pos + 10 + mac_len;
+        let other_len = safe_u16_read(buff, pos + 4)? as usize;
+        if pos + 6 + other_len > end {
+            Err(ParseError::OobRead(pos + 6 + other_len))?
+        }
+        Ok(Tsig {
+            algorithm,
+            time_signed,
+            fudge,
+            mac: Cow::from(mac),
+            original_id: safe_u16_read(buff, pos)?,
+            error: safe_u16_read(buff, pos + 2)?.into(),
+            other: Cow::from(safe_slice_read(buff, pos + 6, other_len)?),
+        })
+    }
+
+    #[inline]
+    pub(crate) fn serialize<W: Writer + ?Sized>(&self, packet: &mut W) {
+        // RFC 8945: the algorithm name is not compressed.
+        self.algorithm.serialize(packet);
+        push_u16(packet, (self.time_signed >> 32) as u16);
+        push_u32(packet, self.time_signed as u32);
+        push_u16(packet, self.fudge);
+        let len = checked_u16(packet, self.mac.len());
+        push_u16(packet, len);
+        packet.put(&self.mac);
+        push_u16(packet, self.original_id);
+        push_u16(packet, self.error.into());
+        let len = checked_u16(packet, self.other.len());
+        push_u16(packet, len);
+        packet.put(&self.other);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn tsig_roundtrip() {
+        let buff = [
+            4, 104, 109, 97, 99, 0, // hmac
+            0x00, 0x01, 0x02, 0x03, 0x04, 0x05, // time signed
+            0x01, 0x2C, // fudge
+            0x00, 0x02, 0xAA, 0xBB, // mac
+            0x12, 0x34, // original id
+            0x00, 0x12, // error
+            0x00, 0x01, 0xFF, // other
+        ];
+        let tsig = Tsig::parse(&buff[..], 0, buff.len() as u16, &ParseOptions::default()).unwrap();
+        assert_eq!(tsig.algorithm.to_string(), "hmac.");
+        assert_eq!(tsig.time_signed, 0x0001_0203_0405);
+        assert_eq!(tsig.fudge, 300);
+        assert_eq!(&tsig.mac[..], &[0xAA, 0xBB]);
+        assert_eq!(tsig.original_id, 0x1234);
+        assert_eq!(tsig.error, ExtendedResponseCode::BadTime);
+        assert_eq!(&tsig.other[..], &[0xFF]);
+
+        let mut out = Vec::new();
+        tsig.serialize(&mut out);
+        assert_eq!(&out[..], &buff[..]);
+        let parsed = Tsig::parse(&out[..], 0, out.len() as u16, &ParseOptions::default()).unwrap();
+        assert_eq!(parsed, tsig);
+
+        assert!(
+            Tsig::parse(
+                &buff[..],
+                0,
+                buff.len() as u16 - 1,
+                &ParseOptions::default()
+            )
+            .is_err()
+        );
+    }
+}
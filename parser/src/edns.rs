@@ -0,0 +1,871 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use crate::DnsPacket;
+use crate::ParseError;
+use crate::binutils::*;
+use crate::body::name::Name;
+use crate::body::{RecordData, RecordPreamble, ResourceRecord, Type};
+use crate::header::{ExtendedResponseCode, ResponseCode};
+use crate::writer::{Counter, Writer, checked_u16, patch_len};
+
+use thiserror::Error;
+
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use core::ops::Deref;
+use core::str;
+
+const DNSSEC_OK: u32 = 0b1000_0000_0000_0000;
+
+const CLIENT_SUBNET: u16 = 8;
+const EXTENDED_ERROR: u16 = 15;
+
+/// UDP payload size used when an OPT record has to be created (DNS flag day 2020).
+const DEFAULT_UDP_PAYLOAD: u16 = 1232;
+
+const FAMILY_IPV4: u16 = 1;
+const FAMILY_IPV6: u16 = 2;
+
+/// An error was encountered when trying to parse an EDNS(0) option.
+#[derive(Error, Debug)]
+pub enum EdnsError {
+    /// The address family of a client subnet option is not IPv4 (1) or IPv6 (2).
+    #[error("Address family {0} is not supported in the client subnet option.")]
+    AddressFamily(u16),
+    /// The prefix length of a client subnet option is bigger than the address length.
+    #[error("Prefix length {0} is bigger than the address length.")]
+    PrefixLength(u8),
+    /// The address of a client subnet option does not have the length required by the source prefix length.
+    #[error("Address length ({0} bytes) does not match the source prefix length.")]
+    AddressLength(usize),
+    /// The address of a client subnet option has bits set after the source prefix length.
+    #[error("Address has bits set beyond the source prefix length.")]
+    AddressBits,
+}
+
+/// The EDNS(0) information of a DNS packet, carried in the additional section by an OPT
+/// pseudo-record (RFC 6891).
+///
+/// The OPT record reuses the CLASS and TTL fields of the [ResourceRecord] for the
+/// UDP payload size and the extended flags:
+///
+/// ```text
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    |                  UDP PAYLOAD                  |
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    |    EXTENDED-RCODE     |        VERSION        |
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    |DO|                    Z                       |
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    /                    OPTIONS                    /
+///    /                                               /
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+/// ```
+///
+/// ```
+/// # use dominion_parser::edns::Edns;
+/// # use dominion_parser::body::ResourceRecord;
+/// let mut edns = Edns::new(1232);
+/// edns.dnssec_ok = true;
+///
+/// let record = ResourceRecord::from(edns);
+/// let edns = Edns::try_from(&record).unwrap();
+/// assert_eq!(edns.udp_payload_size, 1232);
+/// assert!(edns.dnssec_ok);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Edns<'a> {
+    /// The maximum UDP payload size that the sender is able to reassemble.
+    pub udp_payload_size: u16,
+    /// The upper 8 bits of the extended 12-bit response code.
+    pub extended_rcode: u8,
+    /// The EDNS version implemented by the sender.
+    pub version: u8,
+    /// DNSSEC OK: the sender is able to accept DNSSEC security RRs.
+    pub dnssec_ok: bool,
+    /// The rest of the flags, currently reserved (should be 0).
+    pub z: u16,
+    /// The options carried in the RDATA of the OPT record.
+    pub options: Vec<EdnsOption<'a>>,
+}
+
+impl<'a> TryFrom<&ResourceRecord<'a>> for Edns<'a> {
+    type Error = &'static str;
+
+    #[inline]
+    fn try_from(rr: &ResourceRecord<'a>) -> Result<Self, Self::Error> {
+        let RecordData::Opt(options) = &rr.data else {
+            return Err("ResourceRecord is not an OPT record");
+        };
+        let flags = rr.preamble.ttl as u32;
+        Ok(Edns {
+            udp_payload_size: rr.preamble.class.into(),
+            extended_rcode: (flags >> 24) as u8,
+            version: (flags >> 16) as u8,
+            dnssec_ok: flags & DNSSEC_OK != 0,
+            z: (flags & !DNSSEC_OK) as u16,
+            options: options.clone(),
+        })
+    }
+}
+
+impl<'a> From<Edns<'a>> for ResourceRecord<'a> {
+    #[inline]
+    fn from(edns: Edns<'a>) -> Self {
+        let mut flags = ((edns.extended_rcode as u32) << 24) | ((edns.version as u32) << 16);
+        flags |= (edns.z as u32) & !DNSSEC_OK;
+        if edns.dnssec_ok {
+            flags |= DNSSEC_OK;
+        }
+        let preamble = RecordPreamble {
+            name: Name::new(),
+            rrtype: Type::Opt,
+            class: edns.udp_payload_size.into(),
+            ttl: flags as i32,
+        };
+        ResourceRecord {
+            preamble,
+            data: RecordData::Opt(edns.options),
+        }
+    }
+}
+
+impl Edns<'_> {
+    /// Create a new EDNS(0) information with the provided UDP payload size and without any option.
+    #[inline]
+    pub fn new(udp_payload_size: u16) -> Self {
+        Edns {
+            udp_payload_size,
+            extended_rcode: 0,
+            version: 0,
+            dnssec_ok: false,
+            z: 0,
+            options: Vec::new(),
+        }
+    }
+
+    /// Obtain the client subnet option, if there is one.
+    #[inline]
+    pub fn client_subnet(&self) -> Option<&ClientSubnet> {
+        self.options.iter().find_map(|option| match option {
+            EdnsOption::ClientSubnet(subnet) => Some(subnet),
+            _ => None,
+        })
+    }
+
+    /// Return an iterator over the extended DNS error options.
+    #[inline]
+    pub fn extended_errors(&self) -> impl Iterator<Item = &'_ ExtendedError<'_>> {
+        self.options.iter().filter_map(|option| match option {
+            EdnsOption::ExtendedError(error) => Some(error),
+            _ => None,
+        })
+    }
+
+    /// Set the client subnet option, replacing the existing one if there was one.
+    #[inline]
+    pub fn set_client_subnet(&mut self, subnet: ClientSubnet) {
+        let option = EdnsOption::ClientSubnet(subnet);
+        match self
+            .options
+            .iter_mut()
+            .find(|option| option.code() == CLIENT_SUBNET)
+        {
+            Some(o) => *o = option,
+            None => self.options.push(option),
+        }
+    }
+}
+
+/// An option carried in the RDATA of an OPT pseudo-record.
+///
+/// ```text
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    |                  OPTION-CODE                  |
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    |                 OPTION-LENGTH                 |
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    /                  OPTION-DATA                  /
+///    /                                               /
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+/// ```
+#[non_exhaustive]
+#[derive(Debug, Clone)]
+pub enum EdnsOption<'a> {
+    /// EDNS Client Subnet (RFC 7871)
+    ClientSubnet(ClientSubnet),
+    /// Extended DNS Error (RFC 8914)
+    ExtendedError(ExtendedError<'a>),
+    /// ?: An option code that does not correspond to any known option, with its raw data.
+    Unknown(u16, Cow<'a, [u8]>),
+}
+
+impl<'a> EdnsOption<'a> {
+    /// The OPTION-CODE of the option.
+    #[inline]
+    pub fn code(&self) -> u16 {
+        match self {
+            Self::ClientSubnet(_) => CLIENT_SUBNET,
+            Self::ExtendedError(_) => EXTENDED_ERROR,
+            Self::Unknown(code, _) => *code,
+        }
+    }
+
+    /// Parse the OPTION-DATA of an option with the provided OPTION-CODE.
+    #[inline]
+    fn parse(code: u16, data: &'a [u8]) -> Result<Self, ParseError> {
+        match code {
+            CLIENT_SUBNET => Ok(Self::ClientSubnet(ClientSubnet::parse(data)?)),
+            EXTENDED_ERROR => Ok(Self::ExtendedError(ExtendedError::parse(data)?)),
+            _ => Ok(Self::Unknown(code, Cow::from(data))),
+        }
+    }
+
+    /// Parse the options contained in the `rdlen` bytes starting at position `pos`.
+    pub(crate) fn parse_all(
+        buff: &'a [u8],
+        pos: usize,
+        rdlen: u16,
+    ) -> Result<Vec<Self>, ParseError> {
+        let mut options = Vec::new();
+        let end = pos + rdlen as usize;
+        let mut pos = pos;
+        while pos < end {
+            let code = safe_u16_read(buff, pos)?;
+            let len = safe_u16_read(buff, pos + 2)? as usize;
+            let start = pos + 4;
+            if start + len > end {
+                Err(ParseError::OobRead(start + len))?
+            }
+            options.push(Self::parse(code, &buff[start..start + len])?);
+            pos = start + len;
+        }
+        Ok(options)
+    }
+
+    /// Serialize the option and append it tho the end of the provided `packet`
+    pub(crate) fn serialize<W: Writer + ?Sized>(&self, packet: &mut W) {
+        push_u16(packet, self.code());
+        if packet.written_mut().is_some() {
+            // Placeholder for the OPTION-LENGTH, it is written once we know the size of the data.
+            push_u16(packet, 0);
+            let start = packet.position();
+            self.serialize_data(packet);
+            patch_len(packet, start);
+        } else {
+            let mut counter = Counter::default();
+            self.serialize_data(&mut counter);
+            let len = checked_u16(packet, counter.position());
+            push_u16(packet, len);
+            self.serialize_data(packet);
+        }
+    }
+
+    #[inline]
+    fn serialize_data<W: Writer + ?Sized>(&self, packet: &mut W) {
+        match self {
+            Self::ClientSubnet(subnet) => subnet.serialize(packet),
+            Self::ExtendedError(error) => error.serialize(packet),
+            Self::Unknown(_, data) => packet.put(data.deref()),
+        }
+    }
+}
+
+/// The EDNS Client Subnet option (RFC 7871), used by resolvers to convey information about
+/// the network that originated a query.
+///
+/// ```text
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    |                    FAMILY                     |
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    |   SOURCE PREFIX-LENGTH  |  SCOPE PREFIX-LENGTH |
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    /                    ADDRESS                    /
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+/// ```
+///
+/// Only the first `source_prefix` bits of the address are serialized. Prefixes longer than the
+/// address (32 bits for IPv4 and 128 bits for IPv6) are shortened to its length.
+///
+/// ```
+/// # use dominion_parser::edns::ClientSubnet;
+/// let subnet = ClientSubnet::new("192.0.2.77".parse().unwrap(), 24);
+/// assert_eq!(subnet.family(), 1);
+/// assert_eq!(subnet.address, "192.0.2.0".parse::<core::net::IpAddr>().unwrap());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientSubnet {
+    /// The number of significant bits of the address used in the query.
+    pub source_prefix: u8,
+    /// The number of significant bits of the address that the response covers.
+    pub scope_prefix: u8,
+    /// The address of the client subnet.
+    pub address: IpAddr,
+}
+
+impl ClientSubnet {
+    /// Create a new client subnet option for a query, keeping only the first `source_prefix`
+    /// bits of the `address`.
+    #[inline]
+    pub fn new(address: IpAddr, source_prefix: u8) -> Self {
+        let source_prefix = source_prefix.min(max_prefix(address));
+        ClientSubnet {
+            source_prefix,
+            scope_prefix: 0,
+            address: truncate(address, source_prefix),
+        }
+    }
+
+    /// The address family of the subnet, IPv4 (1) or IPv6 (2).
+    #[inline]
+    pub fn family(&self) -> u16 {
+        match self.address {
+            IpAddr::V4(_) => FAMILY_IPV4,
+            IpAddr::V6(_) => FAMILY_IPV6,
+        }
+    }
+
+    fn parse(data: &[u8]) -> Result<Self, ParseError> {
+        let family = safe_u16_read(data, 0)?;
+        let source_prefix = safe_u8_read(data, 2)?;
+        let scope_prefix = safe_u8_read(data, 3)?;
+        let max_prefix = match family {
+            FAMILY_IPV4 => 32,
+            FAMILY_IPV6 => 128,
+            _ => Err(EdnsError::AddressFamily(family))?,
+        };
+        if source_prefix > max_prefix {
+            Err(EdnsError::PrefixLength(source_prefix))?
+        }
+        if scope_prefix > max_prefix {
+            Err(EdnsError::PrefixLength(scope_prefix))?
+        }
+        let bytes = &data[4..];
+        if bytes.len() != (source_prefix as usize).div_ceil(8) {
+            Err(EdnsError::AddressLength(bytes.len()))?
+        }
+        let address = match family {
+            FAMILY_IPV4 => {
+                let mut octets = [0u8; 4];
+                octets[..bytes.len()].copy_from_slice(bytes);
+                IpAddr::V4(Ipv4Addr::from(octets))
+            }
+            _ => {
+                let mut octets = [0u8; 16];
+                octets[..bytes.len()].copy_from_slice(bytes);
+                IpAddr::V6(Ipv6Addr::from(octets))
+            }
+        };
+        if truncate(address, source_prefix) != address {
+            Err(EdnsError::AddressBits)?
+        }
+        Ok(ClientSubnet {
+            source_prefix,
+            scope_prefix,
+            address,
+        })
+    }
+
+    fn serialize<W: Writer + ?Sized>(&self, packet: &mut W) {
+        let max_prefix = max_prefix(self.address);
+        let source_prefix = self.source_prefix.min(max_prefix);
+        let octets = match truncate(self.address, source_prefix) {
+            IpAddr::V4(ip) => ip.octets().to_vec(),
+            IpAddr::V6(ip) => ip.octets().to_vec(),
+        };
+        let len = (source_prefix as usize).div_ceil(8);
+        push_u16(packet, self.family());
+        packet.put(&[source_prefix]);
+        packet.put(&[self.scope_prefix.min(max_prefix)]);
+        packet.put(&octets[..len]);
+    }
+}
+
+/// The number of bits of the address.
+#[inline]
+fn max_prefix(address: IpAddr) -> u8 {
+    match address {
+        IpAddr::V4(_) => 32,
+        IpAddr::V6(_) => 128,
+    }
+}
+
+/// Set to zero all the bits of the address after the first `prefix` bits.
+fn truncate(address: IpAddr, prefix: u8) -> IpAddr {
+    match address {
+        IpAddr::V4(ip) => {
+            let mask = u32::MAX
+                .checked_shl(32 - prefix.min(32) as u32)
+                .unwrap_or(0);
+            IpAddr::V4(Ipv4Addr::from(u32::from(ip) & mask))
+        }
+        IpAddr::V6(ip) => {
+            let mask = u128::MAX
+                .checked_shl(128 - prefix.min(128) as u32)
+                .unwrap_or(0);
+            IpAddr::V6(Ipv6Addr::from(u128::from(ip) & mask))
+        }
+    }
+}
+
+/// The Extended DNS Error option (RFC 8914), with additional information about the cause of
+/// a DNS error.
+///
+/// ```text
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    |                   INFO-CODE                   |
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    /                  EXTRA-TEXT                   /
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtendedError<'a> {
+    /// The reason of the error.
+    pub info_code: InfoCode,
+    /// Text with additional information intended for human consumption. Invalid UTF-8 sequences
+    /// are replaced by `U+FFFD` when parsing, so that the rest of the packet can be read.
+    pub extra_text: Option<Cow<'a, str>>,
+}
+
+impl<'a> ExtendedError<'a> {
+    fn parse(data: &'a [u8]) -> Result<Self, ParseError> {
+        let info_code = safe_u16_read(data, 0)?.into();
+        let extra_text = match &data[2..] {
+            [] => None,
+            text => Some(String::from_utf8_lossy(text)),
+        };
+        Ok(ExtendedError {
+            info_code,
+            extra_text,
+        })
+    }
+
+    fn serialize<W: Writer + ?Sized>(&self, packet: &mut W) {
+        push_u16(packet, self.info_code.into());
+        if let Some(text) = &self.extra_text {
+            packet.put(text.as_bytes());
+        }
+    }
+}
+
+macro_rules! info_codes {
+    (
+        $(
+            #[$inner:meta]
+            $variant:tt = $value:literal
+        )+
+    ) => {
+        /// The INFO-CODE of an [ExtendedError], as registered by IANA.
+        #[non_exhaustive]
+        #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+        pub enum InfoCode {
+            $(
+                #[$inner]
+                $variant,
+            )*
+            /// ?: A value has been received that does not correspond to any known info code.
+            Unknown(u16),
+        }
+
+        impl From<u16> for InfoCode {
+            #[inline]
+            fn from(value: u16) -> Self {
+                match value {
+                    $($value => Self::$variant,)*
+                    _ => Self::Unknown(value),
+                }
+            }
+        }
+
+        impl From<InfoCode> for u16 {
+            #[inline]
+            fn from(value: InfoCode) -> Self {
+                match value {
+                    $(InfoCode::$variant => $value,)*
+                    InfoCode::Unknown(n) => n,
+                }
+            }
+        }
+    };
+}
+
+info_codes! {
+    /// The error does not match any other info code.
+    Other = 0
+    /// The DNSKEY RRset contains only unsupported DNSSEC algorithms.
+    UnsupportedDnskeyAlgorithm = 1
+    /// The DS RRset contains only unsupported digest types.
+    UnsupportedDsDigestType = 2
+    /// The answer was served from a stale cache.
+    StaleAnswer = 3
+    /// The answer was forged for policy reasons.
+    ForgedAnswer = 4
+    /// The DNSSEC validation ended in the indeterminate state.
+    DnssecIndeterminate = 5
+    /// The DNSSEC validation ended in the bogus state.
+    DnssecBogus = 6
+    /// No valid signature was found because they are all expired.
+    SignatureExpired = 7
+    /// No valid signature was found because they are all not yet valid.
+    SignatureNotYetValid = 8
+    /// A DS record existed but no supported matching DNSKEY could be found.
+    DnskeyMissing = 9
+    /// Signatures for the RRset could not be found.
+    RrsigsMissing = 10
+    /// No DNSKEY record with the zone key bit set could be found.
+    NoZoneKeyBitSet = 11
+    /// The requested data is missing and the covering NSEC or NSEC3 records could not be found.
+    NsecMissing = 12
+    /// The server is answering with a cached error.
+    CachedError = 13
+    /// The server is unable to answer the query, as it was not fully functional.
+    NotReady = 14
+    /// The domain is on a blocklist of the operator of the server.
+    Blocked = 15
+    /// The domain is on a blocklist due to an external requirement.
+    Censored = 16
+    /// The domain is on a blocklist as requested by the client.
+    Filtered = 17
+    /// The client is not authorized to use the server.
+    Prohibited = 18
+    /// The NXDOMAIN answer was served from a stale cache.
+    StaleNxdomainAnswer = 19
+    /// The server is not authoritative for the queried name and recursion is not available.
+    NotAuthoritative = 20
+    /// The requested operation or query is not supported.
+    NotSupported = 21
+    /// The resolver could not reach any of the authoritative name servers.
+    NoReachableAuthority = 22
+    /// An unrecoverable network error occurred while communicating with another server.
+    NetworkError = 23
+    /// The authoritative server can not answer with data for the zone it is configured to support.
+    InvalidData = 24
+    /// The signature expired before it became valid.
+    SignatureExpiredBeforeValid = 25
+    /// The query was received too early (TLS 1.3 early data).
+    TooEarly = 26
+    /// The NSEC3 iterations value of the zone is not supported.
+    UnsupportedNsec3IterationsValue = 27
+    /// The server is unable to conform to policy.
+    UnableToConformToPolicy = 28
+    /// The answer was synthesized from cached data.
+    Synthesized = 29
+    /// The query type is not valid for the operation.
+    InvalidQueryType = 30
+}
+
+impl<'a> DnsPacket<'a> {
+    /// Obtain the EDNS(0) information of the packet from the OPT record in the additional
+    /// section, if there is one.
+    #[inline]
+    pub fn edns(&self) -> Option<Edns<'a>> {
+        self.additional
+            .iter()
+            .find(|rr| rr.preamble.rrtype == Type::Opt)
+            .and_then(|rr| Edns::try_from(rr).ok())
+    }
+
+    /// Set the EDNS(0) information of the packet, replacing the OPT record in the additional
+    /// section if there was one. A new OPT record is placed before the TSIG record, which has to
+    /// be the last one.
+    #[inline]
+    pub fn set_edns(&mut self, edns: Edns<'a>) {
+        let record = ResourceRecord::from(edns);
+        match self
+            .additional
+            .iter_mut()
+            .find(|rr| rr.preamble.rrtype == Type::Opt)
+        {
+            Some(rr) => *rr = record,
+            None => {
+                let signed = self
+                    .additional
+                    .last()
+                    .is_some_and(|rr| rr.preamble.rrtype == Type::Tsig);
+                let i = self.additional.len() - signed as usize;
+                self.additional.insert(i, record)
+            }
+        }
+    }
+
+    /// Remove the EDNS(0) information of the packet, returning it if there was one.
+    #[inline]
+    pub fn remove_edns(&mut self) -> Option<Edns<'a>> {
+        let i = self
+            .additional
+            .iter()
+            .position(|rr| rr.preamble.rrtype == Type::Opt)?;
+        Edns::try_from(&self.additional.remove(i)).ok()
+    }
+
+    /// Attach an extended DNS error to the packet, creating the OPT record if the packet did
+    /// not have one.
+    ///
+    /// ```
+    /// # use dominion_parser::DnsPacket;
+    /// # use dominion_parser::edns::InfoCode;
+    /// # const REQ: &'static [u8; 33] = include_bytes!("../assets/dns_request.bin");
+    /// let mut res = DnsPacket::try_from(&REQ[..]).unwrap();
+    /// res.add_extended_error(InfoCode::Prohibited, Some("not allowed".into()));
+    ///
+    /// let edns = res.edns().unwrap();
+    /// let error = edns.extended_errors().next().unwrap();
+    /// assert_eq!(error.info_code, InfoCode::Prohibited);
+    /// ```
+    #[inline]
+    pub fn add_extended_error(&mut self, info_code: InfoCode, extra_text: Option<Cow<'a, str>>) {
+        let mut edns = self
+            .edns()
+            .unwrap_or_else(|| Edns::new(DEFAULT_UDP_PAYLOAD));
+        edns.options.push(EdnsOption::ExtendedError(ExtendedError {
+            info_code,
+            extra_text,
+        }));
+        self.set_edns(edns);
+    }
+
+    /// Obtain the 12-bit response code of the packet, combining the RCODE of the header with
+    /// the extended RCODE of the OPT record.
+    #[inline]
+    pub fn rcode(&self) -> ExtendedResponseCode {
+        if let ResponseCode::Unknown(n @ 16..) = self.header.flags.rcode {
+            return (n as u16).into();
+        }
+        let extended = self.edns().map_or(0, |edns| edns.extended_rcode);
+        (((extended as u16) << 4) | u16::from(self.header.flags.rcode)).into()
+    }
+
+    /// Set the response code of the packet, splitting it between the RCODE of the header and
+    /// the extended RCODE of the OPT record. The OPT record is only created if the code does
+    /// not fit in the header.
+    ///
+    /// ```
+    /// # use dominion_parser::DnsPacket;
+    /// # use dominion_parser::header::ExtendedResponseCode;
+    /// # const REQ: &'static [u8; 33] = include_bytes!("../assets/dns_request.bin");
+    /// let mut res = DnsPacket::try_from(&REQ[..]).unwrap();
+    /// res.set_rcode(ExtendedResponseCode::BadCookie);
+    ///
+    /// assert_eq!(res.edns().unwrap().extended_rcode, 1);
+    /// assert_eq!(res.rcode(), ExtendedResponseCode::BadCookie);
+    /// ```
+    #[inline]
+    pub fn set_rcode(&mut self, rcode: ExtendedResponseCode) {
+        let rcode = u16::from(rcode);
+        self.header.flags.rcode = (rcode & 0xF).into();
+        let extended = (rcode >> 4) as u8;
+        match self.edns() {
+            Some(mut edns) => {
+                edns.extended_rcode = extended;
+                self.set_edns(edns);
+            }
+            None if extended != 0 => {
+                let mut edns = Edns::new(DEFAULT_UDP_PAYLOAD);
+                edns.extended_rcode = extended;
+                self.set_edns(edns);
+            }
+            None => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edns_flags() {
+        let mut edns = Edns::new(4096);
+        edns.extended_rcode = 1;
+        edns.version = 2;
+        edns.dnssec_ok = true;
+        edns.z = 3;
+        let rr = ResourceRecord::from(edns);
+        assert_eq!(rr.preamble.ttl as u32, 0x0102_8003);
+        assert_eq!(u16::from(rr.preamble.class), 4096);
+
+        let edns = Edns::try_from(&rr).unwrap();
+        assert_eq!(edns.udp_payload_size, 4096);
+        assert_eq!(edns.extended_rcode, 1);
+        assert_eq!(edns.version, 2);
+        assert!(edns.dnssec_ok);
+        assert_eq!(edns.z, 3);
+    }
+
+    #[test]
+    fn unknown_options() {
+        let buff = [
+            0, 10, 0, 2, 1, 2, // <cookie>
+            0, 12, 0, 0, // <padding>
+            1, 1, // <outside of rdata>
+        ];
+        let options = EdnsOption::parse_all(&buff[..], 0, 10).unwrap();
+        assert_eq!(options.len(), 2);
+        assert_eq!(options[0].code(), 10);
+        assert_eq!(options[1].code(), 12);
+
+        let mut out = Vec::new();
+        for option in &options {
+            option.serialize(&mut out);
+        }
+        assert_eq!(&out[..], &buff[..10]);
+    }
+
+    #[test]
+    fn client_subnet_ipv4() {
+        let buff = [0, 8, 0, 7, 0, 1, 24, 0, 192, 0, 2];
+        let options = EdnsOption::parse_all(&buff[..], 0, 11).unwrap();
+        let EdnsOption::ClientSubnet(subnet) = &options[0] else {
+            panic!("Expected client subnet option")
+        };
+        assert_eq!(subnet.family(), 1);
+        assert_eq!(subnet.source_prefix, 24);
+        assert_eq!(subnet.scope_prefix, 0);
+        assert_eq!(subnet.address, "192.0.2.0".parse::<IpAddr>().unwrap());
+
+        let mut out = Vec::new();
+        options[0].serialize(&mut out);
+        assert_eq!(&out[..], &buff[..]);
+    }
+
+    #[test]
+    fn client_subnet_ipv6() {
+        let buff = [
+            0, 8, 0, 10, 0, 2, 48, 56, 0x20, 0x01, 0x0d, 0xb8, 0x12, 0x34,
+        ];
+        let options = EdnsOption::parse_all(&buff[..], 0, 14).unwrap();
+        let EdnsOption::ClientSubnet(subnet) = &options[0] else {
+            panic!("Expected client subnet option")
+        };
+        assert_eq!(subnet.family(), 2);
+        assert_eq!(subnet.source_prefix, 48);
+        assert_eq!(subnet.scope_prefix, 56);
+        assert_eq!(subnet.address, "2001:db8:1234::".parse::<IpAddr>().unwrap());
+
+        let mut out = Vec::new();
+        options[0].serialize(&mut out);
+        assert_eq!(&out[..], &buff[..]);
+    }
+
+    #[test]
+    fn client_subnet_truncate() {
+        let subnet = ClientSubnet {
+            source_prefix: 20,
+            scope_prefix: 0,
+            address: "198.51.100.77".parse().unwrap(),
+        };
+        let mut out = Vec::new();
+        EdnsOption::ClientSubnet(subnet).serialize(&mut out);
+        assert_eq!(&out[..], &[0, 8, 0, 7, 0, 1, 20, 0, 198, 51, 96]);
+
+        let subnet = ClientSubnet::new("198.51.100.77".parse().unwrap(), 0);
+        let mut out = Vec::new();
+        EdnsOption::ClientSubnet(subnet).serialize(&mut out);
+        assert_eq!(&out[..], &[0, 8, 0, 4, 0, 1, 0, 0]);
+    }
+
+    #[test]
+    fn client_subnet_prefix_too_long() {
+        let subnet = ClientSubnet::new("192.0.2.1".parse().unwrap(), 40);
+        assert_eq!(subnet.source_prefix, 32);
+
+        let oversized = [
+            ClientSubnet {
+                source_prefix: 40,
+                scope_prefix: 64,
+                address: "192.0.2.1".parse().unwrap(),
+            },
+            ClientSubnet {
+                source_prefix: 200,
+                scope_prefix: 0,
+                address: "2001:db8::1".parse().unwrap(),
+            },
+        ];
+        for subnet in oversized {
+            let address = subnet.address;
+            let mut out = Vec::new();
+            EdnsOption::ClientSubnet(subnet).serialize(&mut out);
+            let options = EdnsOption::parse_all(&out[..], 0, out.len() as u16).unwrap();
+            let EdnsOption::ClientSubnet(parsed) = &options[0] else {
+                panic!("Expected client subnet option")
+            };
+            assert_eq!(parsed.address, address);
+            assert_eq!(parsed.source_prefix as usize, out.len() * 8 - 64);
+        }
+    }
+
+    #[test]
+    fn client_subnet_invalid() {
+        let invalid: [&[u8]; 6] = [
+            &[0, 3, 24, 0, 192, 0, 2],       // <unknown family>
+            &[0, 1, 33, 0, 192, 0, 2, 1, 0], // <source prefix too big>
+            &[0, 1, 24, 33, 192, 0, 2],      // <scope prefix too big>
+            &[0, 1, 24, 0, 192, 0, 2, 1],    // <address too long>
+            &[0, 1, 24, 0, 192, 0],          // <address too short>
+            &[0, 1, 23, 0, 192, 0, 3],       // <bits after prefix>
+        ];
+        for data in invalid {
+            assert!(ClientSubnet::parse(data).is_err());
+        }
+    }
+
+    #[test]
+    fn extended_error() {
+        let buff = [
+            0, 15, 0, 13, 0, 18, // <prohibited>
+            110, 111, 116, 32, 97, 108, 108, 111, 119, 101, 100, // not allowed
+            0, 15, 0, 2, 0, 99, // <unknown>
+        ];
+        let options = EdnsOption::parse_all(&buff[..], 0, buff.len() as _).unwrap();
+        let EdnsOption::ExtendedError(error) = &options[0] else {
+            panic!("Expected extended error option")
+        };
+        assert_eq!(error.info_code, InfoCode::Prohibited);
+        assert_eq!(error.extra_text.as_deref(), Some("not allowed"));
+        let EdnsOption::ExtendedError(error) = &options[1] else {
+            panic!("Expected extended error option")
+        };
+        assert_eq!(error.info_code, InfoCode::Unknown(99));
+        assert_eq!(error.extra_text, None);
+
+        let mut out = Vec::new();
+        for option in &options {
+            option.serialize(&mut out);
+        }
+        assert_eq!(&out[..], &buff[..]);
+    }
+
+    #[test]
+    fn extended_error_non_utf8() {
+        let buff = [0, 15, 0, 5, 0, 0, b'a', 0xFF, 0xFE];
+        let options = EdnsOption::parse_all(&buff[..], 0, 9).unwrap();
+        let EdnsOption::ExtendedError(error) = &options[0] else {
+            panic!("Expected extended error option")
+        };
+        assert_eq!(error.info_code, InfoCode::Other);
+        assert_eq!(error.extra_text.as_deref(), Some("a\u{FFFD}\u{FFFD}"));
+    }
+
+    #[test]
+    fn info_code_transformations() {
+        assert_eq!(InfoCode::Other, From::from(0u16));
+        assert_eq!(InfoCode::Blocked, From::from(15u16));
+        assert_eq!(InfoCode::InvalidQueryType, From::from(30u16));
+        assert_eq!(InfoCode::Unknown(49152), From::from(49152u16));
+
+        assert_eq!(0u16, u16::from(InfoCode::Other));
+        assert_eq!(15u16, u16::from(InfoCode::Blocked));
+        assert_eq!(30u16, u16::from(InfoCode::InvalidQueryType));
+        assert_eq!(49152u16, u16::from(InfoCode::Unknown(49152)));
+    }
+
+    #[test]
+    fn option_overflow_rdata() {
+        let buff = [0, 10, 0, 4, 1, 2, 1, 1];
+        assert!(EdnsOption::parse_all(&buff[..], 0, 6).is_err());
+    }
+}
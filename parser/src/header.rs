@@ -2,8 +2,11 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use crate::binutils::*;
 use crate::ParseError;
+use crate::binutils::*;
+use crate::writer::Writer;
+
+use alloc::vec::Vec;
 
 macro_rules! u16_flag {
     (
@@ -27,7 +30,7 @@ macro_rules! u16_flag {
             fn from(n: u16) -> Self {
                 match $crate::header::mask_shift($bits, n) {
                     $($value => Self::$variant,)*
-                    _ => ::std::unreachable!("Bitwise operations should make this imposible. Failed with mask {} for value {}", $bits, n),
+                    _ => ::core::unreachable!("Bitwise operations should make this imposible. Failed with mask {} for value {}", $bits, n),
                 }
             }
         }
@@ -55,18 +58,18 @@ macro_rules! u16_flag_reserved {
         pub enum $typ {
             $(
                 #[$inner]
-                $variant = $value,
+                $variant,
             )*
+            /// ?: A value has been received that has not been assigned.
+            Unknown(u8),
         }
 
-        impl TryFrom<u16> for $typ {
-            type Error = ParseError;
-
+        impl From<u16> for $typ {
             #[inline]
-            fn try_from(n: u16) -> Result<Self, Self::Error> {
+            fn from(n: u16) -> Self {
                 match $crate::header::mask_shift($bits, n) {
-                    $($value => Ok(Self::$variant),)*
-                    n => Err(ParseError::HeaderFlag(stringify!($typ), n)),
+                    $($value => Self::$variant,)*
+                    n => Self::Unknown(n as u8),
                 }
             }
         }
@@ -74,7 +77,11 @@ macro_rules! u16_flag_reserved {
         impl From<$typ> for u16 {
             #[inline]
             fn from(flag: $typ) -> Self {
-                $crate::header::unshift($bits, flag as u16)
+                let n = match flag {
+                    $($typ::$variant => $value,)*
+                    $typ::Unknown(n) => n as u16,
+                };
+                $crate::header::unshift($bits, n)
             }
         }
     };
@@ -85,9 +92,10 @@ fn mask_shift(mask: u16, n: u16) -> u16 {
     (n & mask) >> mask.trailing_zeros()
 }
 
+/// Move `n` to the position of the `mask`, discarding the bits that do not fit in it.
 #[inline]
 fn unshift(mask: u16, n: u16) -> u16 {
-    n << mask.trailing_zeros()
+    (n << mask.trailing_zeros()) & mask
 }
 
 /// A DNS header.
@@ -120,12 +128,15 @@ fn unshift(mask: u16, n: u16) -> u16 {
 /// Z: Zero (reserved)
 /// AD: Authentic data (for DNSSEC)
 /// AD: Checking disabled (for DNSSEC)
-/// RCODE: Response code NOERROR (0), FORMERR (1), SERVFAIL (2), NXDOMAIN (3), NOTIMP (4), REFUSED (5)
+/// RCODE: Response code NOERROR (0), FORMERR (1), SERVFAIL (2), NXDOMAIN (3), NOTIMP (4), REFUSED (5), YXDOMAIN (6), YXRRSET (7), NXRRSET (8), NOTAUTH (9), NOTZONE (10), DSOTYPENI (11)
 /// QDCOUNT: Question records count
 /// ANCOUNT: Answer records count
 /// NSCOUNT: Name server records count
 /// ARCOUNT: Aditional records count
 /// ```
+///
+/// The records count are read when parsing, but when a whole [DnsPacket](crate::DnsPacket)
+/// is serialized they are computed from the number of questions and records in each section.
 #[derive(Clone, Debug)]
 pub struct DnsHeader {
     /// Random identifier of connnection
@@ -151,7 +162,7 @@ impl TryFrom<&[u8]> for DnsHeader {
         } else {
             let header = DnsHeader {
                 id: safe_u16_read(bytes, 0)?,
-                flags: safe_u16_read(bytes, 2)?.try_into()?,
+                flags: safe_u16_read(bytes, 2)?.into(),
                 questions: safe_u16_read(bytes, 4)?,
                 answers: safe_u16_read(bytes, 6)?,
                 authority: safe_u16_read(bytes, 8)?,
@@ -178,7 +189,7 @@ impl DnsHeader {
     /// in any other case the `From` trait is implemented to be able to convert from an
     /// [DnsHeader] to an `Vec<u8>`.
     #[inline]
-    pub fn serialize(&self, target: &mut Vec<u8>) {
+    pub fn serialize<W: Writer + ?Sized>(&self, target: &mut W) {
         push_u16(target, self.id);
         push_u16(target, self.flags.into());
         push_u16(target, self.questions);
@@ -203,7 +214,7 @@ impl DnsHeader {
 /// Z: Zero (reserved)
 /// AD: Authentic data (for DNSSEC)
 /// AD: Checking disabled (for DNSSEC)
-/// RCODE: Response code NOERROR (0), FORMERR (1), SERVFAIL (2), NXDOMAIN (3), NOTIMP (4), REFUSED (5)
+/// RCODE: Response code NOERROR (0), FORMERR (1), SERVFAIL (2), NXDOMAIN (3), NOTIMP (4), REFUSED (5), YXDOMAIN (6), YXRRSET (7), NXRRSET (8), NOTAUTH (9), NOTZONE (10), DSOTYPENI (11)
 /// ```
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct Flags {
@@ -229,14 +240,12 @@ pub struct Flags {
     pub rcode: ResponseCode,
 }
 
-impl TryFrom<u16> for Flags {
-    type Error = ParseError;
-
+impl From<u16> for Flags {
     #[inline]
-    fn try_from(n: u16) -> Result<Self, Self::Error> {
-        Ok(Flags {
+    fn from(n: u16) -> Self {
+        Flags {
             qr: n.into(),
-            opcode: n.try_into()?,
+            opcode: n.into(),
             aa: n.into(),
             tc: n.into(),
             rd: n.into(),
@@ -244,8 +253,8 @@ impl TryFrom<u16> for Flags {
             z: n.into(),
             ad: n.into(),
             cd: n.into(),
-            rcode: n.try_into()?,
-        })
+            rcode: n.into(),
+        }
     }
 }
 
@@ -274,7 +283,6 @@ u16_flag! {
         Response = 1
 }
 
-// TODO: Not exaustive. https://www.iana.org/assignments/dns-parameters/dns-parameters.xhtml
 u16_flag_reserved! {
     /// Standard query (0), Inverse query (1), Server status query (2), Notify (4), Update (5), DSO (6)
     0b0111100000000000 is OpCode with:
@@ -355,7 +363,6 @@ u16_flag! {
         Disabled = 1
 }
 
-// TODO: Not exaustive. https://www.iana.org/assignments/dns-parameters/dns-parameters.xhtml#dns-parameters-6
 u16_flag_reserved! {
     /// Response code
     0b0000000000001111 is ResponseCode with:
@@ -371,11 +378,146 @@ u16_flag_reserved! {
         NotImp = 4
         /// Refused - The name server refuses to perform the specified operation for policy reasons.  For example, a name server may not wish to provide the information to the particular requester, or a name server may not wish to perform a particular operation
         Refused = 5
+        /// Some name that ought not to exist, does exist (RFC 2136).
+        YXDomain = 6
+        /// Some RRset that ought not to exist, does exist (RFC 2136).
+        YXRRSet = 7
+        /// Some RRset that ought to exist, does not exist (RFC 2136).
+        NXRRSet = 8
+        /// The server is not authoritative for the zone (RFC 2136), or the request is not authorized (RFC 8945).
+        NotAuth = 9
+        /// A name used in the prerequisite or update section is not within the zone (RFC 2136).
+        NotZone = 10
+        /// DSO-TYPE not implemented (RFC 8490).
+        DsoTypeNi = 11
+}
+
+/// The full response code of a message, which may need 12 bits when the header RCODE is
+/// extended by an OPT record (RFC 6891) or 16 bits in a TSIG record (RFC 8945).
+///
+/// The lower values are the same as in [ResponseCode].
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ExtendedResponseCode {
+    /// There was no error.
+    NoError,
+    /// Format error.
+    FormErr,
+    /// Server failure.
+    ServFail,
+    /// Non-existent domain.
+    NXDomain,
+    /// Not implemented.
+    NotImp,
+    /// Query refused.
+    Refused,
+    /// Some name that ought not to exist, does exist.
+    YXDomain,
+    /// Some RRset that ought not to exist, does exist.
+    YXRRSet,
+    /// Some RRset that ought to exist, does not exist.
+    NXRRSet,
+    /// Server not authoritative for zone, or not authorized.
+    NotAuth,
+    /// Name not contained in zone.
+    NotZone,
+    /// DSO-TYPE not implemented.
+    DsoTypeNi,
+    /// Bad OPT version (RFC 6891). In TSIG records the same value means that the signature
+    /// failed to verify, see [ExtendedResponseCode::BAD_SIG].
+    BadVers,
+    /// Key not recognized.
+    BadKey,
+    /// Signature out of time window.
+    BadTime,
+    /// Bad TKEY mode.
+    BadMode,
+    /// Duplicate key name.
+    BadName,
+    /// Algorithm not supported.
+    BadAlg,
+    /// Bad truncation.
+    BadTrunc,
+    /// Bad or missing server cookie.
+    BadCookie,
+    /// ?: A value has been received that has not been assigned.
+    Unknown(u16),
+}
+
+impl ExtendedResponseCode {
+    /// TSIG signature failure (RFC 8945), which shares its value with [ExtendedResponseCode::BadVers].
+    pub const BAD_SIG: Self = Self::BadVers;
+}
+
+impl From<u16> for ExtendedResponseCode {
+    #[inline]
+    fn from(n: u16) -> Self {
+        match n {
+            0 => Self::NoError,
+            1 => Self::FormErr,
+            2 => Self::ServFail,
+            3 => Self::NXDomain,
+            4 => Self::NotImp,
+            5 => Self::Refused,
+            6 => Self::YXDomain,
+            7 => Self::YXRRSet,
+            8 => Self::NXRRSet,
+            9 => Self::NotAuth,
+            10 => Self::NotZone,
+            11 => Self::DsoTypeNi,
+            16 => Self::BadVers,
+            17 => Self::BadKey,
+            18 => Self::BadTime,
+            19 => Self::BadMode,
+            20 => Self::BadName,
+            21 => Self::BadAlg,
+            22 => Self::BadTrunc,
+            23 => Self::BadCookie,
+            n => Self::Unknown(n),
+        }
+    }
+}
+
+impl From<ExtendedResponseCode> for u16 {
+    #[inline]
+    fn from(code: ExtendedResponseCode) -> Self {
+        match code {
+            ExtendedResponseCode::NoError => 0,
+            ExtendedResponseCode::FormErr => 1,
+            ExtendedResponseCode::ServFail => 2,
+            ExtendedResponseCode::NXDomain => 3,
+            ExtendedResponseCode::NotImp => 4,
+            ExtendedResponseCode::Refused => 5,
+            ExtendedResponseCode::YXDomain => 6,
+            ExtendedResponseCode::YXRRSet => 7,
+            ExtendedResponseCode::NXRRSet => 8,
+            ExtendedResponseCode::NotAuth => 9,
+            ExtendedResponseCode::NotZone => 10,
+            ExtendedResponseCode::DsoTypeNi => 11,
+            ExtendedResponseCode::BadVers => 16,
+            ExtendedResponseCode::BadKey => 17,
+            ExtendedResponseCode::BadTime => 18,
+            ExtendedResponseCode::BadMode => 19,
+            ExtendedResponseCode::BadName => 20,
+            ExtendedResponseCode::BadAlg => 21,
+            ExtendedResponseCode::BadTrunc => 22,
+            ExtendedResponseCode::BadCookie => 23,
+            ExtendedResponseCode::Unknown(n) => n,
+        }
+    }
+}
+
+impl From<ResponseCode> for ExtendedResponseCode {
+    #[inline]
+    fn from(code: ResponseCode) -> Self {
+        u16::from(code).into()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::vec;
 
     #[test]
     fn serialize_header() {
@@ -427,7 +569,7 @@ mod tests {
     #[test]
     fn flags_standard_query() {
         let bits: u16 = 0b0000000000000000;
-        let flags: Flags = bits.try_into().expect("Failed when transforming flags");
+        let flags = Flags::from(bits);
         let transformed: u16 = flags.into();
 
         assert_eq!(flags.qr, QueryResponse::Query);
@@ -438,7 +580,7 @@ mod tests {
     #[test]
     fn flags_inverse_query() {
         let bits: u16 = 0b0000100000000000;
-        let flags: Flags = bits.try_into().expect("Failed when transforming flags");
+        let flags = Flags::from(bits);
         let transformed: u16 = flags.into();
 
         assert_eq!(flags.qr, QueryResponse::Query);
@@ -449,7 +591,7 @@ mod tests {
     #[test]
     fn flags_response_noerror() {
         let bits: u16 = 0b1000010000000000;
-        let flags: Flags = bits.try_into().expect("Failed when transforming flags");
+        let flags = Flags::from(bits);
         let transformed: u16 = flags.into();
 
         assert_eq!(flags.qr, QueryResponse::Response);
@@ -461,7 +603,7 @@ mod tests {
     #[test]
     fn flags_response_servfail() {
         let bits: u16 = 0b1000010000000010;
-        let flags: Flags = bits.try_into().expect("Failed when transforming flags");
+        let flags = Flags::from(bits);
         let transformed: u16 = flags.into();
 
         assert_eq!(flags.qr, QueryResponse::Response);
@@ -473,7 +615,7 @@ mod tests {
     #[test]
     fn flags_response_nxdomain() {
         let bits: u16 = 0b1000010000000011;
-        let flags: Flags = bits.try_into().expect("Failed when transforming flags");
+        let flags = Flags::from(bits);
         let transformed: u16 = flags.into();
 
         assert_eq!(flags.qr, QueryResponse::Response);
@@ -482,10 +624,72 @@ mod tests {
         assert_eq!(transformed, bits);
     }
 
+    #[test]
+    fn flags_roundtrip() {
+        for bits in 0..=u16::MAX {
+            let flags = Flags::from(bits);
+            assert_eq!(u16::from(flags), bits);
+        }
+    }
+
+    #[test]
+    fn flags_unassigned_values() {
+        let bits: u16 = 0b1001110000001001;
+        let flags = Flags::from(bits);
+
+        assert_eq!(flags.opcode, OpCode::Unknown(3));
+        assert_eq!(flags.rcode, ResponseCode::NotAuth);
+
+        let flags = Flags::from(0b0111100000001111);
+        assert_eq!(flags.opcode, OpCode::Unknown(15));
+        assert_eq!(flags.rcode, ResponseCode::Unknown(15));
+    }
+
+    #[test]
+    fn flags_unknown_out_of_range() {
+        let flags = Flags::from(0b0000000000000000);
+        let bits = u16::from(Flags {
+            rcode: ResponseCode::Unknown(16),
+            ..flags
+        });
+        assert_eq!(bits, 0);
+        let bits = u16::from(Flags {
+            opcode: OpCode::Unknown(16),
+            ..flags
+        });
+        assert_eq!(bits, 0);
+
+        let bits = u16::from(Flags {
+            opcode: OpCode::Unknown(0xFF),
+            rcode: ResponseCode::Unknown(0xFF),
+            ..flags
+        });
+        assert_eq!(bits, 0b0111100000001111);
+    }
+
+    #[test]
+    fn extended_rcode_transformations() {
+        for n in 0..=u16::MAX {
+            assert_eq!(u16::from(ExtendedResponseCode::from(n)), n);
+        }
+        assert_eq!(
+            ExtendedResponseCode::from(16),
+            ExtendedResponseCode::BAD_SIG
+        );
+        assert_eq!(
+            ExtendedResponseCode::from(23),
+            ExtendedResponseCode::BadCookie
+        );
+        assert_eq!(
+            ExtendedResponseCode::from(ResponseCode::NotZone),
+            ExtendedResponseCode::NotZone
+        );
+    }
+
     #[test]
     fn flags_response_refused() {
         let bits: u16 = 0b1000010000000101;
-        let flags: Flags = bits.try_into().expect("Failed when transforming flags");
+        let flags = Flags::from(bits);
         let transformed: u16 = flags.into();
 
         assert_eq!(flags.qr, QueryResponse::Response);
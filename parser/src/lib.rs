@@ -53,11 +53,9 @@
 //!             .expect("QType is not a valid Type"),
 //!         class: res.questions[0].class,
 //!         ttl: 300,
-//!         rdlen: 4,
 //!     };
 //!     let data = RecordData::A("204.74.99.100".parse().unwrap());
 //!     let answer = ResourceRecord { preamble, data };
-//!     res.header.answers = 1;
 //!     res.answers.push(answer);
 //!
 //!     let res = Vec::<u8>::from(&res);
@@ -66,7 +64,20 @@
 //!     println!("{:?}", res);
 //! }
 //! ```
+//!
+//! When serializing a whole [DnsPacket] the domain names are compressed where the specification
+//! allows it, use [DnsPacket::serialize_uncompressed] to write every name in full. The length of
+//! the RR data and the records count of the header are computed automatically.
+//!
+//! Packets can be serialized into any [writer::Writer]: a `Vec<u8>`, a caller-provided buffer
+//! (see [DnsPacket::serialize_into]) or any [std::io::Write].
+//!
+//! ## `no_std`
+//!
+//! The crate only needs `core` and `alloc` when the default `std` feature is disabled. The
+//! `dnssec` and `tsig` features require `std`.
 
+#![no_std]
 #![warn(
     missing_docs,
     rust_2018_idioms,
@@ -74,17 +85,48 @@
     rustdoc::broken_intra_doc_links
 )]
 
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
 use thiserror::Error;
 
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::fmt;
+
 use body::Question;
 use body::ResourceRecord;
-use header::DnsHeader;
+use body::name::Compressor;
+use header::{DnsHeader, ExtendedResponseCode, ResponseCode};
+use writer::{SliceWriter, WriteError, Writer, checked_u16};
+
+const INIT_PACKET_SIZE: usize = 512;
 
 mod binutils;
 /// The body of the DNS packet (Questions and Resource Records)
 pub mod body;
+/// Extension mechanisms for DNS (EDNS(0))
+pub mod edns;
 /// The header of the DNS packet
 pub mod header;
+/// DNSSEC signing (requires the `dnssec` feature)
+#[cfg(feature = "dnssec")]
+pub mod signing;
+/// Zone transfers (AXFR and IXFR)
+pub mod transfer;
+/// Transaction signatures (requires the `tsig` feature)
+#[cfg(feature = "tsig")]
+pub mod tsig;
+/// DNS UPDATE messages (RFC 2136)
+pub mod update;
+/// DNSSEC validation (requires the `dnssec` feature)
+#[cfg(feature = "dnssec")]
+pub mod validation;
+/// Lazy, zero-copy view of a DNS packet
+pub mod view;
+/// Destinations for serialized DNS packets
+pub mod writer;
 
 /// Represents a complete DNS packet.
 ///
@@ -123,59 +165,213 @@ pub struct DnsPacket<'a> {
 impl<'a> TryFrom<&'a [u8]> for DnsPacket<'a> {
     type Error = ParseError;
 
+    #[inline]
     fn try_from(buff: &'a [u8]) -> Result<Self, Self::Error> {
-        let header = DnsHeader::try_from(buff)?;
-        let mut questions = Vec::with_capacity(header.questions as _);
-        let mut answers = Vec::with_capacity(header.answers as _);
-        let mut authority = Vec::with_capacity(header.authority as _);
-        let mut additional = Vec::with_capacity(header.additional as _);
+        Ok(Self::parse_with(buff, &ParseOptions::default())?)
+    }
+}
+
+impl<'a> DnsPacket<'a> {
+    /// Parse a complete DNS packet from `buff` with the given [ParseOptions]. Parsing with
+    /// [TryFrom] uses the default options.
+    ///
+    /// # Errors
+    ///
+    /// It will error if the buffer does not contain a valid DNS packet for the `options`. The
+    /// [PacketError] tells where the packet is invalid, and contains what could be parsed
+    /// before that point.
+    ///
+    /// ```
+    /// # use dominion_parser::{DnsPacket, ParseError, ParseOptions, Section};
+    /// # const REQ: &'static [u8; 33] = include_bytes!("../assets/dns_request.bin");
+    /// let mut buff = REQ.to_vec();
+    /// buff.push(0);
+    /// assert!(DnsPacket::parse_with(&buff, &ParseOptions::default()).is_ok());
+    ///
+    /// let err = DnsPacket::parse_with(&buff, &ParseOptions::strict()).unwrap_err();
+    /// assert!(matches!(err.error, ParseError::TrailingBytes(1)));
+    /// assert_eq!(err.offset, 33);
+    ///
+    /// let err = DnsPacket::parse_with(&REQ[..20], &ParseOptions::default()).unwrap_err();
+    /// assert_eq!(err.section, Section::Question);
+    /// assert_eq!(err.partial.unwrap().header.id, 0x1cc3);
+    /// ```
+    pub fn parse_with(buff: &'a [u8], options: &ParseOptions) -> Result<Self, PacketError<'a>> {
+        let header = DnsHeader::try_from(buff)
+            .map_err(|error| PacketError::new(buff, Section::Header, 0, 0, error, None))?;
+        let counts = [
+            header.questions,
+            header.answers,
+            header.authority,
+            header.additional,
+        ];
+        let mut packet = DnsPacket {
+            header: header.clone(),
+            questions: Vec::new(),
+            answers: Vec::new(),
+            authority: Vec::new(),
+            additional: Vec::new(),
+        };
+        if let Some(i) = counts.iter().position(|&n| n > options.max_records) {
+            let error = ParseError::RecordCount(counts[i]);
+            let offset = 4 + 2 * i;
+            let partial = Some(packet);
+            return Err(PacketError::new(
+                buff,
+                Section::Header,
+                0,
+                offset,
+                error,
+                partial,
+            ));
+        }
+        packet.questions.reserve_exact(header.questions as _);
+        packet.answers.reserve_exact(header.answers as _);
+        packet.authority.reserve_exact(header.authority as _);
+        packet.additional.reserve_exact(header.additional as _);
         let mut pos = 12;
-        for _ in 0..header.questions {
-            let (q, size) = Question::parse(buff, pos)?;
-            pos += size;
-            questions.push(q);
+        for i in 0..header.questions as usize {
+            match Question::parse_with(buff, pos, options) {
+                Ok((q, size)) => {
+                    pos += size;
+                    packet.questions.push(q);
+                }
+                Err(error) => {
+                    let partial = Some(packet);
+                    return Err(PacketError::new(
+                        buff,
+                        Section::Question,
+                        i,
+                        pos,
+                        error,
+                        partial,
+                    ));
+                }
+            }
         }
-        for _ in 0..header.answers {
-            let (a, size) = ResourceRecord::parse(buff, pos)?;
-            pos += size;
-            answers.push(a)
+        let sections = [Section::Answer, Section::Authority, Section::Additional];
+        for (section, count) in sections.into_iter().zip(&counts[1..]) {
+            for i in 0..*count as usize {
+                match ResourceRecord::parse_with(buff, pos, options) {
+                    Ok((rr, size)) => {
+                        pos += size;
+                        packet.records_mut(section).push(rr);
+                    }
+                    Err(error) => {
+                        return Err(PacketError::new(buff, section, i, pos, error, Some(packet)));
+                    }
+                }
+            }
         }
-        for _ in 0..header.authority {
-            let (a, size) = ResourceRecord::parse(buff, pos)?;
-            pos += size;
-            authority.push(a)
+        if options.reject_trailing && pos < buff.len() {
+            let error = ParseError::TrailingBytes(buff.len() - pos);
+            let index = header.additional as usize;
+            let partial = Some(packet);
+            return Err(PacketError::new(
+                buff,
+                Section::Additional,
+                index,
+                pos,
+                error,
+                partial,
+            ));
         }
-        for _ in 0..header.additional {
-            let (a, size) = ResourceRecord::parse(buff, pos)?;
-            pos += size;
-            additional.push(a)
+        Ok(packet)
+    }
+
+    fn records_mut(&mut self, section: Section) -> &mut Vec<ResourceRecord<'a>> {
+        match section {
+            Section::Authority => &mut self.authority,
+            Section::Additional => &mut self.additional,
+            _ => &mut self.answers,
         }
-        Ok(Self {
-            header,
-            questions,
-            answers,
-            authority,
-            additional,
-        })
     }
 }
 
+/// # Panics
+///
+/// It panics if the packet can not be represented in the wire format, like a record with more
+/// than 65535 bytes of RDATA. Use [DnsPacket::serialize_into] or a [writer::IoWriter] to get an
+/// error instead.
 impl From<&DnsPacket<'_>> for Vec<u8> {
     fn from(dns: &DnsPacket<'_>) -> Self {
-        let mut out = (&dns.header).into();
-        for question in &dns.questions {
-            question.serialize(&mut out);
+        let mut out = Vec::with_capacity(INIT_PACKET_SIZE);
+        dns.serialize(&mut out);
+        out
+    }
+}
+
+impl DnsPacket<'_> {
+    /// Serialize the [DnsPacket] and append it tho the end of the provided `packet`, which can be
+    /// any [Writer].
+    ///
+    /// The records count in the header are computed from the length of each section. Domain names are compressed where the specification allows it. Use
+    /// [DnsPacket::serialize_uncompressed] if every name has to be written in full. A
+    /// [header::ResponseCode::Unknown] over 15 is split with the OPT record like
+    /// [DnsPacket::set_rcode] does.
+    #[inline]
+    pub fn serialize<W: Writer + ?Sized>(&self, packet: &mut W) {
+        let mut compressor = Compressor::new(packet.position());
+        self.serialize_with(packet, &mut compressor);
+    }
+
+    /// Serialize the [DnsPacket] and append it tho the end of the provided `packet`, without
+    /// compressing any domain name. The records count in the header are computed from the length
+    /// of each section.
+    #[inline]
+    pub fn serialize_uncompressed<W: Writer + ?Sized>(&self, packet: &mut W) {
+        self.serialize_with(packet, &mut Compressor::disabled());
+    }
+
+    /// Serialize the [DnsPacket] at the beginning of `buff` without allocating, returning the
+    /// number of bytes written.
+    ///
+    /// # Errors
+    ///
+    /// It will error if the packet does not fit in `buff`.
+    ///
+    /// ```
+    /// # use dominion_parser::DnsPacket;
+    /// # const REQ: &'static [u8; 33] = include_bytes!("../assets/dns_request.bin");
+    /// let packet = DnsPacket::try_from(&REQ[..]).unwrap();
+    /// let mut buff = [0; 512];
+    /// let n = packet.serialize_into(&mut buff).unwrap();
+    /// assert_eq!(&buff[..n], &REQ[..]);
+    /// ```
+    #[inline]
+    pub fn serialize_into(&self, buff: &mut [u8]) -> Result<usize, WriteError> {
+        let mut writer = SliceWriter::new(buff);
+        self.serialize(&mut writer);
+        writer.finish().map(|written| written.len())
+    }
+
+    fn serialize_with<W: Writer + ?Sized>(&self, packet: &mut W, compressor: &mut Compressor) {
+        // The RCODE of the header only has 4 bits, the rest goes in the OPT record.
+        if let ResponseCode::Unknown(n @ 16..) = self.header.flags.rcode {
+            let mut extended = self.clone();
+            extended.set_rcode(ExtendedResponseCode::from(n as u16));
+            return extended.serialize_with(packet, compressor);
         }
-        for answer in &dns.answers {
-            answer.serialize(&mut out);
+        let header = DnsHeader {
+            questions: checked_u16(packet, self.questions.len()),
+            answers: checked_u16(packet, self.answers.len()),
+            authority: checked_u16(packet, self.authority.len()),
+            additional: checked_u16(packet, self.additional.len()),
+            ..self.header.clone()
+        };
+        header.serialize(packet);
+        for question in &self.questions {
+            question.serialize_compressed(packet, compressor);
         }
-        for auth in &dns.authority {
-            auth.serialize(&mut out);
+        for answer in &self.answers {
+            answer.serialize_compressed(packet, compressor);
         }
-        for extra in &dns.additional {
-            extra.serialize(&mut out);
+        for auth in &self.authority {
+            auth.serialize_compressed(packet, compressor);
+        }
+        for extra in &self.additional {
+            extra.serialize_compressed(packet, compressor);
         }
-        out
     }
 }
 
@@ -187,17 +383,11 @@ pub enum ParseError {
         "Length of packet ({0} bytes) is too small to contain a DNS header (12 bytes in length)."
     )]
     HeaderLength(usize),
-    /// Some header flag used a value that has not been implemented.
-    #[error("Flag {0} has no implementation for value {1} in the current version.")]
-    HeaderFlag(&'static str, u16),
     /// There was a jump to a position forward in the packet (it does not follow the specification) or to itself (it is not sound as it would result in a DoS).
     #[error("Jump points to a section of the packet  equal or greater than the current position.")]
     InvalidJump,
-    /// Some domain name has been compressed with too many jumps. This error may be removed in the future.
-    #[error(
-        "DNS compression contains excesive number of jumps {0} (maximum {})",
-        crate::body::name::MAX_JUMPS
-    )]
+    /// Some domain name has been compressed with more jumps than allowed by the [ParseOptions].
+    #[error("DNS compression contains more than the maximum of {0} jumps.")]
     ExcesiveJumps(u8),
     /// The DNS packet contains a label prefix that is not a length prefix or a pointer. Those values dont have a standard definition so are not implemented.
     #[error("Byte {0:#b} does not have a pointer or length prefix.")]
@@ -207,8 +397,189 @@ pub enum ParseError {
     OobRead(usize),
     /// Some text is not valid UTF-8.
     #[error("Non UTF-8 text string: {0}")]
-    NonUtf8(#[from] std::str::Utf8Error),
+    NonUtf8(#[from] core::str::Utf8Error),
     /// Error when parsing a domain name
     #[error("Domain name could not be parsed: {0}")]
     InvalidName(#[from] crate::body::name::NameError),
+    /// Error when parsing an EDNS(0) option
+    #[error("EDNS option could not be parsed: {0}")]
+    InvalidEdns(#[from] crate::edns::EdnsError),
+    /// Error when parsing the data of a DNSSEC record
+    #[error("DNSSEC record could not be parsed: {0}")]
+    InvalidDnssec(#[from] crate::body::dnssec::DnssecError),
+    /// Some section has more records than allowed by the [ParseOptions].
+    #[error("Section with {0} records is bigger than the maximum allowed.")]
+    RecordCount(u16),
+    /// There are bytes after the end of the packet, and the [ParseOptions] reject them.
+    #[error("There are {0} bytes after the end of the packet.")]
+    TrailingBytes(usize),
+    /// The data of a record does not have the length given by its RDLENGTH.
+    #[error("Record data of {1} bytes does not match its RDLENGTH of {0} bytes.")]
+    RdataLength(u16, usize),
+}
+
+/// A section of a DNS packet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Section {
+    /// The header of the packet.
+    Header,
+    /// The question(s) for the name server.
+    Question,
+    /// Resource Records answering the question(s).
+    Answer,
+    /// Resource Records pointing toward a domain authority.
+    Authority,
+    /// Resource Records holding additional information.
+    Additional,
+}
+
+impl fmt::Display for Section {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Section::Header => write!(f, "header"),
+            Section::Question => write!(f, "question"),
+            Section::Answer => write!(f, "answer"),
+            Section::Authority => write!(f, "authority record"),
+            Section::Additional => write!(f, "additional record"),
+        }
+    }
+}
+
+/// An error when parsing a DNS packet with [DnsPacket::parse_with], with the location of the
+/// invalid bytes.
+///
+/// Bytes after the last record that are rejected by the [ParseOptions] are reported as an
+/// additional record after the last one.
+#[derive(Debug)]
+pub struct PacketError<'a> {
+    /// The section of the packet that could not be parsed.
+    pub section: Section,
+    /// The index of the question or record in its section that could not be parsed.
+    pub index: usize,
+    /// The position in the packet where the invalid question, record or header field starts.
+    pub offset: usize,
+    /// The bytes of the packet starting at the `offset`, at most 16 of them.
+    pub bytes: &'a [u8],
+    /// The reason why the packet could not be parsed.
+    pub error: ParseError,
+    /// The header and the questions and records parsed before the error, if the header is valid.
+    pub partial: Option<Box<DnsPacket<'a>>>,
+}
+
+impl<'a> PacketError<'a> {
+    fn new(
+        buff: &'a [u8],
+        section: Section,
+        index: usize,
+        offset: usize,
+        error: ParseError,
+        partial: Option<DnsPacket<'a>>,
+    ) -> Self {
+        let end = buff.len().min(offset + 16);
+        PacketError {
+            section,
+            index,
+            offset,
+            bytes: buff.get(offset..end).unwrap_or_default(),
+            error,
+            partial: partial.map(Box::new),
+        }
+    }
+}
+
+impl fmt::Display for PacketError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.section {
+            Section::Header => write!(f, "Invalid header at byte {}", self.offset)?,
+            section => write!(
+                f,
+                "Invalid {section} {} at byte {}",
+                self.index, self.offset
+            )?,
+        }
+        if !self.bytes.is_empty() {
+            write!(f, " [")?;
+            for (i, byte) in self.bytes.iter().enumerate() {
+                match i {
+                    0 => write!(f, "{byte:02x}")?,
+                    _ => write!(f, " {byte:02x}")?,
+                }
+            }
+            write!(f, "]")?;
+        }
+        write!(f, ": {}", self.error)
+    }
+}
+
+impl core::error::Error for PacketError<'_> {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl From<PacketError<'_>> for ParseError {
+    #[inline]
+    fn from(err: PacketError<'_>) -> Self {
+        err.error
+    }
+}
+
+/// Options to choose how strict is the parsing of DNS packets, used with
+/// [DnsPacket::parse_with].
+///
+/// The [Default] options follow the specification while being tolerant with trailing bytes,
+/// [ParseOptions::strict] and [ParseOptions::lenient] are provided as starting points.
+///
+/// ```
+/// # use dominion_parser::ParseOptions;
+/// let options = ParseOptions {
+///     max_records: 16,
+///     ..ParseOptions::strict()
+/// };
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Maximum number of compression pointers followed in a single domain name.
+    pub max_jumps: u8,
+    /// Allow compression pointers to the same or a later position of the packet. Loops are
+    /// still stopped by `max_jumps`.
+    pub forward_pointers: bool,
+    /// Reject packets with bytes after the last record.
+    pub reject_trailing: bool,
+    /// Maximum number of questions or records in each section of the packet.
+    pub max_records: u16,
+}
+
+impl Default for ParseOptions {
+    #[inline]
+    fn default() -> Self {
+        ParseOptions {
+            max_jumps: crate::body::name::MAX_JUMPS,
+            forward_pointers: false,
+            reject_trailing: false,
+            max_records: u16::MAX,
+        }
+    }
+}
+
+impl ParseOptions {
+    /// Options that reject anything outside of the specification, like trailing bytes.
+    #[inline]
+    pub fn strict() -> Self {
+        ParseOptions {
+            reject_trailing: true,
+            ..Default::default()
+        }
+    }
+
+    /// Options that accept as many packets as possible: forward pointers and more compression
+    /// jumps.
+    #[inline]
+    pub fn lenient() -> Self {
+        ParseOptions {
+            max_jumps: u8::MAX,
+            forward_pointers: true,
+            ..Default::default()
+        }
+    }
 }
@@ -0,0 +1,972 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use crate::body::dnssec::{
+    Algorithm, DigestType, Dnskey, Ds, Nsec, Nsec3, Nsec3Param, Rrsig, TypeBitmap,
+};
+use crate::body::name::Name;
+use crate::body::{Class, RecordData, RecordPreamble, ResourceRecord, Type};
+use crate::validation::{
+    ValidationError, ds_digest, nsec3_hash, rrset, rrsig_labels, signed_data, timestamp,
+};
+
+use ring::rand::SystemRandom;
+use ring::signature::{self, EcdsaKeyPair, Ed25519KeyPair, KeyPair, RsaKeyPair};
+use thiserror::Error;
+
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use std::time::SystemTime;
+
+/// The only valid value of the protocol field of a DNSKEY.
+const DNSKEY_PROTOCOL: u8 = 3;
+/// SHA-1, the only hash algorithm defined for NSEC3.
+const NSEC3_SHA1: u8 = 1;
+
+const BASE32HEX: &[u8; 32] = b"0123456789abcdefghijklmnopqrstuv";
+
+/// An error was encountered when trying to sign DNS data.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum SigningError {
+    /// The algorithm can not be used to sign.
+    #[error("Algorithm {0:?} is not supported for signing.")]
+    UnsupportedAlgorithm(Algorithm),
+    /// The private key is malformed or does not match the algorithm.
+    #[error("The private key was rejected.")]
+    InvalidKey,
+    /// The cryptographic operation failed.
+    #[error("The data could not be signed.")]
+    Signing,
+    /// A zone can not be signed without keys.
+    #[error("There are no keys to sign the zone.")]
+    NoKeys,
+    /// A zone can not be signed without its SOA record.
+    #[error("The zone does not have a SOA record.")]
+    MissingSoa,
+    /// A record of the zone is not a subdomain of the apex.
+    #[error("The name {0} is outside of the zone.")]
+    OutOfZone(String),
+    /// The records could not be put in canonical form.
+    #[error("Invalid RRset: {0}")]
+    InvalidRrset(#[from] ValidationError),
+}
+
+enum KeyPairKind {
+    Ecdsa(EcdsaKeyPair),
+    Ed25519(Ed25519KeyPair),
+    Rsa(RsaKeyPair),
+}
+
+/// A private key of a zone, able to create the RRSIG records of the zone and the DNSKEY and DS
+/// records that refer to it.
+///
+/// ```
+/// # use dominion_parser::body::dnssec::{Algorithm, Dnskey};
+/// # use dominion_parser::body::{Class, RecordData, RecordPreamble, ResourceRecord, Type};
+/// # use dominion_parser::signing::SigningKey;
+/// # use dominion_parser::validation::verify_rrsig;
+/// # use std::time::{Duration, SystemTime};
+/// let owner = "example.com".try_into().unwrap();
+/// let flags = Dnskey::ZONE_KEY;
+/// let (key, _pkcs8) = SigningKey::generate(owner, flags, Algorithm::Ed25519).unwrap();
+///
+/// let preamble = RecordPreamble {
+///     name: "www.example.com".try_into().unwrap(),
+///     rrtype: Type::A,
+///     class: Class::IN,
+///     ttl: 300,
+/// };
+/// let data = RecordData::A("192.0.2.1".parse().unwrap());
+/// let answer = ResourceRecord { preamble, data };
+///
+/// let now = SystemTime::now();
+/// let expiration = now + Duration::from_secs(3600);
+/// let rrsig = key.sign_rrset(&[&answer], now, expiration).unwrap();
+///
+/// let RecordData::Rrsig(rrsig) = &rrsig.data else { panic!() };
+/// assert!(verify_rrsig(&[&answer], rrsig, &key.dnskey_record(3600), now).is_ok());
+/// ```
+pub struct SigningKey<'a> {
+    owner: Name<'a>,
+    flags: u16,
+    algorithm: Algorithm,
+    key_pair: KeyPairKind,
+    public_key: Vec<u8>,
+    key_tag: u16,
+}
+
+impl fmt::Debug for SigningKey<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SigningKey")
+            .field("owner", &self.owner)
+            .field("flags", &self.flags)
+            .field("algorithm", &self.algorithm)
+            .field("key_tag", &self.key_tag)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a> SigningKey<'a> {
+    /// Load the private key of the zone `owner` from a PKCS#8 document.
+    ///
+    /// ECDSA (P-256 and P-384), Ed25519 and RSA (SHA-256 and SHA-512) keys are supported.
+    pub fn from_pkcs8(
+        owner: Name<'a>,
+        flags: u16,
+        algorithm: Algorithm,
+        pkcs8: &[u8],
+    ) -> Result<Self, SigningError> {
+        let (key_pair, public_key) = match algorithm {
+            Algorithm::EcdsaP256Sha256 | Algorithm::EcdsaP384Sha384 => {
+                let rng = SystemRandom::new();
+                let key = EcdsaKeyPair::from_pkcs8(ecdsa_signing(algorithm), pkcs8, &rng)
+                    .map_err(|_| SigningError::InvalidKey)?;
+                // RFC 6605: the public key does not include the uncompressed point prefix.
+                let public_key = key.public_key().as_ref()[1..].to_vec();
+                (KeyPairKind::Ecdsa(key), public_key)
+            }
+            Algorithm::Ed25519 => {
+                let key = Ed25519KeyPair::from_pkcs8_maybe_unchecked(pkcs8)
+                    .map_err(|_| SigningError::InvalidKey)?;
+                let public_key = key.public_key().as_ref().to_vec();
+                (KeyPairKind::Ed25519(key), public_key)
+            }
+            Algorithm::RsaSha256 | Algorithm::RsaSha512 => {
+                let key = RsaKeyPair::from_pkcs8(pkcs8).map_err(|_| SigningError::InvalidKey)?;
+                let components = signature::RsaPublicKeyComponents::<Vec<u8>>::from(key.public());
+                let public_key = rsa_public_key(&components.e, &components.n);
+                (KeyPairKind::Rsa(key), public_key)
+            }
+            other => Err(SigningError::UnsupportedAlgorithm(other))?,
+        };
+        let mut key = SigningKey {
+            owner,
+            flags,
+            algorithm,
+            key_pair,
+            public_key,
+            key_tag: 0,
+        };
+        key.key_tag = key.dnskey().key_tag();
+        Ok(key)
+    }
+
+    /// Generate a new private key for the zone `owner`, returning it together with its PKCS#8
+    /// document so it can be stored.
+    ///
+    /// Only ECDSA and Ed25519 keys can be generated.
+    pub fn generate(
+        owner: Name<'a>,
+        flags: u16,
+        algorithm: Algorithm,
+    ) -> Result<(Self, Vec<u8>), SigningError> {
+        let rng = SystemRandom::new();
+        let pkcs8 = match algorithm {
+            Algorithm::EcdsaP256Sha256 | Algorithm::EcdsaP384Sha384 => {
+                EcdsaKeyPair::generate_pkcs8(ecdsa_signing(algorithm), &rng)
+            }
+            Algorithm::Ed25519 => Ed25519KeyPair::generate_pkcs8(&rng),
+            other => Err(SigningError::UnsupportedAlgorithm(other))?,
+        }
+        .map_err(|_| SigningError::Signing)?;
+        let key = Self::from_pkcs8(owner, flags, algorithm, pkcs8.as_ref())?;
+        Ok((key, pkcs8.as_ref().to_vec()))
+    }
+
+    /// The name of the zone of the key.
+    #[inline]
+    pub fn owner(&self) -> &Name<'a> {
+        &self.owner
+    }
+
+    /// The flags of the DNSKEY of the key.
+    #[inline]
+    pub fn flags(&self) -> u16 {
+        self.flags
+    }
+
+    /// The algorithm of the key.
+    #[inline]
+    pub fn algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+
+    /// The key tag of the DNSKEY of the key.
+    #[inline]
+    pub fn key_tag(&self) -> u16 {
+        self.key_tag
+    }
+
+    /// The public part of the key, as a DNSKEY.
+    pub fn dnskey(&self) -> Dnskey<'static> {
+        Dnskey {
+            flags: self.flags,
+            protocol: DNSKEY_PROTOCOL,
+            algorithm: self.algorithm,
+            public_key: Cow::Owned(self.public_key.clone()),
+        }
+    }
+
+    /// The DNSKEY record of the key, to be published at the apex of the zone.
+    pub fn dnskey_record(&self, ttl: i32) -> ResourceRecord<'a> {
+        self.record(Type::Dnskey, ttl, RecordData::Dnskey(self.dnskey()))
+    }
+
+    /// The DS that refers to the key, to be published in the parent zone.
+    pub fn ds(&self, digest_type: DigestType) -> Result<Ds<'static>, SigningError> {
+        let digest = ds_digest(&self.owner, &self.dnskey(), digest_type)?;
+        Ok(Ds {
+            key_tag: self.key_tag,
+            algorithm: self.algorithm,
+            digest_type,
+            digest: Cow::Owned(digest),
+        })
+    }
+
+    /// The DS record that refers to the key, to be published in the parent zone.
+    pub fn ds_record(
+        &self,
+        ttl: i32,
+        digest_type: DigestType,
+    ) -> Result<ResourceRecord<'a>, SigningError> {
+        Ok(self.record(Type::Ds, ttl, RecordData::Ds(self.ds(digest_type)?)))
+    }
+
+    /// Create the RRSIG record of a RRset, valid between `inception` and `expiration`.
+    pub fn sign_rrset<'b>(
+        &self,
+        rrset: &[&ResourceRecord<'b>],
+        inception: SystemTime,
+        expiration: SystemTime,
+    ) -> Result<ResourceRecord<'b>, SigningError>
+    where
+        'a: 'b,
+    {
+        let preamble = &rrset.first().ok_or(ValidationError::EmptyRrset)?.preamble;
+        let mut rrsig = Rrsig {
+            type_covered: preamble.rrtype,
+            algorithm: self.algorithm,
+            labels: rrsig_labels(&preamble.name) as u8,
+            original_ttl: preamble.ttl as u32,
+            expiration: timestamp(expiration),
+            inception: timestamp(inception),
+            key_tag: self.key_tag,
+            signer: self.owner.clone(),
+            signature: Cow::Borrowed(&[]),
+        };
+        let data = signed_data(rrset, &rrsig)?;
+        rrsig.signature = Cow::Owned(self.sign(&data)?);
+        Ok(ResourceRecord {
+            preamble: RecordPreamble {
+                name: preamble.name.clone(),
+                rrtype: Type::Rrsig,
+                class: preamble.class,
+                ttl: preamble.ttl,
+            },
+            data: RecordData::Rrsig(rrsig),
+        })
+    }
+
+    /// Sign every RRset in `records`, appending the RRSIG records to them.
+    ///
+    /// Useful to sign answers synthesized on the fly, the records that are already RRSIG or OPT
+    /// records are not signed.
+    pub fn sign_records<'b>(
+        &self,
+        records: &mut Vec<ResourceRecord<'b>>,
+        inception: SystemTime,
+        expiration: SystemTime,
+    ) -> Result<(), SigningError>
+    where
+        'a: 'b,
+    {
+        let mut signatures = Vec::new();
+        for (i, rr) in records.iter().enumerate() {
+            let preamble = &rr.preamble;
+            if matches!(preamble.rrtype, Type::Rrsig | Type::Opt) {
+                continue;
+            }
+            let signed = records[..i].iter().any(|prev| {
+                prev.preamble.rrtype == preamble.rrtype && prev.preamble.name == preamble.name
+            });
+            if !signed {
+                let rrset = rrset(records, &preamble.name, preamble.rrtype);
+                signatures.push(self.sign_rrset(&rrset, inception, expiration)?);
+            }
+        }
+        records.extend(signatures);
+        Ok(())
+    }
+
+    fn sign(&self, data: &[u8]) -> Result<Vec<u8>, SigningError> {
+        let rng = SystemRandom::new();
+        match &self.key_pair {
+            KeyPairKind::Ecdsa(key) => key
+                .sign(&rng, data)
+                .map(|sig| sig.as_ref().to_vec())
+                .map_err(|_| SigningError::Signing),
+            KeyPairKind::Ed25519(key) => Ok(key.sign(data).as_ref().to_vec()),
+            KeyPairKind::Rsa(key) => {
+                let padding = if self.algorithm == Algorithm::RsaSha256 {
+                    &signature::RSA_PKCS1_SHA256
+                } else {
+                    &signature::RSA_PKCS1_SHA512
+                };
+                let mut sig = vec![0; key.public().modulus_len()];
+                key.sign(padding, &rng, data, &mut sig)
+                    .map_err(|_| SigningError::Signing)?;
+                Ok(sig)
+            }
+        }
+    }
+
+    fn record(&self, rrtype: Type, ttl: i32, data: RecordData<'static>) -> ResourceRecord<'a> {
+        ResourceRecord {
+            preamble: RecordPreamble {
+                name: self.owner.clone(),
+                rrtype,
+                class: Class::IN,
+                ttl,
+            },
+            data,
+        }
+    }
+}
+
+/// How the non-existence of names and types is proven in a signed zone.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Denial {
+    /// A chain of NSEC records (RFC 4034).
+    Nsec,
+    /// A chain of NSEC3 records (RFC 5155).
+    Nsec3 {
+        /// Additional times the hash function is applied, RFC 9276 recommends 0.
+        iterations: u16,
+        /// Salt appended to the names before hashing, RFC 9276 recommends no salt.
+        salt: Vec<u8>,
+        /// Skip unsigned delegations from the chain.
+        opt_out: bool,
+    },
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum OwnerKind {
+    Authoritative,
+    Delegation,
+    Glue,
+}
+
+/// Sign a whole zone, returning its records together with the DNSKEY records of the `keys`,
+/// the NSEC or NSEC3 chain and the RRSIG records.
+///
+/// The records are returned in canonical order, the DNSSEC records already present in the zone
+/// are replaced. Keys with the secure entry point flag sign the DNSKEY RRset and the rest of the
+/// keys sign every other RRset, if all the keys have (or lack) the flag they sign everything.
+/// Delegations only have their DS and denial records signed, and the glue below them is not
+/// signed.
+pub fn sign_zone<'a>(
+    records: &[ResourceRecord<'a>],
+    keys: &[SigningKey<'a>],
+    denial: &Denial,
+    inception: SystemTime,
+    expiration: SystemTime,
+) -> Result<Vec<ResourceRecord<'a>>, SigningError> {
+    let (apex, soa_ttl, minimum) = records
+        .iter()
+        .find_map(|rr| match &rr.data {
+            RecordData::Soa { minimum, .. } => {
+                Some((rr.preamble.name.clone(), rr.preamble.ttl, *minimum))
+            }
+            _ => None,
+        })
+        .ok_or(SigningError::MissingSoa)?;
+    if keys.is_empty() {
+        Err(SigningError::NoKeys)?
+    }
+    // RFC 9077: the TTL of the denial records is the minimum of the SOA TTL and MINIMUM fields.
+    let denial_ttl = (soa_ttl as u32).min(minimum) as i32;
+
+    let (ksks, zsks): (Vec<_>, Vec<_>) = keys
+        .iter()
+        .partition(|key| key.dnskey().is_secure_entry_point());
+    let key_signers = if ksks.is_empty() { &zsks } else { &ksks };
+    let zone_signers = if zsks.is_empty() { &ksks } else { &zsks };
+
+    let mut zone: Vec<ResourceRecord<'a>> = records
+        .iter()
+        .filter(|rr| {
+            !matches!(
+                rr.preamble.rrtype,
+                Type::Rrsig | Type::Nsec | Type::Nsec3 | Type::Nsec3Param | Type::Dnskey
+            )
+        })
+        .cloned()
+        .collect();
+    if let Some(rr) = zone.iter().find(|rr| !in_zone(&apex, &rr.preamble.name)) {
+        Err(SigningError::OutOfZone(rr.preamble.name.to_string()))?
+    }
+    zone.extend(keys.iter().map(|key| key.dnskey_record(soa_ttl)));
+    if let Denial::Nsec3 {
+        iterations, salt, ..
+    } = denial
+    {
+        let param = Nsec3Param {
+            hash_algorithm: NSEC3_SHA1,
+            flags: 0,
+            iterations: *iterations,
+            salt: Cow::Owned(salt.clone()),
+        };
+        zone.push(ResourceRecord {
+            preamble: RecordPreamble {
+                name: apex.clone(),
+                rrtype: Type::Nsec3Param,
+                class: Class::IN,
+                ttl: 0,
+            },
+            data: RecordData::Nsec3Param(param),
+        });
+    }
+    zone.sort_by(|a, b| {
+        a.preamble
+            .name
+            .cmp(&b.preamble.name)
+            .then(u16::from(a.preamble.rrtype).cmp(&u16::from(b.preamble.rrtype)))
+    });
+
+    let delegations: Vec<&Name<'a>> = zone
+        .iter()
+        .filter(|rr| rr.preamble.rrtype == Type::Ns && rr.preamble.name != apex)
+        .map(|rr| &rr.preamble.name)
+        .collect();
+    let owners: Vec<(&[ResourceRecord<'a>], OwnerKind)> = zone
+        .chunk_by(|a, b| a.preamble.name == b.preamble.name)
+        .map(|group| {
+            let name = &group[0].preamble.name;
+            let kind = if delegations.iter().any(|d| d.is_subdomain(name)) {
+                OwnerKind::Glue
+            } else if delegations.contains(&name) {
+                OwnerKind::Delegation
+            } else {
+                OwnerKind::Authoritative
+            };
+            (group, kind)
+        })
+        .collect();
+    let chain: Vec<_> = owners
+        .iter()
+        .filter(|(_, kind)| *kind != OwnerKind::Glue)
+        .collect();
+
+    let mut signed = Vec::with_capacity(zone.len() * 2);
+    for (i, (group, kind)) in chain.iter().enumerate() {
+        for rrset in group.chunk_by(|a, b| a.preamble.rrtype == b.preamble.rrtype) {
+            let rrtype = rrset[0].preamble.rrtype;
+            if *kind == OwnerKind::Delegation && rrtype != Type::Ds {
+                continue;
+            }
+            let signers = if rrtype == Type::Dnskey {
+                key_signers
+            } else {
+                zone_signers
+            };
+            let rrset: Vec<_> = rrset.iter().collect();
+            for key in signers {
+                signed.push(key.sign_rrset(&rrset, inception, expiration)?);
+            }
+        }
+        if *denial == Denial::Nsec {
+            let (next, _) = chain[(i + 1) % chain.len()];
+            let mut types = types_of(group);
+            types.insert(Type::Rrsig);
+            types.insert(Type::Nsec);
+            let nsec = ResourceRecord {
+                preamble: RecordPreamble {
+                    name: group[0].preamble.name.clone(),
+                    rrtype: Type::Nsec,
+                    class: Class::IN,
+                    ttl: denial_ttl,
+                },
+                data: RecordData::Nsec(Nsec {
+                    next: next[0].preamble.name.clone(),
+                    types,
+                }),
+            };
+            for key in zone_signers {
+                signed.push(key.sign_rrset(&[&nsec], inception, expiration)?);
+            }
+            signed.push(nsec);
+        }
+    }
+    signed.extend(owners.iter().flat_map(|(group, _)| group.iter().cloned()));
+
+    if let Denial::Nsec3 {
+        iterations,
+        salt,
+        opt_out,
+    } = denial
+    {
+        let mut hashes: Vec<(Vec<u8>, TypeBitmap)> = Vec::with_capacity(chain.len());
+        for (group, kind) in &chain {
+            let name = &group[0].preamble.name;
+            let mut types = types_of(group);
+            let unsigned = *kind == OwnerKind::Delegation && !types.contains(Type::Ds);
+            if unsigned && *opt_out {
+                continue;
+            }
+            if !unsigned {
+                types.insert(Type::Rrsig);
+            }
+            hashes.push((nsec3_hash(name, salt, *iterations), types));
+            // Empty non-terminals between the apex and the name also need a NSEC3 record.
+            for labels in apex.label_count() + 1..name.label_count() {
+                let ancestor = ancestor(name, labels);
+                if !owners
+                    .iter()
+                    .any(|(group, _)| group[0].preamble.name == ancestor)
+                {
+                    hashes.push((nsec3_hash(&ancestor, salt, *iterations), TypeBitmap::new()));
+                }
+            }
+        }
+        hashes.sort_by(|a, b| a.0.cmp(&b.0));
+        hashes.dedup_by(|a, b| a.0 == b.0);
+
+        for (i, (hash, types)) in hashes.iter().enumerate() {
+            let mut owner = apex.clone();
+            owner
+                .push_label(Cow::Owned(base32hex(hash)))
+                .map_err(|_| SigningError::OutOfZone(apex.to_string()))?;
+            let nsec3 = ResourceRecord {
+                preamble: RecordPreamble {
+                    name: owner,
+                    rrtype: Type::Nsec3,
+                    class: Class::IN,
+                    ttl: denial_ttl,
+                },
+                data: RecordData::Nsec3(Nsec3 {
+                    hash_algorithm: NSEC3_SHA1,
+                    flags: if *opt_out { Nsec3::OPT_OUT } else { 0 },
+                    iterations: *iterations,
+                    salt: Cow::Owned(salt.clone()),
+                    next_hashed: Cow::Owned(hashes[(i + 1) % hashes.len()].0.clone()),
+                    types: types.clone(),
+                }),
+            };
+            for key in zone_signers {
+                signed.push(key.sign_rrset(&[&nsec3], inception, expiration)?);
+            }
+            signed.push(nsec3);
+        }
+    }
+
+    signed.sort_by(|a, b| {
+        a.preamble
+            .name
+            .cmp(&b.preamble.name)
+            .then(u16::from(a.preamble.rrtype).cmp(&u16::from(b.preamble.rrtype)))
+    });
+    Ok(signed)
+}
+
+#[inline]
+fn in_zone(apex: &Name<'_>, name: &Name<'_>) -> bool {
+    apex == name || apex.is_subdomain(name)
+}
+
+/// The ancestor of `name` with the given number of labels.
+fn ancestor<'n>(name: &'n Name<'_>, labels: usize) -> Name<'n> {
+    let mut ancestor = Name::new();
+    for label in name.iter_hierarchy().take(labels) {
+        ancestor
+            .push(label.clone())
+            .expect("Labels of a valid name are valid");
+    }
+    ancestor
+}
+
+fn types_of(records: &[ResourceRecord<'_>]) -> TypeBitmap {
+    records.iter().map(|rr| rr.preamble.rrtype).collect()
+}
+
+#[inline]
+fn ecdsa_signing(algorithm: Algorithm) -> &'static signature::EcdsaSigningAlgorithm {
+    if algorithm == Algorithm::EcdsaP256Sha256 {
+        &signature::ECDSA_P256_SHA256_FIXED_SIGNING
+    } else {
+        &signature::ECDSA_P384_SHA384_FIXED_SIGNING
+    }
+}
+
+/// Encode a RSA public key in the format of RFC 3110.
+fn rsa_public_key(exponent: &[u8], modulus: &[u8]) -> Vec<u8> {
+    let mut key = Vec::with_capacity(3 + exponent.len() + modulus.len());
+    if exponent.len() > u8::MAX as usize {
+        key.push(0);
+        key.extend((exponent.len() as u16).to_be_bytes());
+    } else {
+        key.push(exponent.len() as u8);
+    }
+    key.extend(exponent);
+    key.extend(modulus);
+    key
+}
+
+/// Encode bytes in lowercase base32 with the extended hex alphabet and without padding.
+fn base32hex(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity((bytes.len() * 8).div_ceil(5));
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for &byte in bytes {
+        buffer = (buffer << 8) | byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(BASE32HEX[((buffer >> bits) & 0x1F) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(BASE32HEX[((buffer << (5 - bits)) & 0x1F) as usize] as char);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validation::{verify_ds, verify_rrsig};
+    use alloc::format;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    const RSA_KEY: &[u8] = include_bytes!("../assets/dnssec_rsa.pk8");
+
+    fn validity() -> (SystemTime, SystemTime) {
+        let inception = UNIX_EPOCH + Duration::from_secs(1_800_000_000);
+        (inception, inception + Duration::from_secs(30 * 24 * 3600))
+    }
+
+    fn record<'a>(name: &'a str, data: RecordData<'a>) -> ResourceRecord<'a> {
+        let rrtype = match &data {
+            RecordData::A(_) => Type::A,
+            RecordData::Ns(_) => Type::Ns,
+            RecordData::Soa { .. } => Type::Soa,
+            RecordData::Mx { .. } => Type::Mx,
+            RecordData::Txt(_) => Type::Txt,
+            RecordData::Ds(_) => Type::Ds,
+            _ => unreachable!("Type not used in tests"),
+        };
+        ResourceRecord {
+            preamble: RecordPreamble {
+                name: name.try_into().unwrap(),
+                rrtype,
+                class: Class::IN,
+                ttl: 3600,
+            },
+            data,
+        }
+    }
+
+    fn zone() -> Vec<ResourceRecord<'static>> {
+        vec![
+            record(
+                "www.example.com",
+                RecordData::A("192.0.2.1".parse().unwrap()),
+            ),
+            record(
+                "example.com",
+                RecordData::Soa {
+                    mname: "ns1.example.com".try_into().unwrap(),
+                    rname: "hostmaster.example.com".try_into().unwrap(),
+                    serial: 1,
+                    refresh: 7200,
+                    retry: 3600,
+                    expire: 1209600,
+                    minimum: 300,
+                },
+            ),
+            record(
+                "example.com",
+                RecordData::Ns("ns1.example.com".try_into().unwrap()),
+            ),
+            record(
+                "example.com",
+                RecordData::Mx {
+                    preference: 10,
+                    exchange: "mail.example.com".try_into().unwrap(),
+                },
+            ),
+            record(
+                "ns1.example.com",
+                RecordData::A("192.0.2.53".parse().unwrap()),
+            ),
+            record(
+                "mail.example.com",
+                RecordData::A("192.0.2.25".parse().unwrap()),
+            ),
+            record("a.b.example.com", RecordData::Txt("ent".into())),
+            record(
+                "sub.example.com",
+                RecordData::Ns("ns.sub.example.com".try_into().unwrap()),
+            ),
+            record(
+                "sub.example.com",
+                RecordData::Ds(Ds {
+                    key_tag: 1,
+                    algorithm: Algorithm::Ed25519,
+                    digest_type: DigestType::Sha256,
+                    digest: Cow::Owned(vec![0xAB; 32]),
+                }),
+            ),
+            record(
+                "ns.sub.example.com",
+                RecordData::A("192.0.2.99".parse().unwrap()),
+            ),
+            record(
+                "unsigned.example.com",
+                RecordData::Ns("ns.example.net".try_into().unwrap()),
+            ),
+        ]
+    }
+
+    fn keys() -> Vec<SigningKey<'static>> {
+        let apex = Name::try_from("example.com").unwrap();
+        let ksk_flags = Dnskey::ZONE_KEY | Dnskey::SECURE_ENTRY_POINT;
+        let (ksk, _) =
+            SigningKey::generate(apex.clone(), ksk_flags, Algorithm::EcdsaP256Sha256).unwrap();
+        let (zsk, _) = SigningKey::generate(apex, Dnskey::ZONE_KEY, Algorithm::Ed25519).unwrap();
+        vec![ksk, zsk]
+    }
+
+    /// Check every RRSIG of the zone with its DNSKEY records, returning the number of them.
+    fn verify_zone(zone: &[ResourceRecord<'_>]) -> usize {
+        let (now, _) = validity();
+        let dnskeys: Vec<_> = zone
+            .iter()
+            .filter(|rr| rr.preamble.rrtype == Type::Dnskey)
+            .collect();
+        let mut verified = 0;
+        for rr in zone {
+            let RecordData::Rrsig(rrsig) = &rr.data else {
+                continue;
+            };
+            let rrset = rrset(zone, &rr.preamble.name, rrsig.type_covered);
+            let valid = dnskeys
+                .iter()
+                .any(|key| verify_rrsig(&rrset, rrsig, key, now).is_ok());
+            assert!(valid, "Invalid RRSIG at {}", rr.preamble.name);
+            verified += 1;
+        }
+        verified
+    }
+
+    fn has_rrsig(zone: &[ResourceRecord<'_>], name: &str, rrtype: Type) -> bool {
+        let name = Name::try_from(name).unwrap();
+        !crate::validation::rrsigs(zone, &name, rrtype).is_empty()
+    }
+
+    #[test]
+    fn sign_algorithms() {
+        let (inception, expiration) = validity();
+        let owner = Name::try_from("example.com").unwrap();
+        let mut keys = vec![
+            SigningKey::from_pkcs8(
+                owner.clone(),
+                Dnskey::ZONE_KEY,
+                Algorithm::RsaSha256,
+                RSA_KEY,
+            )
+            .unwrap(),
+        ];
+        for algorithm in [
+            Algorithm::EcdsaP256Sha256,
+            Algorithm::EcdsaP384Sha384,
+            Algorithm::Ed25519,
+        ] {
+            let (key, pkcs8) =
+                SigningKey::generate(owner.clone(), Dnskey::ZONE_KEY, algorithm).unwrap();
+            let loaded =
+                SigningKey::from_pkcs8(owner.clone(), Dnskey::ZONE_KEY, algorithm, &pkcs8).unwrap();
+            assert_eq!(key.dnskey(), loaded.dnskey());
+            keys.push(key);
+        }
+
+        let answer = record(
+            "www.example.com",
+            RecordData::A("192.0.2.1".parse().unwrap()),
+        );
+        for key in keys {
+            let rrsig = key.sign_rrset(&[&answer], inception, expiration).unwrap();
+            let RecordData::Rrsig(rrsig) = &rrsig.data else {
+                panic!("Expected RRSIG record")
+            };
+            assert_eq!(rrsig.key_tag, key.key_tag());
+            verify_rrsig(&[&answer], rrsig, &key.dnskey_record(3600), inception).unwrap();
+        }
+    }
+
+    #[test]
+    fn unsupported_algorithm() {
+        let owner = Name::try_from("example.com").unwrap();
+        let err = SigningKey::generate(owner, Dnskey::ZONE_KEY, Algorithm::RsaSha1).unwrap_err();
+        assert_eq!(err, SigningError::UnsupportedAlgorithm(Algorithm::RsaSha1));
+    }
+
+    #[test]
+    fn ds_of_key() {
+        let keys = keys();
+        let ds = keys[0].ds(DigestType::Sha256).unwrap();
+        assert_eq!(ds.key_tag, keys[0].key_tag());
+        verify_ds(&ds, &keys[0].dnskey_record(3600)).unwrap();
+        assert!(verify_ds(&ds, &keys[1].dnskey_record(3600)).is_err());
+    }
+
+    #[test]
+    fn sign_synthesized_answer() {
+        let (inception, expiration) = validity();
+        let keys = keys();
+        let mut answers = vec![
+            record(
+                "www.example.com",
+                RecordData::A("192.0.2.1".parse().unwrap()),
+            ),
+            record("www.example.com", RecordData::Txt("hello".into())),
+            record(
+                "www.example.com",
+                RecordData::A("192.0.2.2".parse().unwrap()),
+            ),
+        ];
+        keys[1]
+            .sign_records(&mut answers, inception, expiration)
+            .unwrap();
+
+        assert_eq!(answers.len(), 5);
+        let mut zone = answers.clone();
+        zone.push(keys[1].dnskey_record(3600));
+        assert_eq!(verify_zone(&zone), 2);
+    }
+
+    #[test]
+    fn sign_zone_nsec() {
+        let (inception, expiration) = validity();
+        let signed = sign_zone(&zone(), &keys(), &Denial::Nsec, inception, expiration).unwrap();
+
+        // One RRSIG for each of the 4 apex RRsets, the 4 authoritative RRsets below it, the DS
+        // of the delegation and the 7 NSEC records
+        assert_eq!(verify_zone(&signed), 4 + 4 + 1 + 7);
+        assert!(has_rrsig(&signed, "example.com", Type::Dnskey));
+        assert!(has_rrsig(&signed, "sub.example.com", Type::Ds));
+        assert!(!has_rrsig(&signed, "sub.example.com", Type::Ns));
+        assert!(!has_rrsig(&signed, "ns.sub.example.com", Type::A));
+        assert!(!has_rrsig(&signed, "unsigned.example.com", Type::Ns));
+
+        let chain: Vec<_> = signed
+            .iter()
+            .filter_map(|rr| match &rr.data {
+                RecordData::Nsec(nsec) => Some((rr.preamble.name.to_string(), nsec)),
+                _ => None,
+            })
+            .collect();
+        let owners: Vec<_> = chain.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(
+            owners,
+            vec![
+                "example.com.",
+                "a.b.example.com.",
+                "mail.example.com.",
+                "ns1.example.com.",
+                "sub.example.com.",
+                "unsigned.example.com.",
+                "www.example.com.",
+            ]
+        );
+        for (i, (_, nsec)) in chain.iter().enumerate() {
+            let next = (i + 1) % owners.len();
+            assert_eq!(nsec.next.to_string(), owners[next]);
+        }
+        assert!(chain[0].1.types.contains(Type::Dnskey));
+        assert!(chain[4].1.types.contains(Type::Ds));
+    }
+
+    #[test]
+    fn sign_zone_nsec3() {
+        let (inception, expiration) = validity();
+        let salt = vec![0xaa, 0xbb];
+        let denial = Denial::Nsec3 {
+            iterations: 1,
+            salt: salt.clone(),
+            opt_out: false,
+        };
+        let signed = sign_zone(&zone(), &keys(), &denial, inception, expiration).unwrap();
+
+        // Same as with NSEC, plus the NSEC3PARAM and an empty non-terminal in the chain
+        assert_eq!(verify_zone(&signed), 5 + 4 + 1 + 8);
+
+        let chain: Vec<_> = signed
+            .iter()
+            .filter_map(|rr| match &rr.data {
+                RecordData::Nsec3(nsec3) => Some((rr.preamble.name.to_string(), nsec3)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(chain.len(), 8);
+        for (i, (_, nsec3)) in chain.iter().enumerate() {
+            let (next, _) = &chain[(i + 1) % chain.len()];
+            let next_hashed = format!("{}.example.com.", base32hex(&nsec3.next_hashed));
+            assert_eq!(&next_hashed, next);
+            assert_eq!(nsec3.iterations, 1);
+            assert_eq!(&nsec3.salt[..], &salt[..]);
+        }
+
+        let ent = Name::try_from("b.example.com").unwrap();
+        let owner = format!("{}.example.com.", base32hex(&nsec3_hash(&ent, &salt, 1)));
+        let ent = signed
+            .iter()
+            .find(|rr| rr.preamble.rrtype == Type::Nsec3 && rr.preamble.name.to_string() == owner)
+            .unwrap();
+        let RecordData::Nsec3(ent) = &ent.data else {
+            panic!("Expected NSEC3 record")
+        };
+        assert!(ent.types.is_empty());
+
+        let opt_out = Denial::Nsec3 {
+            iterations: 0,
+            salt: vec![],
+            opt_out: true,
+        };
+        let signed = sign_zone(&zone(), &keys(), &opt_out, inception, expiration).unwrap();
+        let chain = signed
+            .iter()
+            .filter(|rr| rr.preamble.rrtype == Type::Nsec3)
+            .count();
+        assert_eq!(chain, 7);
+    }
+
+    #[test]
+    fn sign_zone_errors() {
+        let (inception, expiration) = validity();
+        let mut zone = zone();
+        zone.push(record(
+            "www.example.org",
+            RecordData::A("192.0.2.1".parse().unwrap()),
+        ));
+        let err = sign_zone(&zone, &keys(), &Denial::Nsec, inception, expiration).unwrap_err();
+        assert_eq!(err, SigningError::OutOfZone("www.example.org.".to_string()));
+
+        let err = sign_zone(&zone, &[], &Denial::Nsec, inception, expiration).unwrap_err();
+        assert_eq!(err, SigningError::NoKeys);
+
+        let err = sign_zone(&zone[..1], &keys(), &Denial::Nsec, inception, expiration);
+        assert_eq!(err.unwrap_err(), SigningError::MissingSoa);
+    }
+
+    #[test]
+    fn base32hex_encoding() {
+        let name = Name::try_from("example").unwrap();
+        let hash = nsec3_hash(&name, &[0xaa, 0xbb, 0xcc, 0xdd], 12);
+        assert_eq!(base32hex(&hash), "0p9mhaveqvm6t7vbl5lop2u3t2rp3tom");
+    }
+}
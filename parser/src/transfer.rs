@@ -0,0 +1,655 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use crate::DnsPacket;
+use crate::body::{QType, Question, RecordData, ResourceRecord, Type};
+use crate::header::{AuthoritativeAnswer, DnsHeader, ExtendedResponseCode, Flags, QueryResponse};
+
+use thiserror::Error;
+
+use alloc::vec::Vec;
+use core::fmt;
+use core::iter::{self, Peekable};
+
+/// The maximum size of a DNS message over TCP, which is prefixed by its length in two bytes.
+pub const MAX_MESSAGE_SIZE: usize = 65535;
+
+/// An error was encountered when consuming the messages of a zone transfer.
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferError {
+    /// The server answered with an error.
+    #[error("The server answered with error {0:?}.")]
+    Rcode(ExtendedResponseCode),
+    /// A message does not answer the request.
+    #[error("The message with ID {0} does not answer the request.")]
+    Id(u16),
+    /// The first record of the transfer is not a SOA record.
+    #[error("The transfer does not start with a SOA record.")]
+    MissingSoa,
+    /// A SOA record was found where it is not allowed.
+    #[error("Unexpected SOA record with serial {0}.")]
+    UnexpectedSoa(u32),
+    /// More records were received after the end of the transfer.
+    #[error("There are records after the end of the transfer.")]
+    Trailing,
+    /// The transfer has not been completed.
+    #[error("The transfer is incomplete.")]
+    Incomplete,
+}
+
+/// The changes between two versions of a zone, as sent in IXFR (RFC 1995).
+#[derive(Debug, Clone)]
+pub struct Difference<'a> {
+    /// The SOA record of the version being changed.
+    pub from: ResourceRecord<'a>,
+    /// The records deleted from the zone.
+    pub deleted: Vec<ResourceRecord<'a>>,
+    /// The SOA record of the new version.
+    pub to: ResourceRecord<'a>,
+    /// The records added to the zone.
+    pub added: Vec<ResourceRecord<'a>>,
+}
+
+/// The result of a zone transfer.
+#[derive(Debug, Clone)]
+pub enum Transfer<'a> {
+    /// The full zone, sent for AXFR or for IXFR when the server does not have the differences.
+    Full {
+        /// The SOA record of the zone.
+        soa: ResourceRecord<'a>,
+        /// The rest of the records of the zone.
+        records: Vec<ResourceRecord<'a>>,
+    },
+    /// The differences from the version of the client to the current one.
+    Incremental {
+        /// The current SOA record of the zone.
+        soa: ResourceRecord<'a>,
+        /// The differences in order, from the version of the client to the current one.
+        differences: Vec<Difference<'a>>,
+    },
+    /// The version of the client is the current one.
+    UpToDate(ResourceRecord<'a>),
+}
+
+/// Splits the records of a zone transfer response in messages that fit in TCP (RFC 5936).
+///
+/// The question of the request is copied in the first message. The messages are built before
+/// compression, so they may be smaller than the maximum size once serialized. A single record
+/// bigger than the maximum size is sent alone in a message.
+///
+/// The first message always contains at least two records when more follow, even if they do
+/// not fit, because a first message with a single SOA record tells IXFR clients that they are
+/// up to date (RFC 1995).
+///
+/// ```
+/// # use dominion_parser::DnsPacket;
+/// # use dominion_parser::body::{Class, RecordData, RecordPreamble, ResourceRecord, Type};
+/// # use dominion_parser::transfer::{Transfer, TransferReader, axfr_response};
+/// # const REQ: &'static [u8; 33] = include_bytes!("../assets/dns_request.bin");
+/// # let request = DnsPacket::try_from(&REQ[..]).unwrap();
+/// let soa = ResourceRecord {
+///     preamble: RecordPreamble {
+///         name: "example.com".try_into().unwrap(),
+///         rrtype: Type::Soa,
+///         class: Class::IN,
+///         ttl: 3600,
+///     },
+///     data: RecordData::Soa {
+///         mname: "ns.example.com".try_into().unwrap(),
+///         rname: "hostmaster.example.com".try_into().unwrap(),
+///         serial: 2026101701,
+///         refresh: 7200,
+///         retry: 3600,
+///         expire: 1209600,
+///         minimum: 300,
+///     },
+/// };
+/// let zone: Vec<ResourceRecord<'_>> = Vec::new();
+///
+/// let mut reader = TransferReader::new(&request);
+/// for message in axfr_response(&request, soa, zone) {
+///     reader.push(&message).unwrap();
+/// }
+/// assert!(matches!(reader.finish(), Ok(Transfer::Full { .. })));
+/// ```
+pub struct TransferStream<'a, I>
+where
+    I: Iterator<Item = ResourceRecord<'a>>,
+{
+    header: DnsHeader,
+    question: Option<Question<'a>>,
+    records: Peekable<I>,
+    max_size: usize,
+    first: bool,
+    scratch: Vec<u8>,
+}
+
+impl<'a, I> fmt::Debug for TransferStream<'a, I>
+where
+    I: Iterator<Item = ResourceRecord<'a>>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TransferStream")
+            .field("header", &self.header)
+            .field("question", &self.question)
+            .field("max_size", &self.max_size)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a, I> TransferStream<'a, I>
+where
+    I: Iterator<Item = ResourceRecord<'a>>,
+{
+    /// Answer the `request` with the given sequence of records, which has to be already
+    /// bracketed by the SOA records.
+    pub fn new<T>(request: &DnsPacket<'a>, records: T) -> Self
+    where
+        T: IntoIterator<IntoIter = I>,
+    {
+        let mut flags = Flags::from(0);
+        flags.qr = QueryResponse::Response;
+        flags.aa = AuthoritativeAnswer::Authoritative;
+        flags.rd = request.header.flags.rd;
+        TransferStream {
+            header: DnsHeader {
+                id: request.header.id,
+                flags,
+                questions: 0,
+                answers: 0,
+                authority: 0,
+                additional: 0,
+            },
+            question: request.questions.first().cloned(),
+            records: records.into_iter().peekable(),
+            max_size: MAX_MESSAGE_SIZE,
+            first: true,
+            scratch: Vec::new(),
+        }
+    }
+
+    /// Set the maximum size of the messages, for example to leave room for a TSIG record.
+    #[inline]
+    pub fn max_size(mut self, max_size: usize) -> Self {
+        self.max_size = max_size;
+        self
+    }
+}
+
+impl<'a, I> Iterator for TransferStream<'a, I>
+where
+    I: Iterator<Item = ResourceRecord<'a>>,
+{
+    type Item = DnsPacket<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.records.peek()?;
+        let questions: Vec<_> = self.question.take().into_iter().collect();
+        self.scratch.clear();
+        for question in &questions {
+            question.serialize(&mut self.scratch);
+        }
+        let mut size = 12 + self.scratch.len();
+        let min_answers = if self.first { 2 } else { 1 };
+        self.first = false;
+        let mut answers = Vec::new();
+        while let Some(rr) = self.records.peek() {
+            self.scratch.clear();
+            rr.serialize(&mut self.scratch);
+            if answers.len() >= min_answers && size + self.scratch.len() > self.max_size {
+                break;
+            }
+            size += self.scratch.len();
+            answers.extend(self.records.next());
+        }
+        Some(DnsPacket {
+            header: self.header.clone(),
+            questions,
+            answers,
+            authority: Vec::new(),
+            additional: Vec::new(),
+        })
+    }
+}
+
+/// Answer an AXFR `request` with the records of the zone, bracketed by its `soa` (RFC 5936).
+///
+/// The SOA records found in `records` are skipped.
+pub fn axfr_response<'a, T>(
+    request: &DnsPacket<'a>,
+    soa: ResourceRecord<'a>,
+    records: T,
+) -> TransferStream<'a, impl Iterator<Item = ResourceRecord<'a>>>
+where
+    T: IntoIterator<Item = ResourceRecord<'a>>,
+{
+    let records = records
+        .into_iter()
+        .filter(|rr| rr.preamble.rrtype != Type::Soa);
+    let sequence = iter::once(soa.clone())
+        .chain(records)
+        .chain(iter::once(soa));
+    TransferStream::new(request, sequence)
+}
+
+/// Answer an IXFR `request` with the differences from the version of the client to the current
+/// one, whose SOA record is `soa` (RFC 1995).
+///
+/// If there are no differences only the SOA record is sent, which tells the client that it is
+/// up to date.
+pub fn ixfr_response<'a, T>(
+    request: &DnsPacket<'a>,
+    soa: ResourceRecord<'a>,
+    differences: T,
+) -> TransferStream<'a, impl Iterator<Item = ResourceRecord<'a>>>
+where
+    T: IntoIterator<Item = Difference<'a>>,
+{
+    let mut differences = differences.into_iter().peekable();
+    let last = differences.peek().is_some().then(|| soa.clone());
+    let changes = differences.flat_map(|difference| {
+        iter::once(difference.from)
+            .chain(difference.deleted)
+            .chain(iter::once(difference.to))
+            .chain(difference.added)
+    });
+    let sequence = iter::once(soa).chain(changes).chain(last);
+    TransferStream::new(request, sequence)
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum State {
+    Start,
+    First,
+    Full,
+    Deleting,
+    Adding,
+    Done,
+}
+
+/// Consumes the messages of a zone transfer response on the client, for both AXFR and IXFR.
+///
+/// The end of the transfer is detected from the SOA records, as the number of messages is not
+/// known in advance.
+#[derive(Debug, Clone)]
+pub struct TransferReader<'a> {
+    id: u16,
+    incremental: bool,
+    state: State,
+    soa: Option<ResourceRecord<'a>>,
+    records: Vec<ResourceRecord<'a>>,
+    differences: Vec<Difference<'a>>,
+}
+
+impl<'a> TransferReader<'a> {
+    /// Consume the response to `request`, which has to be an AXFR or IXFR query.
+    pub fn new(request: &DnsPacket<'_>) -> Self {
+        let incremental = request
+            .questions
+            .first()
+            .is_some_and(|q| q.qtype == QType::Ixfr);
+        TransferReader {
+            id: request.header.id,
+            incremental,
+            state: State::Start,
+            soa: None,
+            records: Vec::new(),
+            differences: Vec::new(),
+        }
+    }
+
+    /// Consume the next message of the response, returning `true` if the transfer is complete.
+    pub fn push(&mut self, message: &DnsPacket<'a>) -> Result<bool, TransferError> {
+        if message.header.id != self.id {
+            Err(TransferError::Id(message.header.id))?
+        }
+        let rcode = message.rcode();
+        if rcode != ExtendedResponseCode::NoError {
+            Err(TransferError::Rcode(rcode))?
+        }
+        for rr in &message.answers {
+            self.record(rr.clone())?;
+        }
+        // RFC 1995: a single SOA record means that the client is up to date.
+        if self.incremental && self.state == State::First {
+            self.state = State::Done;
+        }
+        Ok(self.state == State::Done)
+    }
+
+    /// Finish the transfer, obtaining the records received.
+    pub fn finish(self) -> Result<Transfer<'a>, TransferError> {
+        if self.state != State::Done {
+            Err(TransferError::Incomplete)?
+        }
+        let soa = self.soa.ok_or(TransferError::Incomplete)?;
+        if !self.differences.is_empty() {
+            Ok(Transfer::Incremental {
+                soa,
+                differences: self.differences,
+            })
+        } else if self.incremental && self.records.is_empty() {
+            Ok(Transfer::UpToDate(soa))
+        } else {
+            Ok(Transfer::Full {
+                soa,
+                records: self.records,
+            })
+        }
+    }
+
+    fn record(&mut self, rr: ResourceRecord<'a>) -> Result<(), TransferError> {
+        let serial = match rr.data {
+            RecordData::Soa { serial, .. } => Some(serial),
+            _ => None,
+        };
+        let current = self.soa.as_ref().and_then(soa_serial);
+        match (self.state, serial) {
+            (State::Start, Some(_)) => {
+                self.soa = Some(rr);
+                self.state = State::First;
+            }
+            (State::Start, None) => Err(TransferError::MissingSoa)?,
+            (State::First | State::Full, Some(serial)) if Some(serial) == current => {
+                self.state = State::Done;
+            }
+            (State::First, Some(_)) if self.incremental => {
+                self.start_difference(rr);
+            }
+            (State::First | State::Full, Some(serial)) => {
+                Err(TransferError::UnexpectedSoa(serial))?
+            }
+            (State::First | State::Full, None) => {
+                self.records.push(rr);
+                self.state = State::Full;
+            }
+            (State::Deleting, Some(_)) => {
+                let difference = self.differences.last_mut().expect("Deleting a difference");
+                difference.to = rr;
+                self.state = State::Adding;
+            }
+            (State::Deleting, None) => {
+                let difference = self.differences.last_mut().expect("Deleting a difference");
+                difference.deleted.push(rr);
+            }
+            (State::Adding, Some(serial)) => {
+                let difference = self.differences.last().expect("Adding a difference");
+                if Some(serial) == current && soa_serial(&difference.to) == current {
+                    self.state = State::Done;
+                } else {
+                    self.start_difference(rr);
+                }
+            }
+            (State::Adding, None) => {
+                let difference = self.differences.last_mut().expect("Adding a difference");
+                difference.added.push(rr);
+            }
+            (State::Done, _) => Err(TransferError::Trailing)?,
+        }
+        Ok(())
+    }
+
+    fn start_difference(&mut self, from: ResourceRecord<'a>) {
+        self.differences.push(Difference {
+            to: from.clone(),
+            from,
+            deleted: Vec::new(),
+            added: Vec::new(),
+        });
+        self.state = State::Deleting;
+    }
+}
+
+#[inline]
+fn soa_serial(rr: &ResourceRecord<'_>) -> Option<u32> {
+    match rr.data {
+        RecordData::Soa { serial, .. } => Some(serial),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::body::{Class, RecordPreamble};
+    use crate::header::ResponseCode;
+    use alloc::format;
+    use alloc::string::{String, ToString};
+    use alloc::vec;
+
+    fn request(qtype: QType) -> DnsPacket<'static> {
+        DnsPacket {
+            header: DnsHeader {
+                id: 0x1234,
+                flags: Flags::from(0),
+                questions: 0,
+                answers: 0,
+                authority: 0,
+                additional: 0,
+            },
+            questions: vec![Question {
+                name: "example.com".try_into().unwrap(),
+                qtype,
+                class: Class::IN,
+            }],
+            answers: Vec::new(),
+            authority: Vec::new(),
+            additional: Vec::new(),
+        }
+    }
+
+    fn soa(serial: u32) -> ResourceRecord<'static> {
+        ResourceRecord {
+            preamble: RecordPreamble {
+                name: "example.com".try_into().unwrap(),
+                rrtype: Type::Soa,
+                class: Class::IN,
+                ttl: 3600,
+            },
+            data: RecordData::Soa {
+                mname: "ns.example.com".try_into().unwrap(),
+                rname: "hostmaster.example.com".try_into().unwrap(),
+                serial,
+                refresh: 7200,
+                retry: 3600,
+                expire: 1209600,
+                minimum: 300,
+            },
+        }
+    }
+
+    fn txt(name: String, text: String) -> ResourceRecord<'static> {
+        ResourceRecord {
+            preamble: RecordPreamble {
+                name: name.try_into().unwrap(),
+                rrtype: Type::Txt,
+                class: Class::IN,
+                ttl: 300,
+            },
+            data: RecordData::Txt(text.into()),
+        }
+    }
+
+    /// Serialize and parse the messages, like they would be sent over TCP.
+    fn send<'a>(messages: impl Iterator<Item = DnsPacket<'a>>) -> Vec<Vec<u8>> {
+        messages.map(|message| Vec::from(&message)).collect()
+    }
+
+    fn receive<'a>(
+        request: &DnsPacket<'_>,
+        messages: &'a [Vec<u8>],
+    ) -> Result<Transfer<'a>, TransferError> {
+        let mut reader = TransferReader::new(request);
+        for (i, message) in messages.iter().enumerate() {
+            let message = DnsPacket::try_from(&message[..]).unwrap();
+            let done = reader.push(&message)?;
+            assert_eq!(done, i == messages.len() - 1);
+        }
+        reader.finish()
+    }
+
+    #[test]
+    fn axfr_split() {
+        let request = request(QType::Axfr);
+        let zone: Vec<_> = (0..2000)
+            .map(|i| txt(format!("host{i}.example.com"), "x".repeat(100)))
+            .collect();
+        let messages = send(axfr_response(&request, soa(1), zone.clone()));
+
+        assert!(messages.len() > 3);
+        assert!(messages.iter().all(|m| m.len() <= MAX_MESSAGE_SIZE));
+        let first = DnsPacket::try_from(&messages[0][..]).unwrap();
+        assert_eq!(first.questions.len(), 1);
+        assert_eq!(first.header.flags.qr, QueryResponse::Response);
+        let second = DnsPacket::try_from(&messages[1][..]).unwrap();
+        assert!(second.questions.is_empty());
+
+        let Transfer::Full { soa, records } = receive(&request, &messages).unwrap() else {
+            panic!("Expected full transfer")
+        };
+        assert_eq!(soa_serial(&soa), Some(1));
+        assert_eq!(records.len(), zone.len());
+        assert_eq!(
+            records[1999].preamble.name.to_string(),
+            "host1999.example.com."
+        );
+    }
+
+    #[test]
+    fn axfr_max_size() {
+        let request = request(QType::Axfr);
+        let zone: Vec<_> = (0..10)
+            .map(|i| txt(format!("host{i}.example.com"), "x".repeat(100)))
+            .collect();
+        let messages = axfr_response(&request, soa(1), zone).max_size(512);
+        let sizes: Vec<_> = messages.map(|m| m.answers.len()).collect();
+        assert_eq!(sizes.iter().sum::<usize>(), 12);
+        assert!(sizes.len() > 2);
+    }
+
+    #[test]
+    fn axfr_empty_zone() {
+        let request = request(QType::Axfr);
+        let messages = send(axfr_response(&request, soa(7), vec![soa(7)]));
+        assert_eq!(messages.len(), 1);
+        let Transfer::Full { records, .. } = receive(&request, &messages).unwrap() else {
+            panic!("Expected full transfer")
+        };
+        assert!(records.is_empty());
+    }
+
+    #[test]
+    fn ixfr_differences() {
+        let request = request(QType::Ixfr);
+        let differences = vec![
+            Difference {
+                from: soa(1),
+                deleted: vec![txt("a.example.com".into(), "old".into())],
+                to: soa(2),
+                added: vec![txt("a.example.com".into(), "new".into())],
+            },
+            Difference {
+                from: soa(2),
+                deleted: Vec::new(),
+                to: soa(3),
+                added: vec![
+                    txt("b.example.com".into(), "b".into()),
+                    txt("c.example.com".into(), "c".into()),
+                ],
+            },
+        ];
+        let messages = send(ixfr_response(&request, soa(3), differences).max_size(200));
+        assert!(messages.len() > 1);
+
+        let Transfer::Incremental { soa, differences } = receive(&request, &messages).unwrap()
+        else {
+            panic!("Expected incremental transfer")
+        };
+        assert_eq!(soa_serial(&soa), Some(3));
+        assert_eq!(differences.len(), 2);
+        assert_eq!(soa_serial(&differences[0].from), Some(1));
+        assert_eq!(soa_serial(&differences[0].to), Some(2));
+        assert_eq!(differences[0].deleted.len(), 1);
+        assert_eq!(differences[0].added.len(), 1);
+        assert_eq!(soa_serial(&differences[1].from), Some(2));
+        assert_eq!(soa_serial(&differences[1].to), Some(3));
+        assert!(differences[1].deleted.is_empty());
+        assert_eq!(differences[1].added.len(), 2);
+    }
+
+    #[test]
+    fn ixfr_up_to_date_and_full() {
+        let request = request(QType::Ixfr);
+        let messages = send(ixfr_response(&request, soa(3), Vec::new()));
+        assert!(matches!(
+            receive(&request, &messages),
+            Ok(Transfer::UpToDate(_))
+        ));
+
+        // The server can answer IXFR with the full zone.
+        let zone = vec![txt("a.example.com".into(), "a".into())];
+        let messages = send(axfr_response(&request, soa(3), zone));
+        assert!(matches!(
+            receive(&request, &messages),
+            Ok(Transfer::Full { .. })
+        ));
+    }
+
+    #[test]
+    fn ixfr_full_small_messages() {
+        let request = request(QType::Ixfr);
+        let zone: Vec<_> = (0..5)
+            .map(|i| txt(format!("host{i}.example.com"), "x".repeat(100)))
+            .collect();
+        // Only the question and the SOA record fit in the first message.
+        let messages = send(axfr_response(&request, soa(3), zone).max_size(100));
+        let first = DnsPacket::try_from(&messages[0][..]).unwrap();
+        assert_eq!(first.answers.len(), 2);
+
+        let Transfer::Full { records, .. } = receive(&request, &messages).unwrap() else {
+            panic!("Expected full transfer")
+        };
+        assert_eq!(records.len(), 5);
+    }
+
+    #[test]
+    fn reader_errors() {
+        let request = request(QType::Axfr);
+        let mut reader = TransferReader::new(&request);
+        let mut message = axfr_response(&request, soa(1), Vec::new()).next().unwrap();
+
+        message.header.id = 1;
+        assert_eq!(reader.push(&message), Err(TransferError::Id(1)));
+
+        message.header.id = 0x1234;
+        message.header.flags.rcode = ResponseCode::NotAuth;
+        assert_eq!(
+            reader.push(&message),
+            Err(TransferError::Rcode(ExtendedResponseCode::NotAuth))
+        );
+
+        message.header.flags.rcode = ResponseCode::NoError;
+        message.answers.remove(0);
+        message
+            .answers
+            .insert(0, txt("a.example.com".into(), "a".into()));
+        assert_eq!(reader.push(&message), Err(TransferError::MissingSoa));
+
+        let mut reader = TransferReader::new(&request);
+        let mut message = axfr_response(&request, soa(1), Vec::new()).next().unwrap();
+        message.answers.push(soa(1));
+        assert_eq!(reader.push(&message), Err(TransferError::Trailing));
+
+        let mut reader = TransferReader::new(&request);
+        let mut message = axfr_response(&request, soa(1), Vec::new()).next().unwrap();
+        message.answers[1] = soa(2);
+        assert_eq!(reader.push(&message), Err(TransferError::UnexpectedSoa(2)));
+
+        let mut reader = TransferReader::new(&request);
+        let mut message = axfr_response(&request, soa(1), Vec::new()).next().unwrap();
+        message.answers.pop();
+        assert_eq!(reader.push(&message), Ok(false));
+        assert_eq!(reader.finish().unwrap_err(), TransferError::Incomplete);
+    }
+}
@@ -0,0 +1,825 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use crate::binutils::*;
+use crate::body::name::Name;
+use crate::body::tsig::Tsig;
+use crate::body::{Class, Question, RecordData, RecordPreamble, ResourceRecord, Type};
+use crate::header::{DnsHeader, ExtendedResponseCode, Flags, QueryResponse};
+use crate::{DnsPacket, INIT_PACKET_SIZE, ParseError};
+
+use ring::hmac;
+use thiserror::Error;
+
+use alloc::borrow::Cow;
+use alloc::vec::Vec;
+use core::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The fudge of the signatures, the seconds of difference allowed between the clocks of the
+/// signer and the verifier (RFC 8945 recommends 300).
+pub const DEFAULT_FUDGE: u16 = 300;
+
+/// Number of consecutive messages without TSIG accepted in a stream (RFC 8945 section 5.3.1).
+const MAX_UNSIGNED: usize = 99;
+
+/// An error was encountered when verifying the TSIG record of a message.
+#[derive(Error, Debug)]
+pub enum TsigError {
+    /// The message is not signed.
+    #[error("The message does not have a TSIG record.")]
+    Unsigned,
+    /// The TSIG record is not the last record of the message.
+    #[error("The TSIG record is not the last record of the message.")]
+    NotLast,
+    /// The key is not known or it uses a different algorithm.
+    #[error("The key of the TSIG record is not known.")]
+    BadKey,
+    /// The MAC does not match the message.
+    #[error("The MAC of the TSIG record is not valid.")]
+    BadSig,
+    /// The message was signed outside of the time allowed by the fudge.
+    #[error("The TSIG record was signed outside of the allowed time.")]
+    BadTime,
+    /// The MAC has been truncated, which is not accepted.
+    #[error("The MAC of the TSIG record is truncated.")]
+    BadTrunc,
+    /// The MAC is longer than the output of the algorithm or shorter than allowed by RFC 8945.
+    #[error("The MAC of the TSIG record has an invalid size of {0} bytes.")]
+    MacSize(usize),
+    /// The other end could not verify our signature and answered with this error.
+    #[error("The signature was rejected with error {0:?}.")]
+    Rejected(ExtendedResponseCode),
+    /// The message could not be parsed.
+    #[error("The message could not be parsed: {0}")]
+    Parse(#[from] ParseError),
+}
+
+impl TsigError {
+    /// The response code of the response to a request that failed verification with this error.
+    #[inline]
+    pub fn rcode(&self) -> ExtendedResponseCode {
+        match self {
+            Self::BadKey | Self::BadSig | Self::BadTime | Self::BadTrunc => {
+                ExtendedResponseCode::NotAuth
+            }
+            Self::Unsigned => ExtendedResponseCode::Refused,
+            _ => ExtendedResponseCode::FormErr,
+        }
+    }
+
+    /// The error of the TSIG record in the response to a request that failed verification with
+    /// this error, if the response has one.
+    #[inline]
+    pub fn tsig_error(&self) -> Option<ExtendedResponseCode> {
+        match self {
+            Self::BadKey => Some(ExtendedResponseCode::BadKey),
+            Self::BadSig => Some(ExtendedResponseCode::BAD_SIG),
+            Self::BadTime => Some(ExtendedResponseCode::BadTime),
+            Self::BadTrunc => Some(ExtendedResponseCode::BadTrunc),
+            _ => None,
+        }
+    }
+}
+
+/// The algorithm used to compute the MAC of the messages.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TsigAlgorithm {
+    /// HMAC with SHA-256 (`hmac-sha256`).
+    HmacSha256,
+    /// HMAC with SHA-512 (`hmac-sha512`).
+    HmacSha512,
+}
+
+impl TsigAlgorithm {
+    /// The name of the algorithm, used in the TSIG records.
+    #[inline]
+    pub fn name(self) -> Name<'static> {
+        let name = match self {
+            Self::HmacSha256 => "hmac-sha256",
+            Self::HmacSha512 => "hmac-sha512",
+        };
+        Name::try_from(name).expect("Algorithm names are valid")
+    }
+
+    /// Find the algorithm with the given name.
+    #[inline]
+    pub fn from_name(name: &Name<'_>) -> Option<Self> {
+        [Self::HmacSha256, Self::HmacSha512]
+            .into_iter()
+            .find(|algorithm| algorithm.name() == *name)
+    }
+
+    #[inline]
+    fn hmac(self) -> hmac::Algorithm {
+        match self {
+            Self::HmacSha256 => hmac::HMAC_SHA256,
+            Self::HmacSha512 => hmac::HMAC_SHA512,
+        }
+    }
+}
+
+/// A secret shared with other servers or clients to sign and verify messages.
+///
+/// ```
+/// # use dominion_parser::DnsPacket;
+/// # use dominion_parser::tsig::{TsigAlgorithm, TsigKey};
+/// # use std::time::SystemTime;
+/// # const REQ: &'static [u8; 33] = include_bytes!("../assets/dns_request.bin");
+/// let name = "transfer.example.com".try_into().unwrap();
+/// let key = TsigKey::new(name, TsigAlgorithm::HmacSha256, b"a shared secret");
+///
+/// let mut request = DnsPacket::try_from(&REQ[..]).unwrap();
+/// let mac = key.sign(&mut request, None, SystemTime::now());
+///
+/// let message = Vec::<u8>::from(&request);
+/// assert_eq!(key.verify(&message, None, SystemTime::now()).unwrap(), mac);
+/// ```
+pub struct TsigKey<'a> {
+    name: Name<'a>,
+    algorithm: TsigAlgorithm,
+    key: hmac::Key,
+}
+
+impl fmt::Debug for TsigKey<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TsigKey")
+            .field("name", &self.name)
+            .field("algorithm", &self.algorithm)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a> TsigKey<'a> {
+    /// Create a key with the given name, algorithm and secret.
+    #[inline]
+    pub fn new(name: Name<'a>, algorithm: TsigAlgorithm, secret: &[u8]) -> Self {
+        TsigKey {
+            name,
+            algorithm,
+            key: hmac::Key::new(algorithm.hmac(), secret),
+        }
+    }
+
+    /// The name of the key.
+    #[inline]
+    pub fn name(&self) -> &Name<'a> {
+        &self.name
+    }
+
+    /// The algorithm of the key.
+    #[inline]
+    pub fn algorithm(&self) -> TsigAlgorithm {
+        self.algorithm
+    }
+
+    /// Sign a message, replacing its TSIG record if it had one, and return its MAC. A response
+    /// is signed together with the MAC of its request.
+    ///
+    /// The signature covers the message as serialized with [DnsPacket::serialize], so it can not
+    /// be modified after signing.
+    pub fn sign<'b>(
+        &self,
+        packet: &mut DnsPacket<'b>,
+        request_mac: Option<&[u8]>,
+        now: SystemTime,
+    ) -> Vec<u8>
+    where
+        'a: 'b,
+    {
+        let tsig = self.tsig(packet.header.id, now);
+        self.sign_with(packet, request_mac, &[], tsig, true)
+    }
+
+    /// Verify a signed message and return its MAC. A response is verified together with the MAC
+    /// of its request.
+    pub fn verify(
+        &self,
+        message: &[u8],
+        request_mac: Option<&[u8]>,
+        now: SystemTime,
+    ) -> Result<Vec<u8>, TsigError> {
+        let signed = Signed::split(message)?.ok_or(TsigError::Unsigned)?;
+        self.check(&signed, request_mac, &[], true, now)
+    }
+
+    /// Sign or verify the messages of a stream answering the request with MAC `request_mac`.
+    #[inline]
+    pub fn stream<'k>(&'k self, request_mac: &[u8]) -> TsigStream<'k, 'a> {
+        TsigStream {
+            key: self,
+            mac: request_mac.to_vec(),
+            first: true,
+            pending: Vec::new(),
+            unsigned: 0,
+        }
+    }
+
+    fn tsig(&self, original_id: u16, now: SystemTime) -> Tsig<'static> {
+        Tsig {
+            algorithm: self.algorithm.name(),
+            time_signed: timestamp(now),
+            fudge: DEFAULT_FUDGE,
+            mac: Cow::Borrowed(&[]),
+            original_id,
+            error: ExtendedResponseCode::NoError,
+            other: Cow::Borrowed(&[]),
+        }
+    }
+
+    fn sign_with<'b>(
+        &self,
+        packet: &mut DnsPacket<'b>,
+        prior_mac: Option<&[u8]>,
+        pending: &[u8],
+        mut tsig: Tsig<'b>,
+        full: bool,
+    ) -> Vec<u8>
+    where
+        'a: 'b,
+    {
+        packet
+            .additional
+            .retain(|rr| rr.preamble.rrtype != Type::Tsig);
+        let mut message = Vec::with_capacity(INIT_PACKET_SIZE);
+        packet.serialize(&mut message);
+        let data = self.digest(prior_mac, &[pending, &message], &tsig, full);
+        let mac = hmac::sign(&self.key, &data).as_ref().to_vec();
+        tsig.mac = Cow::Owned(mac.clone());
+        packet.additional.push(record(self.name.clone(), tsig));
+        mac
+    }
+
+    fn check(
+        &self,
+        signed: &Signed<'_>,
+        prior_mac: Option<&[u8]>,
+        pending: &[u8],
+        full: bool,
+        now: SystemTime,
+    ) -> Result<Vec<u8>, TsigError> {
+        let tsig = &signed.tsig;
+        if signed.owner != self.name
+            || TsigAlgorithm::from_name(&tsig.algorithm) != Some(self.algorithm)
+        {
+            Err(TsigError::BadKey)?
+        }
+        if tsig.mac.is_empty() && tsig.error != ExtendedResponseCode::NoError {
+            Err(TsigError::Rejected(tsig.error))?
+        }
+        // RFC 8945 section 5.2.2.1: the MAC can be truncated to half of the output, but not
+        // below 10 bytes. Truncated MACs are not accepted.
+        let output_len = self.algorithm.hmac().digest_algorithm().output_len();
+        let mac_len = tsig.mac.len();
+        if mac_len > output_len || mac_len < (output_len / 2).max(10) {
+            Err(TsigError::MacSize(mac_len))?
+        }
+        if mac_len < output_len {
+            Err(TsigError::BadTrunc)?
+        }
+        let data = self.digest(prior_mac, &[pending, &signed.message], tsig, full);
+        hmac::verify(&self.key, &data, &tsig.mac).map_err(|_| TsigError::BadSig)?;
+        if timestamp(now).abs_diff(tsig.time_signed) > tsig.fudge as u64 {
+            Err(TsigError::BadTime)?
+        }
+        if tsig.error != ExtendedResponseCode::NoError {
+            Err(TsigError::Rejected(tsig.error))?
+        }
+        Ok(tsig.mac.to_vec())
+    }
+
+    /// The data covered by the MAC (RFC 8945 section 4.3). Only the timers of the TSIG record are
+    /// included in the messages of a stream after the first one.
+    fn digest(
+        &self,
+        prior_mac: Option<&[u8]>,
+        messages: &[&[u8]],
+        tsig: &Tsig<'_>,
+        full: bool,
+    ) -> Vec<u8> {
+        let mut data = Vec::with_capacity(INIT_PACKET_SIZE);
+        if let Some(mac) = prior_mac {
+            push_u16(&mut data, mac.len() as u16);
+            data.extend(mac);
+        }
+        for message in messages {
+            data.extend(*message);
+        }
+        if full {
+            self.name.serialize_canonical(&mut data);
+            push_u16(&mut data, Class::Any.into());
+            push_u32(&mut data, 0);
+            tsig.algorithm.serialize_canonical(&mut data);
+        }
+        push_u16(&mut data, (tsig.time_signed >> 32) as u16);
+        push_u32(&mut data, tsig.time_signed as u32);
+        push_u16(&mut data, tsig.fudge);
+        if full {
+            push_u16(&mut data, tsig.error.into());
+            push_u16(&mut data, tsig.other.len() as u16);
+            data.extend(tsig.other.iter());
+        }
+        data
+    }
+}
+
+/// The keys a server accepts, found by their name.
+///
+/// ```
+/// # use dominion_parser::DnsPacket;
+/// # use dominion_parser::tsig::{KeyStore, TsigAlgorithm, TsigKey};
+/// # use std::time::SystemTime;
+/// # const REQ: &'static [u8; 33] = include_bytes!("../assets/dns_request.bin");
+/// let mut keys = KeyStore::new();
+/// let name = "transfer.example.com".try_into().unwrap();
+/// keys.insert(TsigKey::new(name, TsigAlgorithm::HmacSha256, b"a shared secret"));
+///
+/// let message = Vec::<u8>::from(&DnsPacket::try_from(&REQ[..]).unwrap());
+/// assert!(keys.verify(&message, SystemTime::now()).unwrap().is_none());
+/// ```
+#[derive(Debug, Default)]
+pub struct KeyStore<'a> {
+    keys: Vec<TsigKey<'a>>,
+}
+
+impl<'a> KeyStore<'a> {
+    /// Create an empty store.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a key to the store, returning the key with the same name if there was one.
+    pub fn insert(&mut self, key: TsigKey<'a>) -> Option<TsigKey<'a>> {
+        match self.keys.iter_mut().find(|k| k.name == key.name) {
+            Some(old) => Some(core::mem::replace(old, key)),
+            None => {
+                self.keys.push(key);
+                None
+            }
+        }
+    }
+
+    /// Remove the key with the given name.
+    pub fn remove(&mut self, name: &Name<'_>) -> Option<TsigKey<'a>> {
+        let i = self.keys.iter().position(|k| k.name == *name)?;
+        Some(self.keys.remove(i))
+    }
+
+    /// Obtain the key with the given name.
+    #[inline]
+    pub fn get(&self, name: &Name<'_>) -> Option<&TsigKey<'a>> {
+        self.keys.iter().find(|k| k.name == *name)
+    }
+
+    /// The number of keys in the store.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Returns `true` if there are no keys in the store.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Verify a request with the key it names. For a signed request the key and the MAC of the
+    /// request are returned, which are used to sign the response. Unsigned requests return
+    /// `None`.
+    pub fn verify(
+        &self,
+        message: &[u8],
+        now: SystemTime,
+    ) -> Result<Option<(&TsigKey<'a>, Vec<u8>)>, TsigError> {
+        let Some(signed) = Signed::split(message)? else {
+            return Ok(None);
+        };
+        let key = self.get(&signed.owner).ok_or(TsigError::BadKey)?;
+        let mac = key.check(&signed, None, &[], true, now)?;
+        Ok(Some((key, mac)))
+    }
+
+    /// Create the response to a request that failed verification with `error` (RFC 8945
+    /// section 5.3.2).
+    ///
+    /// The response has the RCODE of [TsigError::rcode] and, if the error has a TSIG error, a
+    /// TSIG record with it. Only BADTIME responses are signed, the rest have an empty MAC.
+    pub fn error_response<'b>(
+        &self,
+        request: &DnsPacket<'b>,
+        error: &TsigError,
+        now: SystemTime,
+    ) -> DnsPacket<'b>
+    where
+        'a: 'b,
+    {
+        let mut flags = Flags::from(0);
+        flags.qr = QueryResponse::Response;
+        flags.opcode = request.header.flags.opcode;
+        flags.rd = request.header.flags.rd;
+        let mut response = DnsPacket {
+            header: DnsHeader {
+                id: request.header.id,
+                flags,
+                questions: 0,
+                answers: 0,
+                authority: 0,
+                additional: 0,
+            },
+            questions: request.questions.clone(),
+            answers: Vec::new(),
+            authority: Vec::new(),
+            additional: Vec::new(),
+        };
+        response.set_rcode(error.rcode());
+        let (Some(code), Some(rr)) = (error.tsig_error(), request.additional.last()) else {
+            return response;
+        };
+        let RecordData::Tsig(tsig) = &rr.data else {
+            return response;
+        };
+        let mut answer = Tsig {
+            algorithm: tsig.algorithm.clone(),
+            time_signed: tsig.time_signed,
+            fudge: tsig.fudge,
+            mac: Cow::Borrowed(&[]),
+            original_id: request.header.id,
+            error: code,
+            other: Cow::Borrowed(&[]),
+        };
+        match self.get(&rr.preamble.name) {
+            Some(key) if code == ExtendedResponseCode::BadTime => {
+                // The current time of the server, so the client can detect the clock skew.
+                answer.other = Cow::Owned(timestamp(now).to_be_bytes()[2..].to_vec());
+                key.sign_with(&mut response, Some(&tsig.mac), &[], answer, true);
+            }
+            _ => response
+                .additional
+                .push(record(rr.preamble.name.clone(), answer)),
+        }
+        response
+    }
+}
+
+/// Signs or verifies the messages of a stream answering a single request, like a zone transfer
+/// over TCP (RFC 8945 section 5.3.1).
+///
+/// Each message is signed together with the MAC of the previous signed message. Up to 99
+/// consecutive messages can be left unsigned, but the first and the last ones have to be signed.
+#[derive(Debug)]
+pub struct TsigStream<'k, 'a> {
+    key: &'k TsigKey<'a>,
+    mac: Vec<u8>,
+    first: bool,
+    pending: Vec<u8>,
+    unsigned: usize,
+}
+
+impl<'a> TsigStream<'_, 'a> {
+    /// Sign the next message of the stream.
+    pub fn sign<'b>(&mut self, packet: &mut DnsPacket<'b>, now: SystemTime)
+    where
+        'a: 'b,
+    {
+        let tsig = self.key.tsig(packet.header.id, now);
+        self.mac = self
+            .key
+            .sign_with(packet, Some(&self.mac), &self.pending, tsig, self.first);
+        self.first = false;
+        self.pending.clear();
+        self.unsigned = 0;
+    }
+
+    /// Send the next message of the stream without signing it, it is covered by the signature of
+    /// the next signed message.
+    pub fn skip(&mut self, packet: &DnsPacket<'_>) -> Result<(), TsigError> {
+        if self.first || self.unsigned == MAX_UNSIGNED {
+            Err(TsigError::Unsigned)?
+        }
+        packet.serialize(&mut self.pending);
+        self.unsigned += 1;
+        Ok(())
+    }
+
+    /// Verify the next message of the stream.
+    pub fn verify(&mut self, message: &[u8], now: SystemTime) -> Result<(), TsigError> {
+        match Signed::split(message)? {
+            Some(signed) => {
+                self.mac =
+                    self.key
+                        .check(&signed, Some(&self.mac), &self.pending, self.first, now)?;
+                self.first = false;
+                self.pending.clear();
+                self.unsigned = 0;
+            }
+            None if self.first || self.unsigned == MAX_UNSIGNED => Err(TsigError::Unsigned)?,
+            None => {
+                self.pending.extend(message);
+                self.unsigned += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that the last message of the stream was signed.
+    #[inline]
+    pub fn finish(self) -> Result<(), TsigError> {
+        if self.first || self.unsigned > 0 {
+            Err(TsigError::Unsigned)?
+        }
+        Ok(())
+    }
+}
+
+/// A signed message, split in the TSIG record and the message as it was before signing it.
+struct Signed<'m> {
+    message: Vec<u8>,
+    owner: Name<'m>,
+    tsig: Tsig<'m>,
+}
+
+impl<'m> Signed<'m> {
+    fn split(message: &'m [u8]) -> Result<Option<Self>, TsigError> {
+        let header = DnsHeader::try_from(message)?;
+        let mut pos = 12;
+        for _ in 0..header.questions {
+            let (_, size) = Question::parse(message, pos)?;
+            pos += size;
+        }
+        let records =
+            header.answers as usize + header.authority as usize + header.additional as usize;
+        let mut signed = None;
+        for i in 0..records {
+            let (rr, size) = ResourceRecord::parse(message, pos)?;
+            if let RecordData::Tsig(tsig) = rr.data {
+                if i + 1 != records || header.additional == 0 {
+                    Err(TsigError::NotLast)?
+                }
+                // The MAC covers the message with its original ID and without the TSIG record.
+                let mut unsigned = message[..pos].to_vec();
+                unsigned[..2].copy_from_slice(&tsig.original_id.to_be_bytes());
+                unsigned[10..12].copy_from_slice(&(header.additional - 1).to_be_bytes());
+                signed = Some(Signed {
+                    message: unsigned,
+                    owner: rr.preamble.name,
+                    tsig,
+                });
+            }
+            pos += size;
+        }
+        Ok(signed)
+    }
+}
+
+fn record<'b>(name: Name<'b>, tsig: Tsig<'b>) -> ResourceRecord<'b> {
+    ResourceRecord {
+        preamble: RecordPreamble {
+            name,
+            rrtype: Type::Tsig,
+            class: Class::Any,
+            ttl: 0,
+        },
+        data: RecordData::Tsig(tsig),
+    }
+}
+
+/// Seconds since the epoch in the 48 bit format of the TSIG records.
+#[inline]
+fn timestamp(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()) & 0xFFFF_FFFF_FFFF
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::header::ResponseCode;
+    use std::time::Duration;
+
+    const REQ: &[u8; 33] = include_bytes!("../assets/dns_request.bin");
+    const RES: &[u8; 49] = include_bytes!("../assets/dns_response.bin");
+
+    fn now() -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(1_800_000_000)
+    }
+
+    fn key(algorithm: TsigAlgorithm, secret: &[u8]) -> TsigKey<'static> {
+        TsigKey::new(
+            "transfer.example.com".try_into().unwrap(),
+            algorithm,
+            secret,
+        )
+    }
+
+    fn signed_request(key: &TsigKey<'_>) -> (Vec<u8>, Vec<u8>) {
+        let mut request = DnsPacket::try_from(&REQ[..]).unwrap();
+        let mac = key.sign(&mut request, None, now());
+        (Vec::from(&request), mac)
+    }
+
+    fn tsig_of(message: &[u8]) -> Tsig<'_> {
+        let packet = DnsPacket::try_from(message).unwrap();
+        match packet.additional.last().map(|rr| &rr.data) {
+            Some(RecordData::Tsig(tsig)) => tsig.clone(),
+            _ => panic!("Expected TSIG record"),
+        }
+    }
+
+    #[test]
+    fn sign_and_verify() {
+        for algorithm in [TsigAlgorithm::HmacSha256, TsigAlgorithm::HmacSha512] {
+            let mut keys = KeyStore::new();
+            keys.insert(key(algorithm, b"secret"));
+            let (request, request_mac) = signed_request(
+                keys.get(&"transfer.example.com".try_into().unwrap())
+                    .unwrap(),
+            );
+
+            let tsig = tsig_of(&request);
+            assert_eq!(TsigAlgorithm::from_name(&tsig.algorithm), Some(algorithm));
+            assert_eq!(tsig.fudge, DEFAULT_FUDGE);
+
+            let (key, mac) = keys.verify(&request, now()).unwrap().unwrap();
+            assert_eq!(mac, request_mac);
+
+            let mut response = DnsPacket::try_from(&RES[..]).unwrap();
+            let response_mac = key.sign(&mut response, Some(&mac), now());
+            let response = Vec::from(&response);
+            assert_eq!(
+                key.verify(&response, Some(&mac), now()).unwrap(),
+                response_mac
+            );
+            let err = key.verify(&response, None, now()).unwrap_err();
+            assert!(matches!(err, TsigError::BadSig));
+        }
+    }
+
+    #[test]
+    fn verify_errors() {
+        let key = key(TsigAlgorithm::HmacSha256, b"secret");
+        let (request, _) = signed_request(&key);
+
+        let mut tampered = request.clone();
+        tampered[2] ^= 0x01;
+        let err = key.verify(&tampered, None, now()).unwrap_err();
+        assert!(matches!(err, TsigError::BadSig));
+
+        let other = TsigKey::new(
+            "other.example.com".try_into().unwrap(),
+            TsigAlgorithm::HmacSha256,
+            b"secret",
+        );
+        let err = other.verify(&request, None, now()).unwrap_err();
+        assert!(matches!(err, TsigError::BadKey));
+
+        let sha512 = self::key(TsigAlgorithm::HmacSha512, b"secret");
+        let err = sha512.verify(&request, None, now()).unwrap_err();
+        assert!(matches!(err, TsigError::BadKey));
+
+        let wrong_secret = self::key(TsigAlgorithm::HmacSha256, b"wrong");
+        let err = wrong_secret.verify(&request, None, now()).unwrap_err();
+        assert!(matches!(err, TsigError::BadSig));
+
+        let late = now() + Duration::from_secs(DEFAULT_FUDGE as u64 + 1);
+        let err = key.verify(&request, None, late).unwrap_err();
+        assert!(matches!(err, TsigError::BadTime));
+
+        let unsigned = Vec::from(&DnsPacket::try_from(&REQ[..]).unwrap());
+        let err = key.verify(&unsigned, None, now()).unwrap_err();
+        assert!(matches!(err, TsigError::Unsigned));
+    }
+
+    #[test]
+    fn verify_mac_size() {
+        let key = key(TsigAlgorithm::HmacSha256, b"secret");
+        let mut request = DnsPacket::try_from(&REQ[..]).unwrap();
+        key.sign(&mut request, None, now());
+        let Some(RecordData::Tsig(tsig)) = request.additional.last_mut().map(|rr| &mut rr.data)
+        else {
+            panic!("Expected TSIG record")
+        };
+        tsig.mac = Cow::Owned(tsig.mac[..16].to_vec());
+        let err = key.verify(&Vec::from(&request), None, now()).unwrap_err();
+        assert!(matches!(err, TsigError::BadTrunc));
+
+        let Some(RecordData::Tsig(tsig)) = request.additional.last_mut().map(|rr| &mut rr.data)
+        else {
+            panic!("Expected TSIG record")
+        };
+        tsig.mac = Cow::Owned(tsig.mac[..8].to_vec());
+        let err = key.verify(&Vec::from(&request), None, now()).unwrap_err();
+        assert!(matches!(err, TsigError::MacSize(8)));
+    }
+
+    #[test]
+    fn tsig_not_last() {
+        let key = key(TsigAlgorithm::HmacSha256, b"secret");
+        let mut request = DnsPacket::try_from(&REQ[..]).unwrap();
+        key.sign(&mut request, None, now());
+        let tsig = request.additional.pop().unwrap();
+        request.answers.push(tsig);
+        let err = key.verify(&Vec::from(&request), None, now()).unwrap_err();
+        assert!(matches!(err, TsigError::NotLast));
+    }
+
+    #[test]
+    fn resign_replaces_tsig() {
+        let key = key(TsigAlgorithm::HmacSha256, b"secret");
+        let mut request = DnsPacket::try_from(&REQ[..]).unwrap();
+        key.sign(&mut request, None, now());
+        let mac = key.sign(&mut request, None, now());
+        assert_eq!(request.additional.len(), 1);
+        assert_eq!(key.verify(&Vec::from(&request), None, now()).unwrap(), mac);
+    }
+
+    #[test]
+    fn error_responses() {
+        let mut keys = KeyStore::new();
+        keys.insert(key(TsigAlgorithm::HmacSha256, b"secret"));
+        let key = keys
+            .get(&"transfer.example.com".try_into().unwrap())
+            .unwrap();
+        let (message, mac) = signed_request(key);
+        let request = DnsPacket::try_from(&message[..]).unwrap();
+
+        let err = keys
+            .verify(&message, now() + Duration::from_secs(3600))
+            .unwrap_err();
+        let response = keys.error_response(&request, &err, now());
+        assert_eq!(response.header.flags.qr, QueryResponse::Response);
+        assert_eq!(response.header.flags.rcode, ResponseCode::NotAuth);
+        let response = Vec::from(&response);
+        let err = key.verify(&response, Some(&mac), now()).unwrap_err();
+        assert!(matches!(
+            err,
+            TsigError::Rejected(ExtendedResponseCode::BadTime)
+        ));
+        assert_eq!(tsig_of(&response).other.len(), 6);
+
+        let wrong_secret = self::key(TsigAlgorithm::HmacSha256, b"wrong");
+        let (message, mac) = signed_request(&wrong_secret);
+        let request = DnsPacket::try_from(&message[..]).unwrap();
+        let err = keys.verify(&message, now()).unwrap_err();
+        let response = Vec::from(&keys.error_response(&request, &err, now()));
+        let tsig = tsig_of(&response);
+        assert_eq!(tsig.error, ExtendedResponseCode::BAD_SIG);
+        assert!(tsig.mac.is_empty());
+        let err = wrong_secret
+            .verify(&response, Some(&mac), now())
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            TsigError::Rejected(ExtendedResponseCode::BAD_SIG)
+        ));
+
+        let unsigned = DnsPacket::try_from(&REQ[..]).unwrap();
+        let response = keys.error_response(&unsigned, &TsigError::Unsigned, now());
+        assert_eq!(response.header.flags.rcode, ResponseCode::Refused);
+        assert!(response.additional.is_empty());
+    }
+
+    #[test]
+    fn stream() {
+        let key = key(TsigAlgorithm::HmacSha256, b"secret");
+        let (_, request_mac) = signed_request(&key);
+
+        let mut signer = key.stream(&request_mac);
+        let mut messages = Vec::new();
+        for i in 0..4 {
+            let mut packet = DnsPacket::try_from(&RES[..]).unwrap();
+            packet.header.id = i;
+            if i == 1 || i == 2 {
+                signer.skip(&packet).unwrap();
+            } else {
+                signer.sign(&mut packet, now());
+            }
+            messages.push(Vec::from(&packet));
+        }
+        signer.finish().unwrap();
+
+        let mut verifier = key.stream(&request_mac);
+        for message in &messages {
+            verifier.verify(message, now()).unwrap();
+        }
+        verifier.finish().unwrap();
+
+        // An unsigned message can not be the last one.
+        let mut verifier = key.stream(&request_mac);
+        for message in &messages[..3] {
+            verifier.verify(message, now()).unwrap();
+        }
+        assert!(matches!(verifier.finish(), Err(TsigError::Unsigned)));
+
+        // Messages can not be reordered.
+        let mut verifier = key.stream(&request_mac);
+        verifier.verify(&messages[0], now()).unwrap();
+        let err = verifier.verify(&messages[3], now()).unwrap_err();
+        assert!(matches!(err, TsigError::BadSig));
+
+        // The first message has to be signed.
+        let mut verifier = key.stream(&request_mac);
+        let err = verifier.verify(&messages[1], now()).unwrap_err();
+        assert!(matches!(err, TsigError::Unsigned));
+    }
+}
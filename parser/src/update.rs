@@ -0,0 +1,667 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use crate::DnsPacket;
+use crate::body::name::Name;
+use crate::body::{Class, QType, Question, RecordData, RecordPreamble, ResourceRecord, Type};
+use crate::header::{DnsHeader, ExtendedResponseCode, Flags, OpCode, QueryResponse};
+
+use thiserror::Error;
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// The type ANY (255), which is only a [QType] but appears in the records of UPDATE messages.
+const ANY: Type = Type::Unknown(255);
+
+/// An error was encountered when reading an UPDATE message or evaluating its prerequisites.
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateError {
+    /// The message does not have the UPDATE opcode.
+    #[error("The message is not an UPDATE.")]
+    OpCode,
+    /// The zone section does not have exactly one entry.
+    #[error("The zone section has {0} entries instead of one.")]
+    ZoneCount(usize),
+    /// The entry of the zone section is not of type SOA.
+    #[error("The zone section entry is not of type SOA.")]
+    ZoneType,
+    /// The record at this position of the prerequisite section is not valid.
+    #[error("Invalid prerequisite at position {0}.")]
+    Prerequisite(usize),
+    /// The record at this position of the update section is not valid.
+    #[error("Invalid update at position {0}.")]
+    Update(usize),
+    /// A record of the message is outside of the zone.
+    #[error("A record is outside of the zone.")]
+    NotZone,
+    /// A name that ought not to exist, exists.
+    #[error("A name that ought not to exist, exists.")]
+    YXDomain,
+    /// A name that ought to exist, does not exist.
+    #[error("A name that ought to exist, does not exist.")]
+    NXDomain,
+    /// A RRset that ought not to exist, exists.
+    #[error("A RRset that ought not to exist, exists.")]
+    YXRRSet,
+    /// A RRset that ought to exist (with the given records), does not exist.
+    #[error("A RRset that ought to exist, does not exist.")]
+    NXRRSet,
+}
+
+impl UpdateError {
+    /// The response code of the response to an UPDATE that failed with this error.
+    #[inline]
+    pub fn rcode(&self) -> ExtendedResponseCode {
+        match self {
+            Self::OpCode => ExtendedResponseCode::NotImp,
+            Self::ZoneCount(_) | Self::ZoneType | Self::Prerequisite(_) | Self::Update(_) => {
+                ExtendedResponseCode::FormErr
+            }
+            Self::NotZone => ExtendedResponseCode::NotZone,
+            Self::YXDomain => ExtendedResponseCode::YXDomain,
+            Self::NXDomain => ExtendedResponseCode::NXDomain,
+            Self::YXRRSet => ExtendedResponseCode::YXRRSet,
+            Self::NXRRSet => ExtendedResponseCode::NXRRSet,
+        }
+    }
+}
+
+/// A condition that has to hold in the zone for the update to be applied (RFC 2136 section
+/// 2.4).
+#[derive(Debug, Clone, Copy)]
+pub enum Prerequisite<'p, 'a> {
+    /// At least one record with the name exists.
+    NameInUse(&'p Name<'a>),
+    /// No record with the name exists.
+    NameNotInUse(&'p Name<'a>),
+    /// A RRset with the name and type exists.
+    RrsetExists(&'p Name<'a>, Type),
+    /// No RRset with the name and type exists.
+    RrsetDoesNotExist(&'p Name<'a>, Type),
+    /// The RRset with the name and type of the record exists, and it has exactly the records of
+    /// the prerequisite section with that name and type.
+    RrsetEquals(&'p ResourceRecord<'a>),
+}
+
+/// A change to the zone (RFC 2136 section 2.5).
+#[derive(Debug, Clone, Copy)]
+pub enum Update<'p, 'a> {
+    /// Add the record to its RRset.
+    Add(&'p ResourceRecord<'a>),
+    /// Delete every record with the name and type.
+    DeleteRrset(&'p Name<'a>, Type),
+    /// Delete every record with the name.
+    DeleteName(&'p Name<'a>),
+    /// Delete the record from its RRset.
+    Delete(&'p ResourceRecord<'a>),
+}
+
+/// A view of a [DnsPacket] with the UPDATE opcode (RFC 2136).
+///
+/// The sections of UPDATE messages have a different meaning than in queries:
+///
+/// ```text
+/// +---------------------+
+/// |        Header       |
+/// +---------------------+
+/// |         Zone        | specifies the zone to be updated (questions)
+/// +---------------------+
+/// |     Prerequisite    | RRs or RRsets which must (not) preexist (answers)
+/// +---------------------+
+/// |        Update       | RRs or RRsets to be added or deleted (authority)
+/// +---------------------+
+/// |   Additional Data   | additional data (additional)
+/// +---------------------+
+/// ```
+///
+/// The sections are validated when the view is created, so every record of them is a valid
+/// [Prerequisite] or [Update] inside the zone.
+///
+/// ```
+/// # use dominion_parser::body::{RecordData, Type};
+/// # use dominion_parser::update::{Update, UpdateBuilder, UpdateMessage};
+/// let zone = "example.com".try_into().unwrap();
+/// let name = "www.example.com".try_into().unwrap();
+/// let packet = UpdateBuilder::new(0x1234, zone)
+///     .name_in_use("example.com".try_into().unwrap())
+///     .delete_rrset(name, Type::A)
+///     .build();
+///
+/// let update = UpdateMessage::try_from(&packet).unwrap();
+/// assert_eq!(update.zone().name.to_string(), "example.com.");
+/// assert_eq!(update.prerequisites().count(), 1);
+/// assert!(matches!(update.updates().next(), Some(Update::DeleteRrset(_, Type::A))));
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct UpdateMessage<'p, 'a> {
+    packet: &'p DnsPacket<'a>,
+}
+
+impl<'p, 'a> TryFrom<&'p DnsPacket<'a>> for UpdateMessage<'p, 'a> {
+    type Error = UpdateError;
+
+    fn try_from(packet: &'p DnsPacket<'a>) -> Result<Self, Self::Error> {
+        if packet.header.flags.opcode != OpCode::Update {
+            Err(UpdateError::OpCode)?
+        }
+        if packet.questions.len() != 1 {
+            Err(UpdateError::ZoneCount(packet.questions.len()))?
+        }
+        let zone = &packet.questions[0];
+        if zone.qtype != QType::Soa {
+            Err(UpdateError::ZoneType)?
+        }
+        for (i, rr) in packet.answers.iter().enumerate() {
+            if !in_zone(&zone.name, &rr.preamble.name) {
+                Err(UpdateError::NotZone)?
+            }
+            if rr.preamble.ttl != 0 || prerequisite(rr, zone.class).is_none() {
+                Err(UpdateError::Prerequisite(i))?
+            }
+        }
+        for (i, rr) in packet.authority.iter().enumerate() {
+            if !in_zone(&zone.name, &rr.preamble.name) {
+                Err(UpdateError::NotZone)?
+            }
+            if update(rr, zone.class).is_none() {
+                Err(UpdateError::Update(i))?
+            }
+        }
+        Ok(UpdateMessage { packet })
+    }
+}
+
+impl<'p, 'a> UpdateMessage<'p, 'a> {
+    /// The underlying [DnsPacket].
+    #[inline]
+    pub fn packet(&self) -> &'p DnsPacket<'a> {
+        self.packet
+    }
+
+    /// The zone to be updated, in a [Question] of type SOA.
+    #[inline]
+    pub fn zone(&self) -> &'p Question<'a> {
+        &self.packet.questions[0]
+    }
+
+    /// The prerequisites of the update.
+    #[inline]
+    pub fn prerequisites(&self) -> impl Iterator<Item = Prerequisite<'p, 'a>> + use<'p, 'a> {
+        let class = self.zone().class;
+        self.packet
+            .answers
+            .iter()
+            .filter_map(move |rr| prerequisite(rr, class))
+    }
+
+    /// The changes to the zone.
+    #[inline]
+    pub fn updates(&self) -> impl Iterator<Item = Update<'p, 'a>> + use<'p, 'a> {
+        let class = self.zone().class;
+        self.packet
+            .authority
+            .iter()
+            .filter_map(move |rr| update(rr, class))
+    }
+
+    /// Records related to the update, or to the records being updated.
+    #[inline]
+    pub fn additional(&self) -> &'p [ResourceRecord<'a>] {
+        &self.packet.additional
+    }
+
+    /// Evaluate the prerequisites against the records of the zone (RFC 2136 section 3.2).
+    ///
+    /// Names are compared ignoring case and the data of the records is compared in its
+    /// canonical form.
+    ///
+    /// ```
+    /// # use dominion_parser::body::{Class, RecordData, RecordPreamble, ResourceRecord, Type};
+    /// # use dominion_parser::update::{UpdateBuilder, UpdateError, UpdateMessage};
+    /// let preamble = RecordPreamble {
+    ///     name: "www.example.com".try_into().unwrap(),
+    ///     rrtype: Type::A,
+    ///     class: Class::IN,
+    ///     ttl: 300,
+    /// };
+    /// let data = RecordData::A("192.0.2.1".parse().unwrap());
+    /// let zone = vec![ResourceRecord { preamble, data }];
+    ///
+    /// let packet = UpdateBuilder::new(0x1234, "example.com".try_into().unwrap())
+    ///     .rrset_does_not_exist("www.example.com".try_into().unwrap(), Type::A)
+    ///     .build();
+    /// let update = UpdateMessage::try_from(&packet).unwrap();
+    /// assert_eq!(update.check_prerequisites(&zone), Err(UpdateError::YXRRSet));
+    /// ```
+    pub fn check_prerequisites(&self, zone: &[ResourceRecord<'_>]) -> Result<(), UpdateError> {
+        for prerequisite in self.prerequisites() {
+            match prerequisite {
+                Prerequisite::NameInUse(name) => {
+                    if !zone.iter().any(|rr| rr.preamble.name == *name) {
+                        Err(UpdateError::NXDomain)?
+                    }
+                }
+                Prerequisite::NameNotInUse(name) => {
+                    if zone.iter().any(|rr| rr.preamble.name == *name) {
+                        Err(UpdateError::YXDomain)?
+                    }
+                }
+                Prerequisite::RrsetExists(name, rrtype) => {
+                    if rrset(zone, name, rrtype).is_empty() {
+                        Err(UpdateError::NXRRSet)?
+                    }
+                }
+                Prerequisite::RrsetDoesNotExist(name, rrtype) => {
+                    if !rrset(zone, name, rrtype).is_empty() {
+                        Err(UpdateError::YXRRSet)?
+                    }
+                }
+                Prerequisite::RrsetEquals(rr) => {
+                    let (name, rrtype) = (&rr.preamble.name, rr.preamble.rrtype);
+                    if rrset(&self.packet.answers, name, rrtype) != rrset(zone, name, rrtype) {
+                        Err(UpdateError::NXRRSet)?
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Create the response to the update with the given response code (RFC 2136 section 3.8),
+    /// which has the zone section of the request and no records.
+    pub fn response(&self, rcode: ExtendedResponseCode) -> DnsPacket<'a> {
+        let mut flags = Flags::from(0);
+        flags.qr = QueryResponse::Response;
+        flags.opcode = OpCode::Update;
+        let mut response = DnsPacket {
+            header: DnsHeader {
+                id: self.packet.header.id,
+                flags,
+                questions: 0,
+                answers: 0,
+                authority: 0,
+                additional: 0,
+            },
+            questions: self.packet.questions.clone(),
+            answers: Vec::new(),
+            authority: Vec::new(),
+            additional: Vec::new(),
+        };
+        response.set_rcode(rcode);
+        response
+    }
+}
+
+/// Builds UPDATE messages for clients.
+///
+/// The prerequisites and updates are added to the message in the order of the calls, the
+/// records are given the class and TTL required by RFC 2136 for each kind of entry.
+#[derive(Debug, Clone)]
+pub struct UpdateBuilder<'a> {
+    packet: DnsPacket<'a>,
+}
+
+impl<'a> UpdateBuilder<'a> {
+    /// Start an UPDATE message with the given ID for the zone `zone` of class IN.
+    pub fn new(id: u16, zone: Name<'a>) -> Self {
+        let mut flags = Flags::from(0);
+        flags.opcode = OpCode::Update;
+        let packet = DnsPacket {
+            header: DnsHeader {
+                id,
+                flags,
+                questions: 0,
+                answers: 0,
+                authority: 0,
+                additional: 0,
+            },
+            questions: vec![Question {
+                name: zone,
+                qtype: QType::Soa,
+                class: Class::IN,
+            }],
+            answers: Vec::new(),
+            authority: Vec::new(),
+            additional: Vec::new(),
+        };
+        UpdateBuilder { packet }
+    }
+
+    /// Require that at least one record with the name exists.
+    pub fn name_in_use(mut self, name: Name<'a>) -> Self {
+        self.packet.answers.push(empty(name, ANY, Class::Any));
+        self
+    }
+
+    /// Require that no record with the name exists.
+    pub fn name_not_in_use(mut self, name: Name<'a>) -> Self {
+        self.packet.answers.push(empty(name, ANY, Class::None));
+        self
+    }
+
+    /// Require that a RRset with the name and type exists.
+    pub fn rrset_exists(mut self, name: Name<'a>, rrtype: Type) -> Self {
+        self.packet.answers.push(empty(name, rrtype, Class::Any));
+        self
+    }
+
+    /// Require that no RRset with the name and type exists.
+    pub fn rrset_does_not_exist(mut self, name: Name<'a>, rrtype: Type) -> Self {
+        self.packet.answers.push(empty(name, rrtype, Class::None));
+        self
+    }
+
+    /// Require that the RRset of the record exists and has exactly the records given with this
+    /// method for its name and type.
+    pub fn rrset_equals(mut self, mut record: ResourceRecord<'a>) -> Self {
+        record.preamble.class = self.packet.questions[0].class;
+        record.preamble.ttl = 0;
+        self.packet.answers.push(record);
+        self
+    }
+
+    /// Add the record to its RRset.
+    pub fn add_record(mut self, mut record: ResourceRecord<'a>) -> Self {
+        record.preamble.class = self.packet.questions[0].class;
+        self.packet.authority.push(record);
+        self
+    }
+
+    /// Delete every record with the name and type.
+    pub fn delete_rrset(mut self, name: Name<'a>, rrtype: Type) -> Self {
+        self.packet.authority.push(empty(name, rrtype, Class::Any));
+        self
+    }
+
+    /// Delete every record with the name.
+    pub fn delete_name(mut self, name: Name<'a>) -> Self {
+        self.packet.authority.push(empty(name, ANY, Class::Any));
+        self
+    }
+
+    /// Delete the record from its RRset.
+    pub fn delete_record(mut self, mut record: ResourceRecord<'a>) -> Self {
+        record.preamble.class = Class::None;
+        record.preamble.ttl = 0;
+        self.packet.authority.push(record);
+        self
+    }
+
+    /// Add a record to the additional data section.
+    pub fn additional(mut self, record: ResourceRecord<'a>) -> Self {
+        self.packet.additional.push(record);
+        self
+    }
+
+    /// Finish the message.
+    #[inline]
+    pub fn build(self) -> DnsPacket<'a> {
+        self.packet
+    }
+}
+
+/// Classify a record of the prerequisite section.
+fn prerequisite<'p, 'a>(rr: &'p ResourceRecord<'a>, zone: Class) -> Option<Prerequisite<'p, 'a>> {
+    let RecordPreamble {
+        name,
+        rrtype,
+        class,
+        ..
+    } = &rr.preamble;
+    let empty = matches!(rr.data, RecordData::Empty);
+    match (class, *rrtype == ANY) {
+        (Class::Any, true) if empty => Some(Prerequisite::NameInUse(name)),
+        (Class::Any, false) if empty => Some(Prerequisite::RrsetExists(name, *rrtype)),
+        (Class::None, true) if empty => Some(Prerequisite::NameNotInUse(name)),
+        (Class::None, false) if empty => Some(Prerequisite::RrsetDoesNotExist(name, *rrtype)),
+        (class, false) if *class == zone && !empty => Some(Prerequisite::RrsetEquals(rr)),
+        _ => None,
+    }
+}
+
+/// Classify a record of the update section.
+fn update<'p, 'a>(rr: &'p ResourceRecord<'a>, zone: Class) -> Option<Update<'p, 'a>> {
+    let RecordPreamble {
+        name,
+        rrtype,
+        class,
+        ttl,
+    } = &rr.preamble;
+    let empty = matches!(rr.data, RecordData::Empty);
+    let meta = matches!(rrtype, Type::Opt | Type::Tsig) || *rrtype == ANY;
+    match class {
+        Class::Any if *ttl == 0 && empty && *rrtype == ANY => Some(Update::DeleteName(name)),
+        Class::Any if *ttl == 0 && empty && !meta => Some(Update::DeleteRrset(name, *rrtype)),
+        Class::None if *ttl == 0 && !empty && !meta => Some(Update::Delete(rr)),
+        class if *class == zone && !empty && !meta => Some(Update::Add(rr)),
+        _ => None,
+    }
+}
+
+/// The data of the RRset with the given name and type in canonical form, sorted.
+fn rrset(records: &[ResourceRecord<'_>], name: &Name<'_>, rrtype: Type) -> Vec<Vec<u8>> {
+    let mut rrset: Vec<Vec<u8>> = records
+        .iter()
+        .filter(|rr| rr.preamble.rrtype == rrtype && rr.preamble.name == *name)
+        .filter(|rr| !matches!(rr.data, RecordData::Empty))
+        .map(|rr| {
+            let mut data = Vec::new();
+            rr.data.serialize_canonical(&mut data);
+            data
+        })
+        .collect();
+    rrset.sort();
+    rrset.dedup();
+    rrset
+}
+
+#[inline]
+fn in_zone(zone: &Name<'_>, name: &Name<'_>) -> bool {
+    zone == name || zone.is_subdomain(name)
+}
+
+/// A record without data for the prerequisites and updates that only refer to a name or type.
+#[inline]
+fn empty<'a>(name: Name<'a>, rrtype: Type, class: Class) -> ResourceRecord<'a> {
+    ResourceRecord {
+        preamble: RecordPreamble {
+            name,
+            rrtype,
+            class,
+            ttl: 0,
+        },
+        data: RecordData::Empty,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::header::ResponseCode;
+    use alloc::string::ToString;
+
+    fn a<'a>(name: &'a str, ip: &str) -> ResourceRecord<'a> {
+        ResourceRecord {
+            preamble: RecordPreamble {
+                name: name.try_into().unwrap(),
+                rrtype: Type::A,
+                class: Class::IN,
+                ttl: 300,
+            },
+            data: RecordData::A(ip.parse().unwrap()),
+        }
+    }
+
+    fn zone() -> Vec<ResourceRecord<'static>> {
+        vec![
+            a("www.example.com", "192.0.2.1"),
+            a("www.example.com", "192.0.2.2"),
+            a("mail.example.com", "192.0.2.25"),
+        ]
+    }
+
+    fn name(name: &str) -> Name<'_> {
+        name.try_into().unwrap()
+    }
+
+    #[test]
+    fn builder_roundtrip() {
+        let packet = UpdateBuilder::new(0x1234, name("example.com"))
+            .name_in_use(name("www.example.com"))
+            .name_not_in_use(name("new.example.com"))
+            .rrset_exists(name("mail.example.com"), Type::A)
+            .rrset_does_not_exist(name("mail.example.com"), Type::Aaaa)
+            .rrset_equals(a("www.example.com", "192.0.2.1"))
+            .add_record(a("new.example.com", "192.0.2.3"))
+            .delete_rrset(name("mail.example.com"), Type::A)
+            .delete_name(name("old.example.com"))
+            .delete_record(a("www.example.com", "192.0.2.2"))
+            .build();
+        let serialized = Vec::from(&packet);
+        let packet = DnsPacket::try_from(&serialized[..]).unwrap();
+        assert_eq!(packet.header.flags.opcode, OpCode::Update);
+
+        let update = UpdateMessage::try_from(&packet).unwrap();
+        assert_eq!(update.zone().name.to_string(), "example.com.");
+        let prerequisites: Vec<_> = update.prerequisites().collect();
+        assert_eq!(prerequisites.len(), 5);
+        assert!(
+            matches!(prerequisites[0], Prerequisite::NameInUse(n) if n.to_string() == "www.example.com.")
+        );
+        assert!(matches!(prerequisites[1], Prerequisite::NameNotInUse(_)));
+        assert!(matches!(
+            prerequisites[2],
+            Prerequisite::RrsetExists(_, Type::A)
+        ));
+        assert!(matches!(
+            prerequisites[3],
+            Prerequisite::RrsetDoesNotExist(_, Type::Aaaa)
+        ));
+        assert!(matches!(prerequisites[4], Prerequisite::RrsetEquals(rr) if rr.preamble.ttl == 0));
+
+        let updates: Vec<_> = update.updates().collect();
+        assert_eq!(updates.len(), 4);
+        assert!(matches!(updates[0], Update::Add(rr) if rr.preamble.ttl == 300));
+        assert!(matches!(updates[1], Update::DeleteRrset(_, Type::A)));
+        assert!(matches!(updates[2], Update::DeleteName(n) if n.to_string() == "old.example.com."));
+        assert!(matches!(updates[3], Update::Delete(rr) if rr.preamble.class == Class::None));
+    }
+
+    #[test]
+    fn empty_rdata() {
+        let packet = UpdateBuilder::new(1, name("example.com"))
+            .delete_rrset(name("www.example.com"), Type::Mx)
+            .build();
+        let serialized = Vec::from(&packet);
+        // NAME (compressed) TYPE CLASS TTL RDLENGTH
+        assert_eq!(
+            &serialized[serialized.len() - 16..],
+            &[
+                3, b'w', b'w', b'w', 0xC0, 0x0C, 0, 15, 0, 255, 0, 0, 0, 0, 0, 0
+            ][..]
+        );
+        let packet = DnsPacket::try_from(&serialized[..]).unwrap();
+        assert!(matches!(packet.authority[0].data, RecordData::Empty));
+    }
+
+    #[test]
+    fn check_prerequisites() {
+        let zone = zone();
+        let check = |builder: UpdateBuilder<'_>| {
+            let packet = builder.build();
+            UpdateMessage::try_from(&packet)
+                .unwrap()
+                .check_prerequisites(&zone)
+        };
+        let builder = || UpdateBuilder::new(1, name("example.com"));
+
+        assert_eq!(
+            check(builder().name_in_use(name("WWW.example.com"))),
+            Ok(())
+        );
+        assert_eq!(
+            check(builder().name_in_use(name("new.example.com"))),
+            Err(UpdateError::NXDomain)
+        );
+        assert_eq!(
+            check(builder().name_not_in_use(name("mail.example.com"))),
+            Err(UpdateError::YXDomain)
+        );
+        assert_eq!(
+            check(builder().rrset_exists(name("mail.example.com"), Type::Aaaa)),
+            Err(UpdateError::NXRRSet)
+        );
+        assert_eq!(
+            check(builder().rrset_does_not_exist(name("mail.example.com"), Type::Aaaa)),
+            Ok(())
+        );
+
+        let equals = builder()
+            .rrset_equals(a("www.example.com", "192.0.2.2"))
+            .rrset_equals(a("www.example.com", "192.0.2.1"));
+        assert_eq!(check(equals), Ok(()));
+        let subset = builder().rrset_equals(a("www.example.com", "192.0.2.1"));
+        assert_eq!(check(subset), Err(UpdateError::NXRRSet));
+    }
+
+    #[test]
+    fn invalid_messages() {
+        let mut packet = UpdateBuilder::new(1, name("example.com")).build();
+        packet.header.flags.opcode = OpCode::Query;
+        assert_eq!(
+            UpdateMessage::try_from(&packet).unwrap_err(),
+            UpdateError::OpCode
+        );
+
+        let mut packet = UpdateBuilder::new(1, name("example.com")).build();
+        packet.questions[0].qtype = QType::A;
+        assert_eq!(
+            UpdateMessage::try_from(&packet).unwrap_err(),
+            UpdateError::ZoneType
+        );
+
+        let mut packet = UpdateBuilder::new(1, name("example.com")).build();
+        packet.questions.clear();
+        let err = UpdateMessage::try_from(&packet).unwrap_err();
+        assert_eq!(err, UpdateError::ZoneCount(0));
+        assert_eq!(err.rcode(), ExtendedResponseCode::FormErr);
+
+        let packet = UpdateBuilder::new(1, name("example.com"))
+            .add_record(a("www.example.org", "192.0.2.1"))
+            .build();
+        let err = UpdateMessage::try_from(&packet).unwrap_err();
+        assert_eq!(err, UpdateError::NotZone);
+        assert_eq!(err.rcode(), ExtendedResponseCode::NotZone);
+
+        let mut packet = UpdateBuilder::new(1, name("example.com"))
+            .rrset_exists(name("www.example.com"), Type::A)
+            .build();
+        packet.answers[0].preamble.ttl = 300;
+        let err = UpdateMessage::try_from(&packet).unwrap_err();
+        assert_eq!(err, UpdateError::Prerequisite(0));
+
+        let mut packet = UpdateBuilder::new(1, name("example.com"))
+            .delete_rrset(name("www.example.com"), Type::A)
+            .build();
+        packet.authority[0].preamble.class = Class::CH;
+        let err = UpdateMessage::try_from(&packet).unwrap_err();
+        assert_eq!(err, UpdateError::Update(0));
+    }
+
+    #[test]
+    fn update_response() {
+        let packet = UpdateBuilder::new(0x1234, name("example.com"))
+            .add_record(a("new.example.com", "192.0.2.3"))
+            .build();
+        let update = UpdateMessage::try_from(&packet).unwrap();
+        let response = update.response(UpdateError::YXRRSet.rcode());
+        assert_eq!(response.header.id, 0x1234);
+        assert_eq!(response.header.flags.qr, QueryResponse::Response);
+        assert_eq!(response.header.flags.opcode, OpCode::Update);
+        assert_eq!(response.header.flags.rcode, ResponseCode::YXRRSet);
+        assert_eq!(response.questions.len(), 1);
+        assert!(response.authority.is_empty());
+    }
+}
@@ -0,0 +1,627 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use crate::binutils::*;
+use crate::body::dnssec::{Algorithm, DigestType, Dnskey, Ds, Rrsig};
+use crate::body::name::Name;
+use crate::body::{RecordData, ResourceRecord, Type};
+
+use ring::{digest, signature};
+use thiserror::Error;
+
+use alloc::vec::Vec;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The only valid value of the protocol field of a DNSKEY.
+const DNSKEY_PROTOCOL: u8 = 3;
+
+/// An error was encountered when trying to validate DNSSEC data.
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidationError {
+    /// There are no records to validate.
+    #[error("The RRset is empty.")]
+    EmptyRrset,
+    /// Not every record has the same owner, class and type.
+    #[error("The records of the RRset do not have the same owner, class and type.")]
+    MixedRrset,
+    /// The type of the RRset is not the one covered by the signature.
+    #[error("The signature covers type {0:?} which is not the type of the RRset.")]
+    TypeCovered(Type),
+    /// The signature has more labels than the owner name of the RRset.
+    #[error("The signature has more labels ({0}) than the owner name of the RRset.")]
+    LabelCount(u8),
+    /// The signer's name is not the owner of the key or is not an ancestor of the RRset.
+    #[error("The signer's name does not match the key or the owner name of the RRset.")]
+    SignerName,
+    /// The record used as a key is not a DNSKEY.
+    #[error("The record is not a DNSKEY.")]
+    NotDnskey,
+    /// The DNSKEY is not a zone key or has an invalid protocol.
+    #[error("The DNSKEY can not be used to validate the zone.")]
+    NotZoneKey,
+    /// The key tag or algorithm of the DNSKEY (with the given key tag) does not match.
+    #[error("The DNSKEY with key tag {0} does not match the key tag or algorithm.")]
+    KeyMismatch(u16),
+    /// The inception of the signature is in the future.
+    #[error("The signature is not valid yet.")]
+    NotYetValid,
+    /// The expiration of the signature is in the past.
+    #[error("The signature has expired.")]
+    Expired,
+    /// The algorithm has not been implemented.
+    #[error("Algorithm {0:?} is not supported.")]
+    UnsupportedAlgorithm(Algorithm),
+    /// The digest type has not been implemented.
+    #[error("Digest type {0:?} is not supported.")]
+    UnsupportedDigest(DigestType),
+    /// The public key of the DNSKEY is malformed.
+    #[error("The public key of the DNSKEY is malformed.")]
+    InvalidKey,
+    /// The signature is not valid for the data.
+    #[error("The signature does not match the data.")]
+    BadSignature,
+    /// The digest of the DS does not match the DNSKEY.
+    #[error("The digest of the DS does not match the DNSKEY.")]
+    DigestMismatch,
+}
+
+/// Collect the records with owner `name` and type `rrtype`, which form a RRset.
+#[inline]
+pub fn rrset<'r, 'a>(
+    records: &'r [ResourceRecord<'a>],
+    name: &Name<'_>,
+    rrtype: Type,
+) -> Vec<&'r ResourceRecord<'a>> {
+    records
+        .iter()
+        .filter(|rr| rr.preamble.rrtype == rrtype && rr.preamble.name == *name)
+        .collect()
+}
+
+/// Collect the signatures with owner `name` that cover the type `rrtype`.
+#[inline]
+pub fn rrsigs<'r, 'a>(
+    records: &'r [ResourceRecord<'a>],
+    name: &Name<'_>,
+    rrtype: Type,
+) -> Vec<&'r Rrsig<'a>> {
+    records
+        .iter()
+        .filter(|rr| rr.preamble.name == *name)
+        .filter_map(|rr| match &rr.data {
+            RecordData::Rrsig(rrsig) if rrsig.type_covered == rrtype => Some(rrsig),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Build the data covered by `rrsig` for a RRset, as described in RFC 4034 section 3.1.8.1.
+///
+/// The records are written in canonical form, with the original TTL of the signature, sorted
+/// by their canonical RDATA and without duplicates. If the signature was created for a wildcard
+/// the owner name is replaced by the wildcard.
+pub fn signed_data(
+    rrset: &[&ResourceRecord<'_>],
+    rrsig: &Rrsig<'_>,
+) -> Result<Vec<u8>, ValidationError> {
+    let first = rrset.first().ok_or(ValidationError::EmptyRrset)?;
+    let preamble = &first.preamble;
+    if rrset.iter().any(|rr| {
+        rr.preamble.rrtype != preamble.rrtype
+            || rr.preamble.class != preamble.class
+            || rr.preamble.name != preamble.name
+    }) {
+        Err(ValidationError::MixedRrset)?
+    }
+    if preamble.rrtype != rrsig.type_covered {
+        Err(ValidationError::TypeCovered(rrsig.type_covered))?
+    }
+
+    let mut owner = Vec::with_capacity(preamble.name.size());
+    canonical_owner(&preamble.name, rrsig.labels, &mut owner)?;
+
+    let mut rdatas: Vec<Vec<u8>> = rrset
+        .iter()
+        .map(|rr| {
+            let mut rdata = Vec::new();
+            rr.data.serialize_canonical(&mut rdata);
+            rdata
+        })
+        .collect();
+    rdatas.sort();
+    rdatas.dedup();
+
+    let mut data = Vec::new();
+    rrsig.serialize_unsigned(&mut data);
+    for rdata in rdatas {
+        data.extend(&owner);
+        push_u16(&mut data, preamble.rrtype.into());
+        push_u16(&mut data, preamble.class.into());
+        push_u32(&mut data, rrsig.original_ttl);
+        push_u16(&mut data, rdata.len() as _);
+        data.extend(rdata);
+    }
+    Ok(data)
+}
+
+/// Check that the current time is inside the validity period of the signature.
+///
+/// The times are compared using serial number arithmetic (RFC 1982), as required by RFC 4034
+/// section 3.1.5.
+#[inline]
+pub fn check_validity(rrsig: &Rrsig<'_>, now: SystemTime) -> Result<(), ValidationError> {
+    let now = timestamp(now);
+    if (now.wrapping_sub(rrsig.inception) as i32) < 0 {
+        Err(ValidationError::NotYetValid)?
+    }
+    if (rrsig.expiration.wrapping_sub(now) as i32) < 0 {
+        Err(ValidationError::Expired)?
+    }
+    Ok(())
+}
+
+/// Verify the signature of a RRset with the key in a DNSKEY record at time `now`.
+///
+/// Besides the cryptographic signature, it checks that the key matches the signature, that the
+/// signer is the owner of the key and an ancestor of the RRset (RFC 4035 section 5.3.1) and that
+/// the signature is inside its validity period.
+///
+/// ```
+/// # use dominion_parser::DnsPacket;
+/// # use dominion_parser::body::Type;
+/// # use dominion_parser::validation;
+/// # use std::time::{Duration, UNIX_EPOCH};
+/// # const RES: &'static [u8; 183] = include_bytes!("../assets/dns_dnssec_response.bin");
+/// # const KEYS: &'static [u8; 307] = include_bytes!("../assets/dns_dnssec_dnskey.bin");
+/// let res = DnsPacket::try_from(&RES[..]).unwrap();
+/// let keys = DnsPacket::try_from(&KEYS[..]).unwrap();
+/// let now = UNIX_EPOCH + Duration::from_secs(1_800_000_000);
+///
+/// let name = &res.questions[0].name;
+/// let rrset = validation::rrset(&res.answers, name, Type::A);
+/// let rrsig = validation::rrsigs(&res.answers, name, Type::A)[0];
+/// let valid = keys
+///     .answers
+///     .iter()
+///     .any(|key| validation::verify_rrsig(&rrset, rrsig, key, now).is_ok());
+/// assert!(valid);
+/// ```
+pub fn verify_rrsig(
+    rrset: &[&ResourceRecord<'_>],
+    rrsig: &Rrsig<'_>,
+    dnskey: &ResourceRecord<'_>,
+    now: SystemTime,
+) -> Result<(), ValidationError> {
+    let key = as_dnskey(dnskey)?;
+    if !key.is_zone_key() || key.protocol != DNSKEY_PROTOCOL {
+        Err(ValidationError::NotZoneKey)?
+    }
+    if key.algorithm != rrsig.algorithm || key.key_tag() != rrsig.key_tag {
+        Err(ValidationError::KeyMismatch(key.key_tag()))?
+    }
+    let owner = &rrset
+        .first()
+        .ok_or(ValidationError::EmptyRrset)?
+        .preamble
+        .name;
+    if rrsig.signer != dnskey.preamble.name
+        || !(rrsig.signer == *owner || rrsig.signer.is_subdomain(owner))
+    {
+        Err(ValidationError::SignerName)?
+    }
+    check_validity(rrsig, now)?;
+    let data = signed_data(rrset, rrsig)?;
+    verify(key, &data, &rrsig.signature)
+}
+
+/// Verify that a DS record refers to the key in a DNSKEY record.
+///
+/// ```
+/// # use dominion_parser::DnsPacket;
+/// # use dominion_parser::body::RecordData;
+/// # use dominion_parser::validation;
+/// # const DS: &'static [u8; 187] = include_bytes!("../assets/dns_dnssec_ds.bin");
+/// # const KEYS: &'static [u8; 307] = include_bytes!("../assets/dns_dnssec_dnskey.bin");
+/// let ds = DnsPacket::try_from(&DS[..]).unwrap();
+/// let keys = DnsPacket::try_from(&KEYS[..]).unwrap();
+///
+/// let RecordData::Ds(ds) = &ds.answers[0].data else { panic!() };
+/// assert!(validation::verify_ds(ds, &keys.answers[0]).is_ok());
+/// ```
+pub fn verify_ds(ds: &Ds<'_>, dnskey: &ResourceRecord<'_>) -> Result<(), ValidationError> {
+    let key = as_dnskey(dnskey)?;
+    if key.algorithm != ds.algorithm || key.key_tag() != ds.key_tag {
+        Err(ValidationError::KeyMismatch(key.key_tag()))?
+    }
+    if ds_digest(&dnskey.preamble.name, key, ds.digest_type)? == ds.digest[..] {
+        Ok(())
+    } else {
+        Err(ValidationError::DigestMismatch)
+    }
+}
+
+/// Compute the digest of a DNSKEY owned by `owner`, as included in DS records (RFC 4034
+/// section 5.1.4).
+pub fn ds_digest(
+    owner: &Name<'_>,
+    key: &Dnskey<'_>,
+    digest_type: DigestType,
+) -> Result<Vec<u8>, ValidationError> {
+    let algorithm = match digest_type {
+        DigestType::Sha1 => &digest::SHA1_FOR_LEGACY_USE_ONLY,
+        DigestType::Sha256 => &digest::SHA256,
+        DigestType::Sha384 => &digest::SHA384,
+        other => Err(ValidationError::UnsupportedDigest(other))?,
+    };
+    let mut data = Vec::new();
+    owner.serialize_canonical(&mut data);
+    key.serialize(&mut data);
+    Ok(digest::digest(algorithm, &data).as_ref().to_vec())
+}
+
+/// Compute the hashed owner name of NSEC3 records (RFC 5155 section 5), in binary form.
+///
+/// ```
+/// # use dominion_parser::body::name::Name;
+/// # use dominion_parser::validation::nsec3_hash;
+/// let name = Name::try_from("example").unwrap();
+/// let hash = nsec3_hash(&name, &[0xaa, 0xbb, 0xcc, 0xdd], 12);
+/// assert_eq!(hash.len(), 20);
+/// ```
+pub fn nsec3_hash(name: &Name<'_>, salt: &[u8], iterations: u16) -> Vec<u8> {
+    let mut data = Vec::with_capacity(name.size() + salt.len());
+    name.serialize_canonical(&mut data);
+    let mut hash = data;
+    for _ in 0..=iterations {
+        hash.extend(salt);
+        hash = digest::digest(&digest::SHA1_FOR_LEGACY_USE_ONLY, &hash)
+            .as_ref()
+            .to_vec();
+    }
+    hash
+}
+
+#[inline]
+fn as_dnskey<'r, 'a>(record: &'r ResourceRecord<'a>) -> Result<&'r Dnskey<'a>, ValidationError> {
+    match &record.data {
+        RecordData::Dnskey(key) => Ok(key),
+        _ => Err(ValidationError::NotDnskey),
+    }
+}
+
+/// Verify the signature of `data` with the algorithm and public key of `key`.
+fn verify(key: &Dnskey<'_>, data: &[u8], sig: &[u8]) -> Result<(), ValidationError> {
+    let public_key = &key.public_key[..];
+    let result = match key.algorithm {
+        Algorithm::RsaSha256 | Algorithm::RsaSha512 => {
+            let params = if key.algorithm == Algorithm::RsaSha256 {
+                &signature::RSA_PKCS1_1024_8192_SHA256_FOR_LEGACY_USE_ONLY
+            } else {
+                &signature::RSA_PKCS1_1024_8192_SHA512_FOR_LEGACY_USE_ONLY
+            };
+            let (e, n) = rsa_components(public_key)?;
+            signature::RsaPublicKeyComponents { n, e }.verify(params, data, sig)
+        }
+        Algorithm::EcdsaP256Sha256 => {
+            let point = ecdsa_point(public_key, 64)?;
+            signature::UnparsedPublicKey::new(&signature::ECDSA_P256_SHA256_FIXED, point)
+                .verify(data, sig)
+        }
+        Algorithm::EcdsaP384Sha384 => {
+            let point = ecdsa_point(public_key, 96)?;
+            signature::UnparsedPublicKey::new(&signature::ECDSA_P384_SHA384_FIXED, point)
+                .verify(data, sig)
+        }
+        Algorithm::Ed25519 => {
+            if public_key.len() != 32 {
+                Err(ValidationError::InvalidKey)?
+            }
+            signature::UnparsedPublicKey::new(&signature::ED25519, public_key).verify(data, sig)
+        }
+        other => Err(ValidationError::UnsupportedAlgorithm(other))?,
+    };
+    result.map_err(|_| ValidationError::BadSignature)
+}
+
+/// Split a RSA public key in the format of RFC 3110 into its exponent and modulus.
+fn rsa_components(key: &[u8]) -> Result<(&[u8], &[u8]), ValidationError> {
+    let (len, start) = match key {
+        [0, high, low, ..] => (u16::from_be_bytes([*high, *low]) as usize, 3),
+        [len, ..] => (*len as usize, 1),
+        [] => Err(ValidationError::InvalidKey)?,
+    };
+    if key.len() <= start + len {
+        Err(ValidationError::InvalidKey)?
+    }
+    let (exponent, modulus) = key[start..].split_at(len);
+    let leading = modulus.iter().take_while(|&&b| b == 0).count();
+    Ok((exponent, &modulus[leading..]))
+}
+
+/// Add the uncompressed point prefix to an ECDSA public key (RFC 6605).
+fn ecdsa_point(key: &[u8], len: usize) -> Result<Vec<u8>, ValidationError> {
+    if key.len() != len {
+        Err(ValidationError::InvalidKey)?
+    }
+    let mut point = Vec::with_capacity(len + 1);
+    point.push(0x04);
+    point.extend(key);
+    Ok(point)
+}
+
+/// Write the owner name of a RRset in canonical form, replacing the labels that are not counted
+/// by the signature with a wildcard (RFC 4035 section 5.3.2).
+fn canonical_owner(name: &Name<'_>, labels: u8, out: &mut Vec<u8>) -> Result<(), ValidationError> {
+    let labels = labels as usize;
+    if labels > rrsig_labels(name) {
+        Err(ValidationError::LabelCount(labels as u8))?
+    }
+    if labels < name.label_count() {
+        out.extend(b"\x01*");
+    }
+    for label in name.iter_human().skip(name.label_count() - labels) {
+        let label = label.as_bytes();
+        out.push(label.len() as _);
+        out.extend(label.iter().map(|b| b.to_ascii_lowercase()));
+    }
+    out.push(0);
+    Ok(())
+}
+
+/// The number of labels of an owner name counted by its signatures, which excludes a leftmost
+/// wildcard label (RFC 4034 section 3.1.3).
+#[inline]
+pub(crate) fn rrsig_labels(name: &Name<'_>) -> usize {
+    match name.iter_human().next() {
+        Some(label) if label.as_bytes() == b"*" => name.label_count() - 1,
+        _ => name.label_count(),
+    }
+}
+
+/// Seconds since the epoch in the 32 bit format of the RRSIG records.
+#[inline]
+pub(crate) fn timestamp(time: SystemTime) -> u32 {
+    time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DnsPacket;
+    use crate::body::{Class, RecordPreamble};
+    use alloc::borrow::Cow;
+    use core::iter::zip;
+    use core::net::Ipv4Addr;
+    use std::time::Duration;
+
+    const RES: &[u8; 183] = include_bytes!("../assets/dns_dnssec_response.bin");
+    const DNSKEY: &[u8; 307] = include_bytes!("../assets/dns_dnssec_dnskey.bin");
+    const DS: &[u8; 187] = include_bytes!("../assets/dns_dnssec_ds.bin");
+    const NSEC: &[u8; 348] = include_bytes!("../assets/dns_dnssec_nxdomain.bin");
+    const NSEC3: &[u8; 576] = include_bytes!("../assets/dns_dnssec_nsec3.bin");
+    const ALGORITHMS: &[u8; 1021] = include_bytes!("../assets/dns_dnssec_algorithms.bin");
+    const RFC8080: &[u8; 168] = include_bytes!("../assets/dns_dnssec_rfc8080.bin");
+
+    fn now() -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(1_800_000_000)
+    }
+
+    fn verify_section(records: &[ResourceRecord<'_>], keys: &[ResourceRecord<'_>]) -> usize {
+        let mut verified = 0;
+        for rr in records {
+            let RecordData::Rrsig(rrsig) = &rr.data else {
+                continue;
+            };
+            let rrset = rrset(records, &rr.preamble.name, rrsig.type_covered);
+            let valid = keys
+                .iter()
+                .any(|key| verify_rrsig(&rrset, rrsig, key, now()).is_ok());
+            assert!(valid, "No key validates RRSIG {rrsig:?}");
+            verified += 1;
+        }
+        verified
+    }
+
+    #[test]
+    fn verify_answers() {
+        let keys = DnsPacket::try_from(&DNSKEY[..]).unwrap();
+        let res = DnsPacket::try_from(&RES[..]).unwrap();
+
+        assert_eq!(verify_section(&res.answers, &keys.answers), 1);
+        assert_eq!(verify_section(&keys.answers, &keys.answers), 1);
+    }
+
+    #[test]
+    fn verify_denial_of_existence() {
+        let keys = DnsPacket::try_from(&DNSKEY[..]).unwrap();
+        let nsec = DnsPacket::try_from(&NSEC[..]).unwrap();
+        let nsec3 = DnsPacket::try_from(&NSEC3[..]).unwrap();
+
+        assert_eq!(verify_section(&nsec.authority, &keys.answers), 2);
+        assert_eq!(verify_section(&nsec3.authority, &keys.answers), 3);
+    }
+
+    #[test]
+    fn verify_algorithms() {
+        let keys = DnsPacket::try_from(&ALGORITHMS[..]).unwrap();
+        let name = &keys.questions[0].name;
+        let rrset = rrset(&keys.answers, name, Type::Dnskey);
+        let rrsigs = rrsigs(&keys.answers, name, Type::Dnskey);
+
+        assert_eq!(rrsigs.len(), 3);
+        for (rrsig, key) in zip(rrsigs, &keys.answers) {
+            verify_rrsig(&rrset, rrsig, key, now()).unwrap();
+        }
+    }
+
+    #[test]
+    fn verify_rfc8080_example() {
+        // RFC 8080, section 6.1: the MX record of example.com signed with an Ed25519 key.
+        let public_key = [
+            0x97, 0x4d, 0x96, 0xa2, 0x2d, 0x22, 0x4b, 0xc0, 0x1a, 0xdb, 0x91, 0x50, 0x91, 0x47,
+            0x7d, 0x44, 0xcc, 0xd9, 0x1c, 0x9a, 0x41, 0xa1, 0x14, 0x30, 0x01, 0x01, 0x17, 0xd5,
+            0x2c, 0x59, 0x24, 0x0e,
+        ];
+        let digest = [
+            0x3a, 0xa5, 0xab, 0x37, 0xef, 0xce, 0x57, 0xf7, 0x37, 0xfc, 0x16, 0x27, 0x01, 0x3f,
+            0xee, 0x07, 0xbd, 0xf2, 0x41, 0xbd, 0x10, 0xf3, 0xb1, 0x96, 0x4a, 0xb5, 0x5c, 0x78,
+            0xe7, 0x9a, 0x30, 0x4b,
+        ];
+        let res = DnsPacket::try_from(&RFC8080[..]).unwrap();
+        let name = &res.questions[0].name;
+        let key = ResourceRecord {
+            preamble: RecordPreamble {
+                name: name.clone(),
+                rrtype: Type::Dnskey,
+                class: Class::IN,
+                ttl: 3600,
+            },
+            data: RecordData::Dnskey(Dnskey {
+                flags: 257,
+                protocol: 3,
+                algorithm: Algorithm::Ed25519,
+                public_key: Cow::from(&public_key[..]),
+            }),
+        };
+        let ds = Ds {
+            key_tag: 3613,
+            algorithm: Algorithm::Ed25519,
+            digest_type: DigestType::Sha256,
+            digest: Cow::from(&digest[..]),
+        };
+        verify_ds(&ds, &key).unwrap();
+
+        let rrset = rrset(&res.answers, name, Type::Mx);
+        let rrsig = rrsigs(&res.answers, name, Type::Mx)[0];
+        let signed = UNIX_EPOCH + Duration::from_secs(1_439_000_000);
+        verify_rrsig(&rrset, rrsig, &key, signed).unwrap();
+        assert_eq!(
+            verify_rrsig(&rrset, rrsig, &key, now()),
+            Err(ValidationError::Expired)
+        );
+    }
+
+    #[test]
+    fn verify_canonical_owner() {
+        let keys = DnsPacket::try_from(&DNSKEY[..]).unwrap();
+        let mut res = DnsPacket::try_from(&RES[..]).unwrap();
+        let name = Name::try_from("WWW.Example.COM").unwrap();
+        for rr in res.answers.iter_mut() {
+            rr.preamble.name = name.clone();
+        }
+        let rrset = rrset(&res.answers, &name, Type::A);
+        let rrsig = rrsigs(&res.answers, &name, Type::A)[0];
+        verify_rrsig(&rrset, rrsig, &keys.answers[1], now()).unwrap();
+    }
+
+    #[test]
+    fn verify_tampered() {
+        let keys = DnsPacket::try_from(&DNSKEY[..]).unwrap();
+        let mut res = DnsPacket::try_from(&RES[..]).unwrap();
+        res.answers[0].data = RecordData::A(Ipv4Addr::new(203, 0, 113, 1));
+        let name = &res.questions[0].name;
+        let rrset = rrset(&res.answers, name, Type::A);
+        let rrsig = rrsigs(&res.answers, name, Type::A)[0];
+
+        let err = verify_rrsig(&rrset, rrsig, &keys.answers[1], now()).unwrap_err();
+        assert_eq!(err, ValidationError::BadSignature);
+    }
+
+    #[test]
+    fn verify_wrong_key() {
+        let keys = DnsPacket::try_from(&DNSKEY[..]).unwrap();
+        let res = DnsPacket::try_from(&RES[..]).unwrap();
+        let name = &res.questions[0].name;
+        let rrset = rrset(&res.answers, name, Type::A);
+        let rrsig = rrsigs(&res.answers, name, Type::A)[0];
+
+        let err = verify_rrsig(&rrset, rrsig, &keys.answers[0], now()).unwrap_err();
+        assert_eq!(err, ValidationError::KeyMismatch(14870));
+        let err = verify_rrsig(&rrset, rrsig, &res.answers[0], now()).unwrap_err();
+        assert_eq!(err, ValidationError::NotDnskey);
+    }
+
+    #[test]
+    fn verify_validity_period() {
+        let keys = DnsPacket::try_from(&DNSKEY[..]).unwrap();
+        let res = DnsPacket::try_from(&RES[..]).unwrap();
+        let name = &res.questions[0].name;
+        let rrset = rrset(&res.answers, name, Type::A);
+        let rrsig = rrsigs(&res.answers, name, Type::A)[0];
+        let key = &keys.answers[1];
+
+        let before = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let after = UNIX_EPOCH + Duration::from_secs(2_100_000_000);
+        assert_eq!(
+            verify_rrsig(&rrset, rrsig, key, before),
+            Err(ValidationError::NotYetValid)
+        );
+        assert_eq!(
+            verify_rrsig(&rrset, rrsig, key, after),
+            Err(ValidationError::Expired)
+        );
+    }
+
+    #[test]
+    fn verify_ds_digest() {
+        let keys = DnsPacket::try_from(&DNSKEY[..]).unwrap();
+        let ds = DnsPacket::try_from(&DS[..]).unwrap();
+        let RecordData::Ds(ds) = &ds.answers[0].data else {
+            panic!("Expected DS record")
+        };
+
+        verify_ds(ds, &keys.answers[0]).unwrap();
+        assert_eq!(
+            verify_ds(ds, &keys.answers[1]),
+            Err(ValidationError::KeyMismatch(2017))
+        );
+
+        let mut tampered = ds.clone();
+        tampered.digest.to_mut()[0] ^= 0xFF;
+        assert_eq!(
+            verify_ds(&tampered, &keys.answers[0]),
+            Err(ValidationError::DigestMismatch)
+        );
+    }
+
+    #[test]
+    fn nsec3_hash_vectors() {
+        // RFC 5155, appendix A
+        let salt = [0xaa, 0xbb, 0xcc, 0xdd];
+        let expected: [(&str, [u8; 20]); 2] = [
+            (
+                "example",
+                [
+                    0x06, 0x53, 0x68, 0xab, 0xee, 0xd7, 0xec, 0x6e, 0x9f, 0xeb, 0xa9, 0x6b, 0x8c,
+                    0x8b, 0xc3, 0xe8, 0xb7, 0x91, 0xf7, 0x16,
+                ],
+            ),
+            (
+                "a.example",
+                [
+                    0x19, 0x6d, 0xd8, 0xc3, 0x30, 0x67, 0x83, 0xa8, 0x19, 0x0f, 0x52, 0xc2, 0x62,
+                    0xd2, 0xb7, 0xe5, 0xe8, 0x36, 0xe7, 0xf5,
+                ],
+            ),
+        ];
+        for (name, hash) in expected {
+            let name = Name::try_from(name).unwrap();
+            assert_eq!(nsec3_hash(&name, &salt, 12), hash);
+        }
+    }
+
+    #[test]
+    fn wildcard_owner() {
+        let name = Name::try_from("a.b.example.com").unwrap();
+        let mut out = Vec::new();
+        canonical_owner(&name, 2, &mut out).unwrap();
+        assert_eq!(&out[..], b"\x01*\x07example\x03com\x00");
+
+        let name = Name::try_from("*.example.com").unwrap();
+        let mut out = Vec::new();
+        canonical_owner(&name, 2, &mut out).unwrap();
+        assert_eq!(&out[..], b"\x01*\x07example\x03com\x00");
+        assert!(canonical_owner(&name, 3, &mut out).is_err());
+    }
+}
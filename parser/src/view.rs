@@ -0,0 +1,413 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use crate::binutils::*;
+use crate::body::name::NameView;
+use crate::body::{Class, QType, Question, ResourceRecord, Type};
+use crate::header::DnsHeader;
+use crate::{DnsPacket, ParseError};
+
+use core::iter::FusedIterator;
+
+/// A DNS packet that is read lazily from its buffer, without copying it.
+///
+/// The structure of the packet is validated when the view is created: every domain name
+/// and the bounds of every question and resource record. The sections are then read on
+/// demand with the iterators, and the RDATA of the records is only parsed when a record is
+/// converted into a [ResourceRecord]. This is cheaper than parsing a [DnsPacket] when only
+/// some parts of the packet are needed, like the header or the question.
+///
+/// ```
+/// # use dominion_parser::DnsPacket;
+/// # use dominion_parser::body::QType;
+/// # use dominion_parser::view::DnsPacketView;
+/// # const REQ: &'static [u8; 33] = include_bytes!("../assets/dns_request.bin");
+/// let view = DnsPacketView::try_from(&REQ[..]).unwrap();
+/// let question = view.questions().next().unwrap();
+/// assert_eq!(question.name().to_string(), "hello.world.com.");
+/// assert_eq!(question.qtype(), QType::A);
+///
+/// let packet = DnsPacket::try_from(&view).unwrap();
+/// assert_eq!(packet.questions.len(), 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct DnsPacketView<'a> {
+    buff: &'a [u8],
+    header: DnsHeader,
+    /// Start of the questions, answers, authority and additional sections, and end of the packet.
+    sections: [usize; 5],
+}
+
+impl<'a> TryFrom<&'a [u8]> for DnsPacketView<'a> {
+    type Error = ParseError;
+
+    fn try_from(buff: &'a [u8]) -> Result<Self, Self::Error> {
+        let header = DnsHeader::try_from(buff)?;
+        let mut sections = [12; 5];
+        let mut pos = 12;
+        for _ in 0..header.questions {
+            let (_, size) = NameView::parse(buff, pos)?;
+            pos += size + 4;
+            if buff.len() < pos {
+                Err(ParseError::OobRead(pos))?
+            }
+        }
+        let counts = [header.answers, header.authority, header.additional];
+        for (i, count) in counts.into_iter().enumerate() {
+            sections[i + 1] = pos;
+            for _ in 0..count {
+                let (_, size) = NameView::parse(buff, pos)?;
+                let rdlen = safe_u16_read(buff, pos + size + 8)?;
+                pos += size + 10 + rdlen as usize;
+                if buff.len() < pos {
+                    Err(ParseError::OobRead(pos))?
+                }
+            }
+        }
+        sections[4] = pos;
+        Ok(Self {
+            buff,
+            header,
+            sections,
+        })
+    }
+}
+
+impl<'a> TryFrom<&DnsPacketView<'a>> for DnsPacket<'a> {
+    type Error = ParseError;
+
+    fn try_from(view: &DnsPacketView<'a>) -> Result<Self, Self::Error> {
+        Ok(Self {
+            header: view.header.clone(),
+            questions: view.questions().map(|q| q.to_question()).collect(),
+            answers: view
+                .answers()
+                .map(|rr| rr.to_record())
+                .collect::<Result<_, _>>()?,
+            authority: view
+                .authority()
+                .map(|rr| rr.to_record())
+                .collect::<Result<_, _>>()?,
+            additional: view
+                .additional()
+                .map(|rr| rr.to_record())
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+impl<'a> DnsPacketView<'a> {
+    /// The header of the packet.
+    #[inline]
+    pub fn header(&self) -> &DnsHeader {
+        &self.header
+    }
+
+    /// The bytes of the packet, without any trailing bytes of the buffer.
+    #[inline]
+    pub fn as_bytes(&self) -> &'a [u8] {
+        &self.buff[..self.sections[4]]
+    }
+
+    /// Return an iterator over the questions of the packet.
+    #[inline]
+    pub fn questions(&self) -> Questions<'a> {
+        Questions {
+            buff: self.buff,
+            pos: self.sections[0],
+            remaining: self.header.questions as _,
+        }
+    }
+
+    /// Return an iterator over the resource records answering the question(s).
+    #[inline]
+    pub fn answers(&self) -> Records<'a> {
+        self.records(1, self.header.answers)
+    }
+
+    /// Return an iterator over the resource records pointing toward a domain authority.
+    #[inline]
+    pub fn authority(&self) -> Records<'a> {
+        self.records(2, self.header.authority)
+    }
+
+    /// Return an iterator over the resource records holding additional information.
+    #[inline]
+    pub fn additional(&self) -> Records<'a> {
+        self.records(3, self.header.additional)
+    }
+
+    #[inline]
+    fn records(&self, section: usize, count: u16) -> Records<'a> {
+        Records {
+            buff: self.buff,
+            pos: self.sections[section],
+            remaining: count as _,
+        }
+    }
+}
+
+/// A question read from a [DnsPacketView].
+#[derive(Debug, Clone, Copy)]
+pub struct QuestionView<'a> {
+    name: NameView<'a>,
+    qtype: QType,
+    class: Class,
+}
+
+impl<'a> QuestionView<'a> {
+    /// The domain name being queried.
+    #[inline]
+    pub fn name(&self) -> NameView<'a> {
+        self.name
+    }
+
+    /// The type of the query.
+    #[inline]
+    pub fn qtype(&self) -> QType {
+        self.qtype
+    }
+
+    /// The class of the query.
+    #[inline]
+    pub fn class(&self) -> Class {
+        self.class
+    }
+
+    /// Copy the question into an owned [Question].
+    #[inline]
+    pub fn to_question(&self) -> Question<'a> {
+        Question {
+            name: self.name.to_name(),
+            qtype: self.qtype,
+            class: self.class,
+        }
+    }
+}
+
+/// An iterator over the questions of a [DnsPacketView].
+#[derive(Debug, Clone)]
+pub struct Questions<'a> {
+    buff: &'a [u8],
+    pos: usize,
+    remaining: usize,
+}
+
+impl<'a> Iterator for Questions<'a> {
+    type Item = QuestionView<'a>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let (name, size) = NameView::parse_unchecked(self.buff, self.pos);
+        let n = self.pos + size;
+        self.pos = n + 4;
+        self.remaining -= 1;
+        Some(QuestionView {
+            name,
+            qtype: safe_u16_read(self.buff, n).ok()?.into(),
+            class: safe_u16_read(self.buff, n + 2).ok()?.into(),
+        })
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl ExactSizeIterator for Questions<'_> {}
+
+impl FusedIterator for Questions<'_> {}
+
+/// A resource record read from a [DnsPacketView]. The RDATA is not parsed until the record is
+/// converted into a [ResourceRecord].
+#[derive(Debug, Clone, Copy)]
+pub struct RecordView<'a> {
+    buff: &'a [u8],
+    pos: usize,
+    name: NameView<'a>,
+    rrtype: Type,
+    class: Class,
+    ttl: i32,
+    rdata: &'a [u8],
+}
+
+impl<'a> RecordView<'a> {
+    /// The domain name the RR refers to.
+    #[inline]
+    pub fn name(&self) -> NameView<'a> {
+        self.name
+    }
+
+    /// The RR type.
+    #[inline]
+    pub fn rrtype(&self) -> Type {
+        self.rrtype
+    }
+
+    /// The RR class.
+    #[inline]
+    pub fn class(&self) -> Class {
+        self.class
+    }
+
+    /// The time interval that the resource record may be cached.
+    #[inline]
+    pub fn ttl(&self) -> i32 {
+        self.ttl
+    }
+
+    /// The RDATA as found in the packet. Domain names inside it may be compressed.
+    #[inline]
+    pub fn rdata(&self) -> &'a [u8] {
+        self.rdata
+    }
+
+    /// Parse the record into an owned [ResourceRecord].
+    ///
+    /// # Errors
+    ///
+    /// It will error if the RDATA is not valid for the type of the record.
+    #[inline]
+    pub fn to_record(&self) -> Result<ResourceRecord<'a>, ParseError> {
+        ResourceRecord::parse(self.buff, self.pos).map(|(rr, _)| rr)
+    }
+}
+
+/// An iterator over the resource records of a section of a [DnsPacketView].
+#[derive(Debug, Clone)]
+pub struct Records<'a> {
+    buff: &'a [u8],
+    pos: usize,
+    remaining: usize,
+}
+
+impl<'a> Iterator for Records<'a> {
+    type Item = RecordView<'a>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let pos = self.pos;
+        let (name, size) = NameView::parse_unchecked(self.buff, pos);
+        let n = pos + size;
+        let rdlen = safe_u16_read(self.buff, n + 8).ok()? as usize;
+        let rdata = safe_slice_read(self.buff, n + 10, rdlen).ok()?;
+        self.pos = n + 10 + rdlen;
+        self.remaining -= 1;
+        Some(RecordView {
+            buff: self.buff,
+            pos,
+            name,
+            rrtype: safe_u16_read(self.buff, n).ok()?.into(),
+            class: safe_u16_read(self.buff, n + 2).ok()?.into(),
+            ttl: safe_i32_read(self.buff, n + 4).ok()?,
+            rdata,
+        })
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl ExactSizeIterator for Records<'_> {}
+
+impl FusedIterator for Records<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::body::RecordData;
+    use crate::body::name::NameError;
+    use alloc::string::ToString;
+    use alloc::vec::Vec;
+
+    const REQ: &[u8; 33] = include_bytes!("../assets/dns_request.bin");
+    const RES: &[u8; 49] = include_bytes!("../assets/dns_response.bin");
+
+    #[test]
+    fn view_request() {
+        let view = DnsPacketView::try_from(&REQ[..]).unwrap();
+        assert_eq!(view.header().questions, 1);
+        assert_eq!(view.questions().len(), 1);
+        assert_eq!(view.answers().count(), 0);
+        assert_eq!(view.as_bytes(), &REQ[..]);
+
+        let question = view.questions().next().unwrap();
+        let (owned, _) = Question::parse(&REQ[..], 12).unwrap();
+        assert_eq!(question.name().to_string(), owned.name.to_string());
+        assert_eq!(question.qtype(), owned.qtype);
+        assert_eq!(question.class(), owned.class);
+        assert!(question.name().matches(&owned.name));
+        assert_eq!(question.name().label_count(), owned.name.label_count());
+    }
+
+    #[test]
+    fn view_response() {
+        let view = DnsPacketView::try_from(&RES[..]).unwrap();
+        let packet = DnsPacket::try_from(&RES[..]).unwrap();
+
+        let answer = view.answers().next().unwrap();
+        // The owner name is compressed with a pointer to the question.
+        assert_eq!(answer.name().to_string(), "hello.world.com.");
+        assert_eq!(answer.rrtype(), Type::A);
+        assert_eq!(answer.ttl(), packet.answers[0].preamble.ttl);
+        assert_eq!(answer.rdata().len(), 4);
+        let rr = answer.to_record().unwrap();
+        assert!(matches!(rr.data, RecordData::A(ip) if ip.octets() == answer.rdata()));
+
+        let converted = DnsPacket::try_from(&view).unwrap();
+        assert_eq!(Vec::from(&converted), Vec::from(&packet));
+    }
+
+    #[test]
+    fn trailing_bytes() {
+        let mut buff = RES.to_vec();
+        buff.extend([1, 2, 3]);
+        let view = DnsPacketView::try_from(&buff[..]).unwrap();
+        assert_eq!(view.as_bytes(), &RES[..]);
+    }
+
+    #[test]
+    fn binary_label() {
+        let mut buff = REQ.to_vec();
+        buff[13] = 0;
+        let view = DnsPacketView::try_from(&buff[..]).unwrap();
+        let question = view.questions().next().unwrap();
+        assert_eq!(question.name().to_string(), "\\000ello.world.com.");
+    }
+
+    #[test]
+    fn invalid_structure() {
+        // Truncated RDATA.
+        let res = DnsPacketView::try_from(&RES[..RES.len() - 1]);
+        assert!(matches!(res, Err(ParseError::OobRead(_))));
+
+        // Forward pointer in the owner name of the answer.
+        let mut buff = RES.to_vec();
+        buff[33] = 0xC0;
+        buff[34] = 0xFF;
+        assert!(DnsPacketView::try_from(&buff[..]).is_err());
+
+        // Domain name of 257 bytes.
+        let mut buff = REQ[..12].to_vec();
+        for _ in 0..4 {
+            buff.push(63);
+            buff.extend([b'a'; 63]);
+        }
+        buff.extend([0, 0, 1, 0, 1]);
+        let res = DnsPacketView::try_from(&buff[..]);
+        assert!(matches!(
+            res,
+            Err(ParseError::InvalidName(NameError::NameLength(257)))
+        ));
+    }
+}
@@ -0,0 +1,393 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use thiserror::Error;
+
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::io;
+
+/// An error was encountered when writing a serialized DNS packet.
+#[derive(Error, Debug)]
+pub enum WriteError {
+    /// The buffer is too small for the packet, with an upper bound of the size that is needed.
+    #[error("The buffer is too small, up to {0} bytes are needed.")]
+    BufferFull(usize),
+    /// A length or a count is too big for its field in the wire format, like the RDLENGTH of
+    /// more than 65535 bytes of RDATA.
+    #[error("The value {0} does not fit in its field of the wire format.")]
+    Overflow(usize),
+    /// The underlying writer failed.
+    #[cfg(feature = "std")]
+    #[error("Error when writing the packet: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// A destination for serialized DNS data.
+///
+/// Writing never fails immediately: a writer that runs out of space or fails remembers the
+/// error, ignores the following bytes and reports the error when it is finished. Like this
+/// every serializer can target any [Writer], like a `Vec<u8>`, a [SliceWriter] or an [IoWriter].
+pub trait Writer {
+    /// Get the number of bytes written so far.
+    fn position(&self) -> usize;
+
+    /// Append the `bytes` to the end of the written data.
+    fn put(&mut self, bytes: &[u8]);
+
+    /// Get the bytes written so far, if the writer allows reading and modifying them.
+    ///
+    /// Domain names are only compressed when the written data can be read. The length of the
+    /// RDATA is written after it when the written data can be modified, otherwise the RDATA is
+    /// serialized twice, first to compute the length.
+    fn written_mut(&mut self) -> Option<&mut [u8]>;
+
+    /// Remember that the data can not be represented in the wire format, to report it when the
+    /// writer is finished.
+    ///
+    /// # Panics
+    ///
+    /// By default it panics, as the writer can not report the error. Writers that can report
+    /// errors, like [SliceWriter] and [IoWriter], override it.
+    #[inline]
+    fn fail(&mut self, error: WriteError) {
+        panic!("{error}")
+    }
+}
+
+impl Writer for Vec<u8> {
+    #[inline]
+    fn position(&self) -> usize {
+        self.len()
+    }
+
+    #[inline]
+    fn put(&mut self, bytes: &[u8]) {
+        self.extend_from_slice(bytes);
+    }
+
+    #[inline]
+    fn written_mut(&mut self) -> Option<&mut [u8]> {
+        Some(self)
+    }
+}
+
+/// A [Writer] into a caller-provided buffer, without allocating.
+///
+/// Once the buffer is full the written data can not be read back, so the domain names that
+/// follow are not compressed and the size reported by [SliceWriter::finish] is an upper bound.
+/// Data that can not be represented in the wire format is reported as
+/// [WriteError::Overflow] instead.
+///
+/// ```
+/// # use dominion_parser::DnsPacket;
+/// # use dominion_parser::writer::{SliceWriter, WriteError};
+/// # const REQ: &'static [u8; 33] = include_bytes!("../assets/dns_request.bin");
+/// let packet = DnsPacket::try_from(&REQ[..]).unwrap();
+///
+/// let mut buff = [0; 512];
+/// let mut writer = SliceWriter::new(&mut buff);
+/// packet.serialize(&mut writer);
+/// assert_eq!(writer.finish().unwrap(), &REQ[..]);
+///
+/// let mut buff = [0; 16];
+/// let mut writer = SliceWriter::new(&mut buff);
+/// packet.serialize(&mut writer);
+/// assert!(matches!(writer.finish(), Err(WriteError::BufferFull(33))));
+/// ```
+#[derive(Debug)]
+pub struct SliceWriter<'b> {
+    buff: &'b mut [u8],
+    /// Number of bytes written, or that would have been written if the buffer is full.
+    len: usize,
+    full: bool,
+    error: Option<WriteError>,
+}
+
+impl<'b> SliceWriter<'b> {
+    /// Create a [Writer] that writes from the beginning of `buff`.
+    #[inline]
+    pub fn new(buff: &'b mut [u8]) -> Self {
+        SliceWriter {
+            buff,
+            len: 0,
+            full: false,
+            error: None,
+        }
+    }
+
+    /// Finish writing, obtaining the part of the buffer that has been written.
+    ///
+    /// # Errors
+    ///
+    /// It will error if the data can not be represented in the wire format, or if it did not
+    /// fit in the buffer with an upper bound of the size that would be needed.
+    #[inline]
+    pub fn finish(self) -> Result<&'b mut [u8], WriteError> {
+        match self.error {
+            Some(error) => Err(error),
+            None if self.full => Err(WriteError::BufferFull(self.len)),
+            None => Ok(&mut self.buff[..self.len]),
+        }
+    }
+}
+
+impl Writer for SliceWriter<'_> {
+    #[inline]
+    fn position(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    fn put(&mut self, bytes: &[u8]) {
+        let end = self.len + bytes.len();
+        match self.buff.get_mut(self.len..end) {
+            Some(dst) if !self.full => dst.copy_from_slice(bytes),
+            _ => self.full = true,
+        }
+        self.len = end;
+    }
+
+    #[inline]
+    fn written_mut(&mut self) -> Option<&mut [u8]> {
+        match self.full {
+            true => None,
+            false => Some(&mut self.buff[..self.len]),
+        }
+    }
+
+    #[inline]
+    fn fail(&mut self, error: WriteError) {
+        self.error.get_or_insert(error);
+    }
+}
+
+/// A [Writer] into any [io::Write] (requires the `std` feature), like a file or a TCP stream.
+///
+/// The written data can not be read back, so domain names are never compressed.
+///
+/// ```
+/// # use dominion_parser::DnsPacket;
+/// # use dominion_parser::writer::IoWriter;
+/// # const REQ: &'static [u8; 33] = include_bytes!("../assets/dns_request.bin");
+/// let packet = DnsPacket::try_from(&REQ[..]).unwrap();
+///
+/// let mut writer = IoWriter::new(std::io::Cursor::new(Vec::new()));
+/// packet.serialize(&mut writer);
+/// let out = writer.finish().unwrap().into_inner();
+/// assert_eq!(&out[..], &REQ[..]);
+/// ```
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct IoWriter<T: io::Write> {
+    inner: T,
+    len: usize,
+    error: Option<WriteError>,
+}
+
+#[cfg(feature = "std")]
+impl<T: io::Write> IoWriter<T> {
+    /// Create a [Writer] into `inner`.
+    #[inline]
+    pub fn new(inner: T) -> Self {
+        IoWriter {
+            inner,
+            len: 0,
+            error: None,
+        }
+    }
+
+    /// Finish writing, obtaining the inner writer.
+    ///
+    /// # Errors
+    ///
+    /// It will error with the first error returned by the inner writer, or if the data can not
+    /// be represented in the wire format.
+    #[inline]
+    pub fn finish(self) -> Result<T, WriteError> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(self.inner),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: io::Write> Writer for IoWriter<T> {
+    #[inline]
+    fn position(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    fn put(&mut self, bytes: &[u8]) {
+        if self.error.is_none() {
+            self.error = self.inner.write_all(bytes).err().map(WriteError::Io);
+        }
+        self.len += bytes.len();
+    }
+
+    #[inline]
+    fn written_mut(&mut self) -> Option<&mut [u8]> {
+        None
+    }
+
+    #[inline]
+    fn fail(&mut self, error: WriteError) {
+        self.error.get_or_insert(error);
+    }
+}
+
+/// A [Writer] that only counts the bytes, used to compute lengths before writing the data.
+#[derive(Debug, Default)]
+pub(crate) struct Counter(usize);
+
+impl Writer for Counter {
+    #[inline]
+    fn position(&self) -> usize {
+        self.0
+    }
+
+    #[inline]
+    fn put(&mut self, bytes: &[u8]) {
+        self.0 += bytes.len();
+    }
+
+    #[inline]
+    fn written_mut(&mut self) -> Option<&mut [u8]> {
+        None
+    }
+
+    /// The data is written again after it is measured, the error is reported then.
+    #[inline]
+    fn fail(&mut self, _error: WriteError) {}
+}
+
+/// Overwrite the two bytes before `start` with the length of the data written after it, if
+/// the writer allows it.
+#[inline]
+pub(crate) fn patch_len<W: Writer + ?Sized>(packet: &mut W, start: usize) {
+    let len = checked_u16(packet, packet.position() - start);
+    if let Some(written) = packet.written_mut() {
+        written[start - 2..start].copy_from_slice(&len.to_be_bytes());
+    }
+}
+
+/// Convert a length to its 8 bits field in the wire format, making the writer fail with
+/// [WriteError::Overflow] if it does not fit.
+#[inline]
+pub(crate) fn checked_u8<W: Writer + ?Sized>(packet: &mut W, n: usize) -> u8 {
+    u8::try_from(n).unwrap_or_else(|_| {
+        packet.fail(WriteError::Overflow(n));
+        u8::MAX
+    })
+}
+
+/// Convert a length or a count to its 16 bits field in the wire format, making the writer fail
+/// with [WriteError::Overflow] if it does not fit.
+#[inline]
+pub(crate) fn checked_u16<W: Writer + ?Sized>(packet: &mut W, n: usize) -> u16 {
+    u16::try_from(n).unwrap_or_else(|_| {
+        packet.fail(WriteError::Overflow(n));
+        u16::MAX
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DnsPacket;
+    use crate::body::RecordData;
+    use alloc::vec;
+
+    const RES: &[u8; 49] = include_bytes!("../assets/dns_response.bin");
+
+    #[test]
+    fn slice_writer() {
+        let packet = DnsPacket::try_from(&RES[..]).unwrap();
+        let mut buff = [0; 512];
+        let mut writer = SliceWriter::new(&mut buff);
+        packet.serialize(&mut writer);
+        assert_eq!(writer.finish().unwrap(), &Vec::from(&packet)[..]);
+
+        // Exactly the size of the packet.
+        let mut buff = [0; 49];
+        let mut writer = SliceWriter::new(&mut buff);
+        packet.serialize(&mut writer);
+        assert_eq!(writer.finish().unwrap(), &RES[..]);
+
+        let mut buff = [0; 48];
+        let mut writer = SliceWriter::new(&mut buff);
+        packet.serialize(&mut writer);
+        assert!(matches!(writer.finish(), Err(WriteError::BufferFull(49))));
+
+        // Names are not compressed once the buffer is full.
+        let mut uncompressed = Vec::new();
+        packet.serialize_uncompressed(&mut uncompressed);
+        let mut buff = [0; 16];
+        let mut writer = SliceWriter::new(&mut buff);
+        packet.serialize(&mut writer);
+        assert!(matches!(
+            writer.finish(),
+            Err(WriteError::BufferFull(n)) if n == uncompressed.len() && n > RES.len()
+        ));
+    }
+
+    /// A response with more than 65535 bytes of RDATA in a record.
+    fn oversized_rdata() -> DnsPacket<'static> {
+        let mut packet = DnsPacket::try_from(&RES[..]).unwrap();
+        packet.answers[0].data = RecordData::Txt(vec![b'a'; u16::MAX as usize].into());
+        packet
+    }
+
+    #[test]
+    fn overflow_slice_writer() {
+        let packet = oversized_rdata();
+        let mut buff = vec![0; 2 * u16::MAX as usize];
+        let res = packet.serialize_into(&mut buff);
+        assert!(matches!(res, Err(WriteError::Overflow(65792))));
+
+        // The overflow is reported even if the buffer is full.
+        let res = packet.serialize_into(&mut buff[..100]);
+        assert!(matches!(res, Err(WriteError::Overflow(65792))));
+    }
+
+    #[test]
+    #[should_panic]
+    fn overflow_vec() {
+        let _ = Vec::from(&oversized_rdata());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn io_writer_uncompressed() {
+        let packet = DnsPacket::try_from(&RES[..]).unwrap();
+        let mut writer = IoWriter::new(Vec::new());
+        packet.serialize(&mut writer);
+        let out = writer.finish().unwrap();
+
+        let mut uncompressed = Vec::new();
+        packet.serialize_uncompressed(&mut uncompressed);
+        assert_eq!(out, uncompressed);
+        assert!(DnsPacket::try_from(&out[..]).is_ok());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn io_writer_error() {
+        let packet = DnsPacket::try_from(&RES[..]).unwrap();
+        let mut buff = [0; 16];
+        let mut writer = IoWriter::new(&mut buff[..]);
+        packet.serialize(&mut writer);
+        assert!(matches!(writer.finish(), Err(WriteError::Io(_))));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn io_writer_overflow() {
+        let mut writer = IoWriter::new(Vec::new());
+        oversized_rdata().serialize(&mut writer);
+        assert!(matches!(writer.finish(), Err(WriteError::Overflow(65792))));
+    }
+}
@@ -2,15 +2,25 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
+use dominion_parser::body::dnssec::{Algorithm, DigestType};
+use dominion_parser::body::name::Name;
 use dominion_parser::body::*;
 use dominion_parser::header::*;
-use dominion_parser::DnsPacket;
+use dominion_parser::{DnsPacket, ParseError, ParseOptions, Section};
 use std::net::Ipv4Addr;
 
 const REQ: &[u8; 33] = include_bytes!("../assets/dns_request.bin");
 const RES: &[u8; 49] = include_bytes!("../assets/dns_response.bin");
 
 const LONG_REQ: &[u8; 270] = include_bytes!("../assets/dns_longreq.bin");
+const NXDOMAIN: &[u8; 97] = include_bytes!("../assets/dns_nxdomain.bin");
+const EDNS_REQ: &[u8; 52] = include_bytes!("../assets/dns_request_edns.bin");
+
+const DNSSEC_RES: &[u8; 183] = include_bytes!("../assets/dns_dnssec_response.bin");
+const DNSSEC_DNSKEY: &[u8; 307] = include_bytes!("../assets/dns_dnssec_dnskey.bin");
+const DNSSEC_DS: &[u8; 187] = include_bytes!("../assets/dns_dnssec_ds.bin");
+const DNSSEC_NSEC: &[u8; 348] = include_bytes!("../assets/dns_dnssec_nxdomain.bin");
+const DNSSEC_NSEC3: &[u8; 576] = include_bytes!("../assets/dns_dnssec_nsec3.bin");
 
 #[test]
 fn test_parse_long_request() {
@@ -109,7 +119,6 @@ fn test_parse_response() {
 
     assert_eq!(answers.len(), 1);
     assert_eq!(ip, real_ip);
-    assert_eq!(answers[0].preamble.rdlen, 4);
     assert_eq!(
         answers[0].preamble.name.to_string(),
         "hello.world.com.".to_string()
@@ -118,3 +127,264 @@ fn test_parse_response() {
     assert!(authority.is_empty());
     assert!(additional.is_empty());
 }
+
+#[test]
+fn test_parse_nxdomain() {
+    let DnsPacket {
+        header,
+        questions,
+        answers,
+        authority,
+        additional,
+    } = DnsPacket::try_from(&NXDOMAIN[..]).unwrap();
+
+    assert_eq!(header.flags.qr, QueryResponse::Response);
+    assert_eq!(header.flags.rcode, ResponseCode::NXDomain);
+    assert_eq!(header.authority, 1);
+
+    assert_eq!(questions.len(), 1);
+    assert_eq!(
+        questions[0].name.to_string(),
+        "nonexistent.example.com.".to_string()
+    );
+    assert!(answers.is_empty());
+    assert!(additional.is_empty());
+
+    assert_eq!(authority.len(), 1);
+    assert_eq!(authority[0].preamble.rrtype, Type::Soa);
+    assert_eq!(authority[0].preamble.ttl, 3600);
+    assert_eq!(
+        authority[0].preamble.name.to_string(),
+        "example.com.".to_string()
+    );
+    let RecordData::Soa {
+        mname,
+        rname,
+        serial,
+        refresh,
+        retry,
+        expire,
+        minimum,
+    } = &authority[0].data
+    else {
+        panic!("Expected SOA record")
+    };
+    assert_eq!(mname.to_string(), "ns.icann.org.".to_string());
+    assert_eq!(rname.to_string(), "noc.dns.icann.org.".to_string());
+    assert_eq!(*serial, 2024081462);
+    assert_eq!(*refresh, 7200);
+    assert_eq!(*retry, 3600);
+    assert_eq!(*expire, 1209600);
+    assert_eq!(*minimum, 3600);
+}
+
+#[test]
+fn test_parse_edns_request() {
+    let packet = DnsPacket::try_from(&EDNS_REQ[..]).unwrap();
+
+    assert_eq!(packet.header.additional, 1);
+    assert_eq!(packet.additional.len(), 1);
+    assert_eq!(packet.additional[0].preamble.rrtype, Type::Opt);
+
+    let edns = packet.edns().unwrap();
+    assert_eq!(edns.udp_payload_size, 1232);
+    assert_eq!(edns.extended_rcode, 0);
+    assert_eq!(edns.version, 0);
+    assert!(edns.dnssec_ok);
+    assert_eq!(edns.z, 0);
+    assert_eq!(edns.options.len(), 1);
+    assert_eq!(edns.options[0].code(), 10);
+    assert_eq!(packet.rcode(), ExtendedResponseCode::NoError);
+}
+
+#[test]
+fn test_parse_rrsig() {
+    let packet = DnsPacket::try_from(&DNSSEC_RES[..]).unwrap();
+
+    assert_eq!(packet.answers.len(), 3);
+    assert_eq!(packet.answers[2].preamble.rrtype, Type::Rrsig);
+    let RecordData::Rrsig(rrsig) = &packet.answers[2].data else {
+        panic!("Expected RRSIG record")
+    };
+    assert_eq!(rrsig.type_covered, Type::A);
+    assert_eq!(rrsig.algorithm, Algorithm::EcdsaP256Sha256);
+    assert_eq!(rrsig.labels, 3);
+    assert_eq!(rrsig.original_ttl, 3600);
+    assert_eq!(rrsig.expiration, 2082758400);
+    assert_eq!(rrsig.inception, 1767225600);
+    assert_eq!(rrsig.key_tag, 2017);
+    assert_eq!(rrsig.signer.to_string(), "example.com.".to_string());
+    assert_eq!(rrsig.signature.len(), 64);
+}
+
+#[test]
+fn test_parse_dnskey() {
+    let packet = DnsPacket::try_from(&DNSSEC_DNSKEY[..]).unwrap();
+
+    assert_eq!(packet.answers.len(), 3);
+    let RecordData::Dnskey(ksk) = &packet.answers[0].data else {
+        panic!("Expected DNSKEY record")
+    };
+    assert_eq!(ksk.flags, 257);
+    assert_eq!(ksk.protocol, 3);
+    assert_eq!(ksk.algorithm, Algorithm::EcdsaP256Sha256);
+    assert!(ksk.is_zone_key());
+    assert!(ksk.is_secure_entry_point());
+    assert_eq!(ksk.public_key.len(), 64);
+    assert_eq!(ksk.key_tag(), 14870);
+
+    let RecordData::Dnskey(zsk) = &packet.answers[1].data else {
+        panic!("Expected DNSKEY record")
+    };
+    assert!(!zsk.is_secure_entry_point());
+    assert_eq!(zsk.key_tag(), 2017);
+
+    let RecordData::Rrsig(rrsig) = &packet.answers[2].data else {
+        panic!("Expected RRSIG record")
+    };
+    assert_eq!(rrsig.type_covered, Type::Dnskey);
+    assert_eq!(rrsig.key_tag, ksk.key_tag());
+}
+
+#[test]
+fn test_parse_ds() {
+    let packet = DnsPacket::try_from(&DNSSEC_DS[..]).unwrap();
+
+    let RecordData::Ds(ds) = &packet.answers[0].data else {
+        panic!("Expected DS record")
+    };
+    assert_eq!(ds.key_tag, 14870);
+    assert_eq!(ds.algorithm, Algorithm::EcdsaP256Sha256);
+    assert_eq!(ds.digest_type, DigestType::Sha256);
+    assert_eq!(ds.digest.len(), 32);
+
+    let RecordData::Rrsig(rrsig) = &packet.answers[1].data else {
+        panic!("Expected RRSIG record")
+    };
+    assert_eq!(rrsig.signer.to_string(), "com.".to_string());
+    assert_eq!(rrsig.labels, 2);
+}
+
+#[test]
+fn test_parse_nsec() {
+    let packet = DnsPacket::try_from(&DNSSEC_NSEC[..]).unwrap();
+
+    assert_eq!(packet.header.flags.rcode, ResponseCode::NXDomain);
+    assert_eq!(packet.authority.len(), 4);
+    let RecordData::Nsec(nsec) = &packet.authority[2].data else {
+        panic!("Expected NSEC record")
+    };
+    assert_eq!(nsec.next.to_string(), "www.example.com.".to_string());
+    let types: Vec<_> = nsec.types.iter().collect();
+    assert_eq!(
+        types,
+        vec![Type::Ns, Type::Soa, Type::Rrsig, Type::Nsec, Type::Dnskey]
+    );
+    assert!(!nsec.types.contains(Type::A));
+}
+
+#[test]
+fn test_parse_nsec3() {
+    let packet = DnsPacket::try_from(&DNSSEC_NSEC3[..]).unwrap();
+
+    assert_eq!(packet.authority.len(), 6);
+    assert_eq!(packet.authority[2].preamble.rrtype, Type::Nsec3);
+    assert_eq!(
+        packet.authority[2].preamble.name.to_string(),
+        "mifdndt3nff3od53o7tla1hrff95jkuk.example.com.".to_string()
+    );
+    let RecordData::Nsec3(nsec3) = &packet.authority[2].data else {
+        panic!("Expected NSEC3 record")
+    };
+    assert_eq!(nsec3.hash_algorithm, 1);
+    assert!(!nsec3.is_opt_out());
+    assert_eq!(nsec3.iterations, 0);
+    assert!(nsec3.salt.is_empty());
+    assert_eq!(nsec3.next_hashed.len(), 20);
+    assert!(nsec3.types.contains(Type::A));
+    assert!(nsec3.types.contains(Type::Rrsig));
+    assert_eq!(nsec3.types.len(), 2);
+
+    let RecordData::Nsec3(apex) = &packet.authority[4].data else {
+        panic!("Expected NSEC3 record")
+    };
+    assert!(apex.types.contains(Type::Nsec3Param));
+}
+
+#[test]
+fn test_parse_options_trailing() {
+    let mut buff = RES.to_vec();
+    buff.extend([0, 0]);
+    assert!(DnsPacket::parse_with(&buff, &ParseOptions::lenient()).is_ok());
+    let err = DnsPacket::parse_with(&buff, &ParseOptions::strict()).unwrap_err();
+    assert!(matches!(err.error, ParseError::TrailingBytes(2)));
+    assert!(DnsPacket::parse_with(&RES[..], &ParseOptions::strict()).is_ok());
+}
+
+#[test]
+fn test_parse_options_max_records() {
+    let options = ParseOptions {
+        max_records: 4,
+        ..Default::default()
+    };
+    assert!(DnsPacket::parse_with(&DNSSEC_NSEC3[..], &ParseOptions::default()).is_ok());
+    let err = DnsPacket::parse_with(&DNSSEC_NSEC3[..], &options).unwrap_err();
+    assert!(matches!(err.error, ParseError::RecordCount(6)));
+    assert_eq!(err.section, Section::Header);
+}
+
+#[test]
+fn test_parse_options_forward_pointer() {
+    // Answer owner name pointing forward to the name in its own RDATA.
+    let mut buff = RES[..33].to_vec();
+    buff.extend([0xC0, 45, 0, 5, 0, 1, 0, 0, 1, 44, 0, 5]);
+    buff.extend(b"\x03www\x00");
+    buff[7] = 1;
+
+    let err = DnsPacket::parse_with(&buff, &ParseOptions::default()).unwrap_err();
+    assert!(matches!(err.error, ParseError::InvalidJump));
+    let packet = DnsPacket::parse_with(&buff, &ParseOptions::lenient()).unwrap();
+    assert_eq!(packet.answers[0].preamble.name.to_string(), "www.");
+}
+
+#[test]
+fn test_parse_any_label_octet() {
+    let mut buff = REQ.to_vec();
+    buff[13] = b'.';
+    buff[14] = 0xFF;
+    let packet = DnsPacket::try_from(&buff[..]).unwrap();
+    let name = &packet.questions[0].name;
+    assert_eq!(name.label_count(), 3);
+    assert_eq!(name.to_string(), "\\.\\255llo.world.com.");
+
+    // The labels are kept as they are, so the name is written back unchanged.
+    let text = name.to_string();
+    assert_eq!(Name::try_from(text.as_str()).unwrap(), *name);
+    assert_eq!(Vec::from(&packet), buff);
+}
+
+#[test]
+fn test_packet_error_location() {
+    // Pointer to itself in the owner name of the answer.
+    let mut buff = RES.to_vec();
+    buff[33] = 0xC0;
+    buff[34] = 33;
+    let err = DnsPacket::parse_with(&buff, &ParseOptions::default()).unwrap_err();
+    assert_eq!(err.section, Section::Answer);
+    assert_eq!(err.index, 0);
+    assert_eq!(err.offset, 33);
+    assert_eq!(&err.bytes[..2], &[0xC0, 33]);
+    assert!(matches!(err.error, ParseError::InvalidJump));
+    let partial = err.partial.as_ref().unwrap();
+    assert_eq!(partial.questions.len(), 1);
+    assert!(partial.answers.is_empty());
+    assert!(
+        err.to_string()
+            .starts_with("Invalid answer 0 at byte 33 [c0 21 00 01")
+    );
+
+    let err = DnsPacket::parse_with(&RES[..8], &ParseOptions::default()).unwrap_err();
+    assert_eq!(err.section, Section::Header);
+    assert!(err.partial.is_none());
+    assert!(matches!(ParseError::from(err), ParseError::HeaderLength(8)));
+}
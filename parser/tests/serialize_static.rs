@@ -0,0 +1,306 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use dominion_parser::DnsPacket;
+use dominion_parser::body::*;
+use dominion_parser::edns::{ClientSubnet, Edns};
+use dominion_parser::header::{ExtendedResponseCode, ResponseCode};
+
+const REQ: &[u8; 33] = include_bytes!("../assets/dns_request.bin");
+const RES: &[u8; 49] = include_bytes!("../assets/dns_response.bin");
+const NXDOMAIN: &[u8; 97] = include_bytes!("../assets/dns_nxdomain.bin");
+const EDNS_REQ: &[u8; 52] = include_bytes!("../assets/dns_request_edns.bin");
+
+const DNSSEC_RES: &[u8; 183] = include_bytes!("../assets/dns_dnssec_response.bin");
+const DNSSEC_DNSKEY: &[u8; 307] = include_bytes!("../assets/dns_dnssec_dnskey.bin");
+const DNSSEC_DS: &[u8; 187] = include_bytes!("../assets/dns_dnssec_ds.bin");
+const DNSSEC_NSEC: &[u8; 348] = include_bytes!("../assets/dns_dnssec_nxdomain.bin");
+const DNSSEC_NSEC3: &[u8; 576] = include_bytes!("../assets/dns_dnssec_nsec3.bin");
+const DNSSEC_RFC8080: &[u8; 168] = include_bytes!("../assets/dns_dnssec_rfc8080.bin");
+
+#[test]
+fn test_serialize_request() {
+    let packet = DnsPacket::try_from(&REQ[..]).unwrap();
+    let serialized = Vec::<u8>::from(&packet);
+    assert_eq!(&serialized[..], &REQ[..]);
+}
+
+#[test]
+fn test_serialize_compressed_response() {
+    let packet = DnsPacket::try_from(&RES[..]).unwrap();
+    let serialized = Vec::<u8>::from(&packet);
+    assert_eq!(&serialized[..], &RES[..]);
+}
+
+#[test]
+fn test_serialize_uncompressed_response() {
+    let packet = DnsPacket::try_from(&RES[..]).unwrap();
+    let mut serialized = Vec::new();
+    packet.serialize_uncompressed(&mut serialized);
+    assert_eq!(serialized.len(), RES.len() + 15);
+
+    let parsed = DnsPacket::try_from(&serialized[..]).unwrap();
+    assert_eq!(
+        parsed.answers[0].preamble.name.to_string(),
+        "hello.world.com.".to_string()
+    );
+}
+
+#[test]
+fn test_compression_round_trip() {
+    let mut packet = DnsPacket::try_from(&RES[..]).unwrap();
+    let name = packet.questions[0].name.clone();
+    let preamble = RecordPreamble {
+        name: name.clone(),
+        rrtype: Type::Mx,
+        class: Class::IN,
+        ttl: 300,
+    };
+    let exchange = "mail.world.com".try_into().unwrap();
+    let data = RecordData::Mx {
+        preference: 10,
+        exchange,
+    };
+    packet.answers.push(ResourceRecord { preamble, data });
+
+    let serialized = Vec::<u8>::from(&packet);
+    // owner <jump>, preference, mail <jump>
+    assert_eq!(serialized.len(), RES.len() + 2 + 10 + 2 + 5 + 2);
+
+    let parsed = DnsPacket::try_from(&serialized[..]).unwrap();
+    assert_eq!(parsed.answers.len(), 2);
+    assert_eq!(
+        parsed.answers[1].preamble.name.to_string(),
+        "hello.world.com.".to_string()
+    );
+    match &parsed.answers[1].data {
+        RecordData::Mx {
+            preference,
+            exchange,
+        } => {
+            assert_eq!(*preference, 10);
+            assert_eq!(exchange.to_string(), "mail.world.com.".to_string());
+        }
+        _ => panic!("Expected MX record"),
+    }
+}
+
+#[test]
+fn test_serialize_computed_lengths() {
+    let mut packet = DnsPacket::try_from(&REQ[..]).unwrap();
+    let preamble = RecordPreamble {
+        name: packet.questions[0].name.clone(),
+        rrtype: Type::Aaaa,
+        class: Class::IN,
+        ttl: 300,
+    };
+    let data = RecordData::Aaaa("::1".parse().unwrap());
+    packet.answers.push(ResourceRecord { preamble, data });
+    assert_eq!(packet.header.answers, 0);
+
+    let serialized = Vec::<u8>::from(&packet);
+    // ANCOUNT
+    assert_eq!(&serialized[6..8], &[0, 1]);
+    // RDLENGTH
+    assert_eq!(&serialized[43..45], &[0, 16]);
+
+    let parsed = DnsPacket::try_from(&serialized[..]).unwrap();
+    assert_eq!(parsed.header.answers, 1);
+    assert_eq!(parsed.answers.len(), 1);
+}
+
+#[test]
+fn test_serialize_nxdomain() {
+    let packet = DnsPacket::try_from(&NXDOMAIN[..]).unwrap();
+    let serialized = Vec::<u8>::from(&packet);
+    assert_eq!(&serialized[..], &NXDOMAIN[..]);
+}
+
+#[test]
+fn test_serialize_negative_answer() {
+    let mut packet = DnsPacket::try_from(&REQ[..]).unwrap();
+    let preamble = RecordPreamble {
+        name: "world.com".try_into().unwrap(),
+        rrtype: Type::Soa,
+        class: Class::IN,
+        ttl: 300,
+    };
+    let data = RecordData::Soa {
+        mname: "ns.world.com".try_into().unwrap(),
+        rname: "hostmaster.world.com".try_into().unwrap(),
+        serial: 1,
+        refresh: 7200,
+        retry: 3600,
+        expire: 1209600,
+        minimum: 300,
+    };
+    packet.authority.push(ResourceRecord { preamble, data });
+
+    let serialized = Vec::<u8>::from(&packet);
+    // owner <jump>, ns <jump>, hostmaster <jump>, 5 * 32 bits
+    assert_eq!(serialized.len(), REQ.len() + 2 + 10 + 5 + 13 + 20);
+
+    let parsed = DnsPacket::try_from(&serialized[..]).unwrap();
+    let RecordData::Soa { mname, rname, .. } = &parsed.authority[0].data else {
+        panic!("Expected SOA record")
+    };
+    assert_eq!(mname.to_string(), "ns.world.com.".to_string());
+    assert_eq!(rname.to_string(), "hostmaster.world.com.".to_string());
+}
+
+#[test]
+fn test_serialize_ptr() {
+    let ip = "192.0.2.1".parse().unwrap();
+    let mut packet = DnsPacket::try_from(&REQ[..]).unwrap();
+    packet.questions[0].name = dominion_parser::body::name::Name::from_reverse_ip(ip);
+    packet.questions[0].qtype = QType::Ptr;
+    let preamble = RecordPreamble {
+        name: packet.questions[0].name.clone(),
+        rrtype: Type::Ptr,
+        class: Class::IN,
+        ttl: 300,
+    };
+    let data = RecordData::Ptr("hello.world.com".try_into().unwrap());
+    packet.answers.push(ResourceRecord { preamble, data });
+
+    let serialized = Vec::<u8>::from(&packet);
+    let parsed = DnsPacket::try_from(&serialized[..]).unwrap();
+    assert_eq!(parsed.answers[0].preamble.name.to_reverse_ip(), Some(ip));
+    let RecordData::Ptr(name) = &parsed.answers[0].data else {
+        panic!("Expected PTR record")
+    };
+    assert_eq!(name.to_string(), "hello.world.com.".to_string());
+}
+
+#[test]
+fn test_serialize_srv() {
+    let mut packet = DnsPacket::try_from(&REQ[..]).unwrap();
+    packet.questions[0].name = "_sip._tcp.world.com".try_into().unwrap();
+    packet.questions[0].qtype = QType::Srv;
+    let preamble = RecordPreamble {
+        name: packet.questions[0].name.clone(),
+        rrtype: Type::Srv,
+        class: Class::IN,
+        ttl: 300,
+    };
+    let data = RecordData::Srv {
+        priority: 10,
+        weight: 60,
+        port: 5060,
+        target: "sip.world.com".try_into().unwrap(),
+    };
+    packet.answers.push(ResourceRecord { preamble, data });
+
+    let serialized = Vec::<u8>::from(&packet);
+    // The target is not compressed
+    assert_eq!(
+        &serialized[serialized.len() - 15..],
+        b"\x03sip\x05world\x03com\x00"
+    );
+
+    let parsed = DnsPacket::try_from(&serialized[..]).unwrap();
+    let RecordData::Srv {
+        priority,
+        weight,
+        port,
+        target,
+    } = &parsed.answers[0].data
+    else {
+        panic!("Expected SRV record")
+    };
+    assert_eq!((*priority, *weight, *port), (10, 60, 5060));
+    assert_eq!(target.to_string(), "sip.world.com.".to_string());
+    assert_eq!(
+        parsed.answers[0].preamble.name.to_string(),
+        "_sip._tcp.world.com.".to_string()
+    );
+}
+
+#[test]
+fn test_serialize_edns_request() {
+    let packet = DnsPacket::try_from(&EDNS_REQ[..]).unwrap();
+    let serialized = Vec::<u8>::from(&packet);
+    assert_eq!(&serialized[..], &EDNS_REQ[..]);
+}
+
+#[test]
+fn test_set_edns() {
+    let mut packet = DnsPacket::try_from(&EDNS_REQ[..]).unwrap();
+    let mut edns = Edns::new(4096);
+    edns.extended_rcode = 1;
+    packet.set_edns(edns);
+    assert_eq!(packet.additional.len(), 1);
+    assert_eq!(packet.rcode(), ExtendedResponseCode::BadVers);
+
+    let serialized = Vec::<u8>::from(&packet);
+    let parsed = DnsPacket::try_from(&serialized[..]).unwrap();
+    let edns = parsed.edns().unwrap();
+    assert_eq!(edns.udp_payload_size, 4096);
+    assert!(!edns.dnssec_ok);
+    assert!(edns.options.is_empty());
+    assert_eq!(parsed.rcode(), ExtendedResponseCode::BadVers);
+
+    let mut packet = DnsPacket::try_from(&REQ[..]).unwrap();
+    assert!(packet.edns().is_none());
+    packet.set_edns(Edns::new(1232));
+    assert_eq!(packet.additional.len(), 1);
+    assert!(packet.remove_edns().is_some());
+    assert!(packet.additional.is_empty());
+}
+
+#[test]
+fn test_unknown_rcode_out_of_header() {
+    let mut packet = DnsPacket::try_from(&REQ[..]).unwrap();
+    packet.header.flags.rcode = ResponseCode::Unknown(0x1C);
+    assert_eq!(packet.rcode(), ExtendedResponseCode::from(0x1C));
+
+    let serialized = Vec::<u8>::from(&packet);
+    let parsed = DnsPacket::try_from(&serialized[..]).unwrap();
+    assert_eq!(parsed.header.flags.rcode, ResponseCode::Unknown(12));
+    assert_eq!(parsed.edns().unwrap().extended_rcode, 1);
+    assert_eq!(parsed.rcode(), ExtendedResponseCode::from(0x1C));
+}
+
+#[test]
+fn test_client_subnet() {
+    let mut packet = DnsPacket::try_from(&EDNS_REQ[..]).unwrap();
+    let mut edns = packet.edns().unwrap();
+    edns.set_client_subnet(ClientSubnet::new(
+        "2001:db8:abcd:12::1".parse().unwrap(),
+        56,
+    ));
+    packet.set_edns(edns);
+
+    let serialized = Vec::<u8>::from(&packet);
+    // cookie option + family, prefixes and 7 bytes of address
+    assert_eq!(serialized.len(), EDNS_REQ.len() + 4 + 4 + 7);
+
+    let parsed = DnsPacket::try_from(&serialized[..]).unwrap();
+    let edns = parsed.edns().unwrap();
+    assert_eq!(edns.options.len(), 2);
+    let subnet = edns.client_subnet().unwrap();
+    assert_eq!(subnet.family(), 2);
+    assert_eq!(subnet.source_prefix, 56);
+    assert_eq!(subnet.scope_prefix, 0);
+    assert_eq!(
+        subnet.address,
+        "2001:db8:abcd::".parse::<std::net::IpAddr>().unwrap()
+    );
+}
+
+#[test]
+fn test_serialize_dnssec() {
+    for bytes in [
+        &DNSSEC_RES[..],
+        &DNSSEC_DNSKEY[..],
+        &DNSSEC_DS[..],
+        &DNSSEC_NSEC[..],
+        &DNSSEC_NSEC3[..],
+        &DNSSEC_RFC8080[..],
+    ] {
+        let packet = DnsPacket::try_from(bytes).unwrap();
+        let serialized = Vec::<u8>::from(&packet);
+        assert_eq!(&serialized[..], bytes);
+    }
+}